@@ -29,6 +29,11 @@ fn bench_rox_50k(c: &mut Criterion) {
         b.iter(|| RoxCodec::decode(black_box(&rox_data)))
     });
 
+    // Metadata and counts only, without deserializing the notes
+    group.bench_function("access", |b| {
+        b.iter(|| RoxCodec::access(black_box(&rox_data)).map(|view| view.note_count()))
+    });
+
     group.finish();
 }
 
@@ -1,21 +1,27 @@
 use rhythm_open_exchange::analysis::pattern_recognition::AnalysisResult as InternalAnalysisResult;
+use rhythm_open_exchange::analysis::{ChartStats, TimingComplexity};
+use rhythm_open_exchange::codec::{DecodeLimits, FormatCapabilities, OutputFormat};
 use rhythm_open_exchange::error::RoxError;
 use rhythm_open_exchange::model::{
-    Note as InternalNote, NoteType, RoxChart as InternalChart, TimingPoint as InternalTimingPoint,
+    Note as InternalNote, NoteCounts, NoteType, RoxChart as InternalChart,
+    TimingPoint as InternalTimingPoint, time,
 };
 use std::collections::HashMap;
 use std::sync::{Arc, RwLock};
 
 uniffi::setup_scaffolding!("rox_ffi");
 
+/// Error exposed to FFI. `code` is [`RoxError::error_code`], so callers can
+/// branch on the error kind without parsing `message`.
 #[derive(Debug, uniffi::Error)]
 pub enum FfiError {
-    Generic { message: String },
+    Generic { code: u32, message: String },
 }
 
 impl From<RoxError> for FfiError {
     fn from(e: RoxError) -> Self {
         Self::Generic {
+            code: e.error_code(),
             message: e.to_string(),
         }
     }
@@ -24,7 +30,66 @@ impl From<RoxError> for FfiError {
 impl std::fmt::Display for FfiError {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
-            FfiError::Generic { message } => write!(f, "{}", message),
+            FfiError::Generic { message, .. } => write!(f, "{}", message),
+        }
+    }
+}
+
+/// What an output format can store, exposed to FFI.
+#[derive(Debug, Clone, uniffi::Record)]
+pub struct FfiFormatCapabilities {
+    pub supports_holds: bool,
+    pub supports_bursts: bool,
+    pub supports_mines: bool,
+    pub supports_sv: bool,
+    pub supports_keysounds: bool,
+    pub supports_fakes: bool,
+    pub allowed_key_counts: Vec<u8>,
+}
+
+impl From<FormatCapabilities> for FfiFormatCapabilities {
+    fn from(c: FormatCapabilities) -> Self {
+        Self {
+            supports_holds: c.supports_holds,
+            supports_bursts: c.supports_bursts,
+            supports_mines: c.supports_mines,
+            supports_sv: c.supports_sv,
+            supports_keysounds: c.supports_keysounds,
+            supports_fakes: c.supports_fakes,
+            allowed_key_counts: c.allowed_key_counts.to_vec(),
+        }
+    }
+}
+
+/// Caps for decoding untrusted input, exposed to FFI. See [`DecodeLimits`].
+#[derive(Debug, Clone, uniffi::Record)]
+pub struct FfiDecodeLimits {
+    pub max_file_bytes: u64,
+    pub max_decompressed_bytes: u64,
+    pub max_notes: u64,
+    pub max_timing_points: u64,
+}
+
+impl From<DecodeLimits> for FfiDecodeLimits {
+    fn from(l: DecodeLimits) -> Self {
+        let wide = |v: usize| u64::try_from(v).unwrap_or(u64::MAX);
+        Self {
+            max_file_bytes: wide(l.max_file_bytes),
+            max_decompressed_bytes: wide(l.max_decompressed_bytes),
+            max_notes: wide(l.max_notes),
+            max_timing_points: wide(l.max_timing_points),
+        }
+    }
+}
+
+impl From<FfiDecodeLimits> for DecodeLimits {
+    fn from(l: FfiDecodeLimits) -> Self {
+        let narrow = |v: u64| usize::try_from(v).unwrap_or(usize::MAX);
+        Self {
+            max_file_bytes: narrow(l.max_file_bytes),
+            max_decompressed_bytes: narrow(l.max_decompressed_bytes),
+            max_notes: narrow(l.max_notes),
+            max_timing_points: narrow(l.max_timing_points),
         }
     }
 }
@@ -86,11 +151,121 @@ impl From<&InternalTimingPoint> for FfiTimingPoint {
     }
 }
 
+/// Note totals by type exposed to FFI.
+#[derive(Debug, Clone, Copy, uniffi::Record)]
+pub struct FfiNoteCounts {
+    pub taps: u64,
+    pub holds: u64,
+    pub bursts: u64,
+    pub mines: u64,
+    pub fakes: u64,
+}
+
+impl From<NoteCounts> for FfiNoteCounts {
+    fn from(c: NoteCounts) -> Self {
+        Self {
+            taps: c.taps as u64,
+            holds: c.holds as u64,
+            bursts: c.bursts as u64,
+            mines: c.mines as u64,
+            fakes: c.fakes as u64,
+        }
+    }
+}
+
+/// Chart summary exposed to FFI (one call instead of one per stat).
+#[derive(Debug, Clone, uniffi::Record)]
+pub struct FfiChartStats {
+    pub key_count: u8,
+    pub duration_us: i64,
+    pub note_count: u64,
+    pub nps: f64,
+    pub bpm_min: f64,
+    pub bpm_max: f64,
+    pub bpm_mode: f64,
+    pub short_hash: String,
+}
+
+impl From<ChartStats> for FfiChartStats {
+    fn from(s: ChartStats) -> Self {
+        Self {
+            key_count: s.key_count,
+            duration_us: s.duration_us,
+            note_count: s.note_count as u64,
+            nps: s.nps,
+            bpm_min: s.bpm_min,
+            bpm_max: s.bpm_max,
+            bpm_mode: s.bpm_mode,
+            short_hash: s.short_hash,
+        }
+    }
+}
+
+/// Timing point summary exposed to FFI.
+#[derive(Debug, Clone, uniffi::Record)]
+pub struct FfiTimingComplexity {
+    pub bpm_change_count: u64,
+    pub sv_change_count: u64,
+    pub has_stops: bool,
+    pub min_bpm: f64,
+    pub max_bpm: f64,
+    pub bpm_ratio: f64,
+}
+
+impl From<TimingComplexity> for FfiTimingComplexity {
+    fn from(t: TimingComplexity) -> Self {
+        Self {
+            bpm_change_count: t.bpm_change_count as u64,
+            sv_change_count: t.sv_change_count as u64,
+            has_stops: t.has_stops,
+            min_bpm: t.min_bpm,
+            max_bpm: t.max_bpm,
+            bpm_ratio: t.bpm_ratio,
+        }
+    }
+}
+
+/// NPS of a sliding window and where it is centered.
+#[derive(Debug, Clone, uniffi::Record)]
+pub struct FfiNpsWindow {
+    pub nps: f64,
+    pub center_time_us: i64,
+}
+
+impl From<(f64, i64)> for FfiNpsWindow {
+    fn from((nps, center_time_us): (f64, i64)) -> Self {
+        Self {
+            nps,
+            center_time_us,
+        }
+    }
+}
+
+/// A section with no notes to play.
+#[derive(Debug, Clone, uniffi::Record)]
+pub struct FfiRestSection {
+    pub start_time_us: i64,
+    pub end_time_us: i64,
+}
+
+impl From<(i64, i64)> for FfiRestSection {
+    fn from((start_time_us, end_time_us): (i64, i64)) -> Self {
+        Self {
+            start_time_us,
+            end_time_us,
+        }
+    }
+}
+
 #[derive(Debug, Clone, uniffi::Record)]
 pub struct FfiPatternEntry {
     pub time_start_us: i64,
     pub time_end_us: i64,
     pub pattern: String,
+    /// Coarse family (Stream, Jump, Jack, Chord, Mixed, Empty).
+    pub group: String,
+    /// Suggested display color as `0xRRGGBB`.
+    pub color: u32,
     pub note_count: u64,
 }
 
@@ -107,11 +282,16 @@ impl From<InternalAnalysisResult> for FfiAnalysisResult {
                 .timeline
                 .entries
                 .into_iter()
-                .map(|e| FfiPatternEntry {
-                    time_start_us: e.start_time,
-                    time_end_us: e.end_time,
-                    pattern: e.pattern_type.as_str().to_string(), // Convert enum to string for FFI
-                    note_count: e.note_count as u64,
+                .map(|e| {
+                    let (r, g, b) = e.pattern_type.color_hint();
+                    FfiPatternEntry {
+                        time_start_us: e.start_time,
+                        time_end_us: e.end_time,
+                        pattern: e.pattern_type.as_str().to_string(), // Convert enum to string for FFI
+                        group: e.pattern_type.group().as_str().to_string(),
+                        color: (u32::from(r) << 16) | (u32::from(g) << 8) | u32::from(b),
+                        note_count: e.note_count as u64,
+                    }
                 })
                 .collect(),
             key_count: res.key_count,
@@ -185,6 +365,10 @@ impl RoxChart {
         self.inner.read().unwrap().key_count()
     }
 
+    pub fn set_key_count(&self, key_count: u8) {
+        self.inner.write().unwrap().set_key_count(key_count);
+    }
+
     pub fn is_coop(&self) -> bool {
         self.inner.read().unwrap().metadata.is_coop
     }
@@ -204,13 +388,17 @@ impl RoxChart {
     // --- Stats ---
 
     pub fn duration_seconds(&self) -> f64 {
-        self.inner.read().unwrap().duration_us() as f64 / 1_000_000.0
+        time::us_to_s(self.inner.read().unwrap().duration_us())
     }
 
     pub fn note_count(&self) -> u64 {
         self.inner.read().unwrap().note_count() as u64
     }
 
+    pub fn note_counts(&self) -> FfiNoteCounts {
+        self.inner.read().unwrap().note_counts().into()
+    }
+
     pub fn hash(&self) -> String {
         // Requires 'analysis' feature
         use rhythm_open_exchange::analysis::RoxAnalysis;
@@ -232,6 +420,11 @@ impl RoxChart {
         self.inner.read().unwrap().short_hash()
     }
 
+    pub fn fuzzy_fingerprint(&self) -> u64 {
+        use rhythm_open_exchange::analysis::RoxAnalysis;
+        self.inner.read().unwrap().fuzzy_fingerprint()
+    }
+
     pub fn bpm_min(&self) -> f64 {
         use rhythm_open_exchange::analysis::RoxAnalysis;
         self.inner.read().unwrap().bpm_min()
@@ -262,6 +455,44 @@ impl RoxChart {
         self.inner.read().unwrap().lowest_nps(window_size_s)
     }
 
+    pub fn highest_nps_at(&self, window_size_s: f64) -> FfiNpsWindow {
+        use rhythm_open_exchange::analysis::RoxAnalysis;
+        self.inner
+            .read()
+            .unwrap()
+            .highest_nps_at(window_size_s)
+            .into()
+    }
+
+    pub fn lowest_nps_at(&self, window_size_s: f64) -> FfiNpsWindow {
+        use rhythm_open_exchange::analysis::RoxAnalysis;
+        self.inner
+            .read()
+            .unwrap()
+            .lowest_nps_at(window_size_s)
+            .into()
+    }
+
+    pub fn rest_sections(&self, min_gap_ms: u32) -> Vec<FfiRestSection> {
+        use rhythm_open_exchange::analysis::RoxAnalysis;
+        self.inner
+            .read()
+            .unwrap()
+            .rest_sections(min_gap_ms)
+            .into_iter()
+            .map(Into::into)
+            .collect()
+    }
+
+    pub fn suggest_difficulty_name(&self) -> String {
+        use rhythm_open_exchange::analysis::RoxAnalysis;
+        self.inner
+            .read()
+            .unwrap()
+            .suggest_difficulty_name()
+            .to_string()
+    }
+
     pub fn highest_drain_time(&self) -> f64 {
         use rhythm_open_exchange::analysis::RoxAnalysis;
         self.inner.read().unwrap().highest_drain_time()
@@ -284,6 +515,42 @@ impl RoxChart {
         self.inner.read().unwrap().lane_balance()
     }
 
+    pub fn dead_lanes(&self) -> Vec<u8> {
+        use rhythm_open_exchange::analysis::RoxAnalysis;
+        self.inner.read().unwrap().dead_lanes()
+    }
+
+    pub fn stats(&self) -> FfiChartStats {
+        use rhythm_open_exchange::analysis::RoxAnalysis;
+        self.inner.read().unwrap().stats().into()
+    }
+
+    pub fn timing_complexity(&self) -> FfiTimingComplexity {
+        use rhythm_open_exchange::analysis::RoxAnalysis;
+        self.inner.read().unwrap().timing_complexity().into()
+    }
+
+    pub fn off_grid_notes(&self, divisor: u32, tolerance_us: i64) -> Vec<u64> {
+        use rhythm_open_exchange::analysis::RoxAnalysis;
+        self.inner
+            .read()
+            .unwrap()
+            .off_grid_notes(divisor, tolerance_us)
+            .into_iter()
+            .map(|i| u64::try_from(i).unwrap_or(u64::MAX))
+            .collect()
+    }
+
+    pub fn beat_snap(&self, time_us: i64) -> u32 {
+        use rhythm_open_exchange::analysis::RoxAnalysis;
+        self.inner
+            .read()
+            .unwrap()
+            .beat_snap(time_us)
+            .divisor()
+            .unwrap_or(0)
+    }
+
     pub fn analyze_patterns(&self) -> FfiAnalysisResult {
         use rhythm_open_exchange::analysis::RoxAnalysis;
         self.inner.read().unwrap().pattern_analysis().into()
@@ -327,6 +594,26 @@ impl RoxChart {
         self.inner.write().unwrap().notes.clear();
     }
 
+    pub fn sample_manifest(&self) -> Vec<String> {
+        self.inner.read().unwrap().sample_manifest()
+    }
+
+    pub fn unused_samples(&self) -> Vec<String> {
+        self.inner.read().unwrap().unused_samples()
+    }
+
+    pub fn remove_mines(&self) {
+        self.inner.write().unwrap().remove_mines();
+    }
+
+    pub fn remove_notes_before(&self, time_us: i64) {
+        self.inner.write().unwrap().remove_notes_before(time_us);
+    }
+
+    pub fn remove_notes_after(&self, time_us: i64) {
+        self.inner.write().unwrap().remove_notes_after(time_us);
+    }
+
     pub fn get_notes(&self) -> Vec<FfiNote> {
         self.inner
             .read()
@@ -374,6 +661,10 @@ impl RoxChart {
     pub fn validate(&self) -> Result<(), FfiError> {
         self.inner.read().unwrap().validate().map_err(Into::into)
     }
+
+    pub fn is_valid(&self) -> bool {
+        self.inner.read().unwrap().is_valid()
+    }
 }
 
 // --- Global Functions (Codec) ---
@@ -394,6 +685,25 @@ pub fn decode_from_bytes(data: Vec<u8>) -> Result<Arc<RoxChart>, FfiError> {
     }))
 }
 
+/// Decode untrusted bytes, auto-detecting the format, within `limits`.
+#[uniffi::export]
+pub fn decode_from_bytes_with_limits(
+    data: Vec<u8>,
+    limits: FfiDecodeLimits,
+) -> Result<Arc<RoxChart>, FfiError> {
+    let chart = rhythm_open_exchange::codec::from_bytes_with_limits(&data, &limits.into())?;
+    Ok(Arc::new(RoxChart {
+        inner: RwLock::new(chart),
+    }))
+}
+
+/// The default decode limits, to adjust before calling
+/// [`decode_from_bytes_with_limits`].
+#[uniffi::export]
+pub fn default_decode_limits() -> FfiDecodeLimits {
+    DecodeLimits::default().into()
+}
+
 #[uniffi::export]
 pub fn decode_from_string(data: String) -> Result<Arc<RoxChart>, FfiError> {
     let chart = rhythm_open_exchange::codec::from_string(&data)?;
@@ -412,3 +722,91 @@ pub fn encode_chart(chart: &RoxChart, path: String) -> Result<(), FfiError> {
 pub fn auto_convert(input: String, output: String) -> Result<(), FfiError> {
     rhythm_open_exchange::codec::auto_convert(&input, &output).map_err(Into::into)
 }
+
+/// Extensions of the formats this build can decode.
+#[uniffi::export]
+pub fn supported_input_formats() -> Vec<String> {
+    rhythm_open_exchange::codec::supported_input_formats()
+        .iter()
+        .map(|&(name, _)| name.to_string())
+        .collect()
+}
+
+/// Extensions of the formats this build can encode.
+#[uniffi::export]
+pub fn supported_output_formats() -> Vec<String> {
+    rhythm_open_exchange::codec::supported_output_formats()
+        .iter()
+        .map(|&(name, _)| name.to_string())
+        .collect()
+}
+
+/// Capabilities of the output format for a file extension (e.g. "osu").
+#[uniffi::export]
+pub fn format_capabilities(extension: String) -> Result<FfiFormatCapabilities, FfiError> {
+    let format = OutputFormat::from_extension(&extension)?;
+    Ok(format.capabilities().into())
+}
+
+/// MIME type of the output format for a file extension (e.g. "osu").
+#[uniffi::export]
+pub fn format_mime_type(extension: String) -> Result<String, FfiError> {
+    let format = OutputFormat::from_extension(&extension)?;
+    Ok(format.mime_type().to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_supported_formats() {
+        assert!(supported_input_formats().contains(&"rox".to_string()));
+        assert!(supported_output_formats().contains(&"osu".to_string()));
+    }
+
+    #[test]
+    fn test_format_capabilities() {
+        assert!(!format_capabilities("osu".into()).unwrap().supports_mines);
+        assert!(format_capabilities("sm".into()).unwrap().supports_mines);
+        assert!(format_capabilities("mp3".into()).is_err());
+    }
+
+    #[test]
+    fn test_format_mime_type() {
+        assert_eq!(format_mime_type("json".into()).unwrap(), "application/json");
+        assert!(format_mime_type("mp3".into()).is_err());
+    }
+
+    #[test]
+    fn test_decode_with_limits() {
+        let data = b"#TITLE:Limits;\n#BPMS:0.000=120.000;\n#NOTES:\n     dance-single:\n     :\n     Beginner:\n     1:\n     0,0,0,0,0:\n1000\n0100\n0010\n0001\n;\n".to_vec();
+        let chart = decode_from_bytes_with_limits(data.clone(), default_decode_limits()).unwrap();
+        assert_eq!(chart.note_count(), 4);
+
+        let limits = FfiDecodeLimits {
+            max_notes: 3,
+            ..default_decode_limits()
+        };
+        match decode_from_bytes_with_limits(data, limits) {
+            Err(FfiError::Generic { code, .. }) => assert_eq!(code, 19), // LimitExceeded
+            Ok(_) => panic!("4 notes must exceed a limit of 3"),
+        }
+    }
+
+    #[test]
+    fn test_error_code_crosses_ffi() {
+        let dir = std::env::temp_dir();
+        let result = auto_convert(
+            dir.join("chart.mp3").to_string_lossy().into_owned(),
+            dir.join("chart.osu").to_string_lossy().into_owned(),
+        );
+        match result {
+            Err(FfiError::Generic { code, message }) => {
+                assert_eq!(code, 14); // UnsupportedFormat
+                assert!(message.contains(".mp3"));
+            }
+            Ok(()) => panic!("converting an .mp3 must fail"),
+        }
+    }
+}
@@ -2,13 +2,49 @@
 //!
 //! Provides JavaScript/TypeScript access to chart decoding, encoding, and conversion.
 
-use rhythm_open_exchange::codec::formats::{
-    FnfDecoder, FnfEncoder, OsuDecoder, OsuEncoder, QuaDecoder, QuaEncoder, SmDecoder, SmEncoder,
-};
-use rhythm_open_exchange::codec::{Decoder, Encoder, RoxCodec};
-use rhythm_open_exchange::model::RoxChart;
+use rhythm_open_exchange::analysis::RoxAnalysis;
+use rhythm_open_exchange::codec::{self, DecodeLimits};
+use rhythm_open_exchange::error::RoxError;
+use rhythm_open_exchange::model::{time, RoxChart};
 use wasm_bindgen::prelude::*;
 
+/// Error thrown by [`decode`], [`decode_with_limits`], [`encode`] and
+/// [`convert`].
+///
+/// `kind` is a stable `snake_case` name to branch on (`"invalid_format"`,
+/// `"unsupported_format"`, `"limit_exceeded"`, ...; see `RoxError::kind`),
+/// or `"invalid_limits"` when the limits object can't be read.
+#[wasm_bindgen]
+#[derive(Debug)]
+pub struct RoxJsError {
+    kind: String,
+    message: String,
+}
+
+#[wasm_bindgen]
+impl RoxJsError {
+    /// Error kind, e.g. `"invalid_format"`.
+    #[wasm_bindgen(getter)]
+    pub fn kind(&self) -> String {
+        self.kind.clone()
+    }
+
+    /// Human-readable message.
+    #[wasm_bindgen(getter)]
+    pub fn message(&self) -> String {
+        self.message.clone()
+    }
+}
+
+impl From<RoxError> for RoxJsError {
+    fn from(e: RoxError) -> Self {
+        Self {
+            kind: e.kind().to_string(),
+            message: e.to_string(),
+        }
+    }
+}
+
 /// A rhythm game chart (WASM wrapper).
 #[wasm_bindgen]
 pub struct Chart {
@@ -47,16 +83,23 @@ impl Chart {
         self.inner.key_count()
     }
 
-    /// Number of notes.
+    /// Number of judged notes, fakes excluded.
     #[wasm_bindgen(getter)]
     pub fn note_count(&self) -> usize {
-        self.inner.notes.len()
+        self.inner.note_count()
+    }
+
+    /// Note totals by type, as `{ taps, holds, bursts, mines, fakes }`.
+    #[wasm_bindgen(getter)]
+    pub fn note_counts(&self) -> Result<JsValue, JsError> {
+        serde_wasm_bindgen::to_value(&self.inner.note_counts())
+            .map_err(|e| JsError::new(&format!("Serialize error: {e}")))
     }
 
     /// Duration in seconds.
     #[wasm_bindgen(getter)]
     pub fn duration(&self) -> f64 {
-        self.inner.duration_us() as f64 / 1_000_000.0
+        time::us_to_s(self.inner.duration_us())
     }
 
     /// Whether this is a coop chart.
@@ -65,56 +108,264 @@ impl Chart {
         self.inner.metadata.is_coop
     }
 
+    /// Whether the chart passes validation.
+    #[wasm_bindgen(getter)]
+    pub fn is_valid(&self) -> bool {
+        self.inner.is_valid()
+    }
+
+    /// Lowest BPM.
+    #[wasm_bindgen(getter)]
+    pub fn bpm_min(&self) -> f64 {
+        self.inner.bpm_min()
+    }
+
+    /// Highest BPM.
+    #[wasm_bindgen(getter)]
+    pub fn bpm_max(&self) -> f64 {
+        self.inner.bpm_max()
+    }
+
+    /// Most common BPM, weighted by duration.
+    #[wasm_bindgen(getter)]
+    pub fn bpm_mode(&self) -> f64 {
+        self.inner.bpm_mode()
+    }
+
+    /// Average notes per second.
+    #[wasm_bindgen(getter)]
+    pub fn nps(&self) -> f64 {
+        self.inner.nps()
+    }
+
+    /// Columns that never get a note.
+    #[wasm_bindgen(getter)]
+    pub fn dead_lanes(&self) -> Vec<u8> {
+        self.inner.dead_lanes()
+    }
+
+    /// Summary stats as `{ key_count, duration_us, note_count, nps, bpm_min, bpm_max, bpm_mode, short_hash }`.
+    #[wasm_bindgen(getter)]
+    pub fn stats(&self) -> Result<JsValue, JsError> {
+        serde_wasm_bindgen::to_value(&self.inner.stats())
+            .map_err(|e| JsError::new(&format!("Serialize error: {e}")))
+    }
+
+    /// Timing summary as `{ bpm_change_count, sv_change_count, has_stops, min_bpm, max_bpm, bpm_ratio }`.
+    #[wasm_bindgen(getter)]
+    pub fn timing_complexity(&self) -> Result<JsValue, JsError> {
+        serde_wasm_bindgen::to_value(&self.inner.timing_complexity())
+            .map_err(|e| JsError::new(&format!("Serialize error: {e}")))
+    }
+
+    /// Indices of notes more than `tolerance_us` off the `1/divisor` beat grid.
+    pub fn off_grid_notes(&self, divisor: u32, tolerance_us: i64) -> Vec<usize> {
+        self.inner.off_grid_notes(divisor, tolerance_us)
+    }
+
+    /// Beat snap divisor of a note at `time_us` (4 for 1/4), or 0 if it is
+    /// on none of 1/1, 1/2, 1/3, 1/4, 1/6, 1/8, 1/12 and 1/16.
+    pub fn beat_snap(&self, time_us: i64) -> u32 {
+        self.inner.beat_snap(time_us).divisor().unwrap_or(0)
+    }
+
+    /// Densest window as `[nps, center_time_us]`.
+    pub fn highest_nps_at(&self, window_size_s: f64) -> Result<JsValue, JsError> {
+        serde_wasm_bindgen::to_value(&self.inner.highest_nps_at(window_size_s))
+            .map_err(|e| JsError::new(&format!("Serialize error: {e}")))
+    }
+
+    /// Sparsest window as `[nps, center_time_us]`.
+    pub fn lowest_nps_at(&self, window_size_s: f64) -> Result<JsValue, JsError> {
+        serde_wasm_bindgen::to_value(&self.inner.lowest_nps_at(window_size_s))
+            .map_err(|e| JsError::new(&format!("Serialize error: {e}")))
+    }
+
+    /// Sections of at least `min_gap_ms` with nothing to play, as
+    /// `[start_time_us, end_time_us]` pairs.
+    pub fn rest_sections(&self, min_gap_ms: u32) -> Result<JsValue, JsError> {
+        serde_wasm_bindgen::to_value(&self.inner.rest_sections(min_gap_ms))
+            .map_err(|e| JsError::new(&format!("Serialize error: {e}")))
+    }
+
+    /// Difficulty name suggested from note density ("Beginner" to "Expert").
+    pub fn suggest_difficulty_name(&self) -> String {
+        self.inner.suggest_difficulty_name().to_string()
+    }
+
     /// Short hash of the chart.
     #[wasm_bindgen(getter)]
     pub fn hash(&self) -> String {
         self.inner.short_hash()
     }
 
+    /// Offset-tolerant fingerprint for spotting duplicate charts (a hint,
+    /// not a proof), as a `BigInt`.
+    #[wasm_bindgen(getter)]
+    pub fn fuzzy_fingerprint(&self) -> u64 {
+        self.inner.fuzzy_fingerprint()
+    }
+
     /// Audio file path.
     #[wasm_bindgen(getter)]
     pub fn audio_file(&self) -> String {
         self.inner.metadata.audio_file.clone()
     }
+
+    /// Sample files referenced by at least one note.
+    #[wasm_bindgen(getter)]
+    pub fn sample_manifest(&self) -> Result<JsValue, JsError> {
+        serde_wasm_bindgen::to_value(&self.inner.sample_manifest())
+            .map_err(|e| JsError::new(&format!("Serialize error: {e}")))
+    }
+
+    /// Sample files no note references.
+    #[wasm_bindgen(getter)]
+    pub fn unused_samples(&self) -> Result<JsValue, JsError> {
+        serde_wasm_bindgen::to_value(&self.inner.unused_samples())
+            .map_err(|e| JsError::new(&format!("Serialize error: {e}")))
+    }
+
+    /// Encode this chart to another format (same formats as [`encode`]).
+    ///
+    /// Decode once with [`decode`], then call this for each export target
+    /// instead of running [`convert`] (which re-parses the input) per format.
+    pub fn reencode(&self, format: &str) -> Result<Vec<u8>, RoxJsError> {
+        encode(self, format)
+    }
+
+    /// Remove all mines from the chart.
+    pub fn remove_mines(&mut self) {
+        self.inner.remove_mines();
+    }
+
+    /// Remove notes starting before `time_us`; holds running at that time
+    /// are cut to start there.
+    pub fn remove_notes_before(&mut self, time_us: i64) {
+        self.inner.remove_notes_before(time_us);
+    }
+
+    /// Remove notes starting after `time_us`; holds running at that time
+    /// are cut to end there.
+    pub fn remove_notes_after(&mut self, time_us: i64) {
+        self.inner.remove_notes_after(time_us);
+    }
+}
+
+/// What an output format can store, as `{ supports_holds, supports_bursts,
+/// supports_mines, supports_sv, supports_keysounds, supports_fakes,
+/// allowed_key_counts }`.
+///
+/// Formats: see [`output_formats`]; "fnf" is accepted for "json".
+#[wasm_bindgen]
+pub fn format_capabilities(format: &str) -> Result<JsValue, JsError> {
+    let format = lookup(codec::supported_output_formats(), format)
+        .map_err(|e| JsError::new(&e.to_string()))?;
+    serde_wasm_bindgen::to_value(&format.capabilities())
+        .map_err(|e| JsError::new(&format!("Serialize error: {e}")))
+}
+
+/// File extension (without the dot) to save an output format as, e.g. for
+/// a download filename.
+///
+/// Formats: see [`output_formats`]; "fnf" is accepted for "json".
+#[wasm_bindgen]
+pub fn format_extension(format: &str) -> Result<String, JsError> {
+    let format = lookup(codec::supported_output_formats(), format)
+        .map_err(|e| JsError::new(&e.to_string()))?;
+    Ok(format.extension().to_string())
+}
+
+/// MIME type of an output format, e.g. for a `Blob` or `Content-Type`.
+///
+/// Formats: see [`output_formats`]; "fnf" is accepted for "json".
+#[wasm_bindgen]
+pub fn format_mime_type(format: &str) -> Result<String, JsError> {
+    let format = lookup(codec::supported_output_formats(), format)
+        .map_err(|e| JsError::new(&e.to_string()))?;
+    Ok(format.mime_type().to_string())
+}
+
+/// Look up a format name in `table`, accepting "fnf" for "json".
+fn lookup<F: Copy>(table: &[(&str, F)], format: &str) -> Result<F, RoxError> {
+    let name = match format.to_lowercase().as_str() {
+        "fnf" => "json".to_string(),
+        other => other.to_string(),
+    };
+    table
+        .iter()
+        .find(|(n, _)| *n == name)
+        .map(|&(_, f)| f)
+        .ok_or_else(|| RoxError::UnsupportedFormat(format.to_string()))
+}
+
+/// Names accepted by [`decode`], [`decode_with_limits`] and [`convert`].
+#[wasm_bindgen]
+pub fn input_formats() -> Vec<String> {
+    codec::supported_input_formats()
+        .iter()
+        .map(|&(name, _)| name.to_string())
+        .collect()
+}
+
+/// Names accepted by [`encode`], [`Chart::reencode`] and [`convert`].
+#[wasm_bindgen]
+pub fn output_formats() -> Vec<String> {
+    codec::supported_output_formats()
+        .iter()
+        .map(|&(name, _)| name.to_string())
+        .collect()
 }
 
 /// Decode chart bytes with the specified format.
 ///
-/// Formats: "rox", "osu", "sm", "qua", "json"/"fnf"
+/// Formats: see [`input_formats`]; "fnf" is accepted for "json".
+#[wasm_bindgen]
+pub fn decode(data: &[u8], format: &str) -> Result<Chart, RoxJsError> {
+    let format = lookup(codec::supported_input_formats(), format)?;
+    Ok(Chart {
+        inner: codec::decode_with_format(data, format)?,
+    })
+}
+
+/// Decode untrusted chart bytes with the specified format, within limits.
+///
+/// `limits` is an object with any of `max_file_bytes`,
+/// `max_decompressed_bytes`, `max_notes` and `max_timing_points`; missing
+/// fields keep their defaults.
+///
+/// Formats: see [`input_formats`]; "fnf" is accepted for "json".
 #[wasm_bindgen]
-pub fn decode(data: &[u8], format: &str) -> Result<Chart, JsError> {
-    let chart = match format.to_lowercase().as_str() {
-        "rox" => RoxCodec::decode(data),
-        "osu" => OsuDecoder::decode(data),
-        "sm" => SmDecoder::decode(data),
-        "qua" => QuaDecoder::decode(data),
-        "json" | "fnf" => FnfDecoder::decode(data),
-        _ => return Err(JsError::new(&format!("Unknown format: {format}"))),
+pub fn decode_with_limits(data: &[u8], format: &str, limits: JsValue) -> Result<Chart, RoxJsError> {
+    let limits: DecodeLimits = if limits.is_undefined() || limits.is_null() {
+        DecodeLimits::default()
+    } else {
+        serde_wasm_bindgen::from_value(limits).map_err(|e| RoxJsError {
+            kind: "invalid_limits".to_string(),
+            message: format!("Invalid limits: {e}"),
+        })?
     };
-    chart
-        .map(|inner| Chart { inner })
-        .map_err(|e| JsError::new(&format!("Decode error: {e}")))
+    let format = lookup(codec::supported_input_formats(), format)?;
+    Ok(Chart {
+        inner: codec::decode_with_limits(data, format, &limits)?,
+    })
 }
 
 /// Encode a chart to bytes with the specified format.
 ///
-/// Formats: "rox", "osu", "sm", "qua", "json"/"fnf"
+/// Formats: see [`output_formats`]; "fnf" is accepted for "json".
 #[wasm_bindgen]
-pub fn encode(chart: &Chart, format: &str) -> Result<Vec<u8>, JsError> {
-    let result = match format.to_lowercase().as_str() {
-        "rox" => RoxCodec::encode(&chart.inner),
-        "osu" => OsuEncoder::encode(&chart.inner),
-        "sm" => SmEncoder::encode(&chart.inner),
-        "qua" => QuaEncoder::encode(&chart.inner),
-        "json" | "fnf" => FnfEncoder::encode(&chart.inner),
-        _ => return Err(JsError::new(&format!("Unknown format: {format}"))),
-    };
-    result.map_err(|e| JsError::new(&format!("Encode error: {e}")))
+pub fn encode(chart: &Chart, format: &str) -> Result<Vec<u8>, RoxJsError> {
+    let format = lookup(codec::supported_output_formats(), format)?;
+    Ok(codec::encode_with_format(&chart.inner, format)?)
 }
 
 /// Convert chart bytes from one format to another.
+///
+/// To export the same input to several formats, [`decode`] it once and use
+/// [`Chart::reencode`] instead.
 #[wasm_bindgen]
-pub fn convert(data: &[u8], from_format: &str, to_format: &str) -> Result<Vec<u8>, JsError> {
+pub fn convert(data: &[u8], from_format: &str, to_format: &str) -> Result<Vec<u8>, RoxJsError> {
     let chart = decode(data, from_format)?;
     encode(&chart, to_format)
 }
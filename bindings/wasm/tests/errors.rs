@@ -0,0 +1,18 @@
+#![cfg(target_arch = "wasm32")]
+
+use rox_wasm::decode;
+use wasm_bindgen_test::wasm_bindgen_test;
+
+#[wasm_bindgen_test]
+fn decode_errors_keep_their_kind() {
+    let Err(err) = decode(b"definitely not a chart", "rox") else {
+        panic!("garbage decoded");
+    };
+    assert_eq!(err.kind(), "invalid_format");
+    assert!(!err.message().is_empty());
+
+    let Err(err) = decode(b"", "mp3") else {
+        panic!("unknown format accepted");
+    };
+    assert_eq!(err.kind(), "unsupported_format");
+}
@@ -0,0 +1,33 @@
+#![cfg(target_arch = "wasm32")]
+
+use rox_wasm::{decode, format_extension, format_mime_type, input_formats, output_formats};
+use wasm_bindgen_test::wasm_bindgen_test;
+
+const OSU_4K: &[u8] = include_bytes!("../../../assets/osu/mania_4k.osu");
+
+#[wasm_bindgen_test]
+fn every_listed_format_is_accepted() {
+    let inputs = input_formats();
+    assert!(inputs.iter().any(|f| f == "osu"));
+    assert!(inputs.iter().any(|f| f == "rox"));
+
+    let chart = decode(OSU_4K, "osu").expect("decode osu");
+    for format in output_formats() {
+        chart.reencode(&format).expect("listed output format");
+    }
+    let jrox = chart.reencode("jrox").expect("encode jrox");
+    assert_eq!(
+        decode(&jrox, "jrox").expect("decode jrox").note_count(),
+        chart.note_count()
+    );
+}
+
+#[wasm_bindgen_test]
+fn extension_and_mime_type() {
+    assert_eq!(format_extension("fnf").expect("fnf"), "json");
+    assert_eq!(format_mime_type("osu").expect("osu"), "text/plain");
+    assert_eq!(
+        format_mime_type("rox").expect("rox"),
+        "application/octet-stream"
+    );
+}
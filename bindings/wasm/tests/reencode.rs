@@ -0,0 +1,24 @@
+#![cfg(target_arch = "wasm32")]
+
+use rox_wasm::decode;
+use wasm_bindgen_test::wasm_bindgen_test;
+
+const OSU_4K: &[u8] = include_bytes!("../../../assets/osu/mania_4k.osu");
+
+#[wasm_bindgen_test]
+fn decode_once_reencode_to_two_formats() {
+    let chart = decode(OSU_4K, "osu").expect("decode osu");
+
+    let rox = chart.reencode("rox").expect("encode rox");
+    let sm = chart.reencode("sm").expect("encode sm");
+
+    let from_rox = decode(&rox, "rox").expect("decode rox");
+    assert_eq!(from_rox.note_count(), chart.note_count());
+    assert_eq!(from_rox.title(), chart.title());
+
+    let from_sm = decode(&sm, "sm").expect("decode sm");
+    assert_eq!(from_sm.key_count(), chart.key_count());
+    assert!(from_sm.note_count() > 0);
+
+    assert!(chart.reencode("nope").is_err());
+}
@@ -0,0 +1,16 @@
+#![cfg(target_arch = "wasm32")]
+
+use rox_wasm::decode;
+use wasm_bindgen_test::wasm_bindgen_test;
+
+const OSU_7K: &[u8] = include_bytes!("../../../assets/osu/mania_7k.osu");
+
+#[wasm_bindgen_test]
+fn bpm_and_nps_getters() {
+    let chart = decode(OSU_7K, "osu").expect("decode osu");
+
+    assert!(chart.bpm_min() > 0.0);
+    assert!(chart.bpm_max() >= chart.bpm_min());
+    assert!((chart.bpm_min()..=chart.bpm_max()).contains(&chart.bpm_mode()));
+    assert!(chart.nps() > 0.0);
+}
@@ -1,4 +1,6 @@
-use crate::model::RoxChart;
+use crate::model::{DisplayBpm, RoxChart};
+
+pub use crate::model::DISPLAY_BPM_TOLERANCE;
 use std::collections::HashMap;
 
 /// Calculate the minimum BPM in the chart.
@@ -73,3 +75,16 @@ pub fn bpm_mode(chart: &RoxChart) -> f64 {
         .map(|(k, _)| k.parse::<f64>().unwrap_or(0.0))
         .unwrap_or(0.0)
 }
+
+/// Display BPM of a chart.
+///
+/// [`Single`](DisplayBpm::Single) holds the BPM in effect the longest when
+/// every BPM point is within [`DISPLAY_BPM_TOLERANCE`] of the others, so
+/// rounding drift in a converted chart doesn't show as a range. Otherwise the
+/// lowest to highest BPM is returned. Points with a BPM of 0 or less are
+/// ignored, and a chart without BPM points is `Single(0.0)`. This is the
+/// value the SM encoder writes as `#DISPLAYBPM` when the chart declares none.
+#[must_use]
+pub fn display_bpm(chart: &RoxChart) -> DisplayBpm {
+    chart.computed_display_bpm()
+}
@@ -0,0 +1,63 @@
+use crate::model::Note;
+
+/// Default tolerance for treating notes as one chord (1ms).
+///
+/// Charts converted from millisecond-based formats can have chord notes a
+/// few microseconds apart after rounding.
+pub const DEFAULT_EPSILON_US: i64 = 1_000;
+
+/// Group notes into chords by start time.
+///
+/// Notes are sorted by time and a note joins the current chord if it starts
+/// within `epsilon_us` of the chord's first note. Anchoring on the first note
+/// (rather than the previous one) keeps very fast rolls from chaining into a
+/// single "chord". Use an epsilon of 0 for exact-time grouping.
+pub fn group_by_time<'a>(
+    notes: impl IntoIterator<Item = &'a Note>,
+    epsilon_us: i64,
+) -> Vec<Vec<&'a Note>> {
+    let mut sorted: Vec<&Note> = notes.into_iter().collect();
+    sorted.sort_by_key(|n| n.time_us);
+
+    let mut groups: Vec<Vec<&Note>> = Vec::new();
+    for note in sorted {
+        match groups.last_mut() {
+            Some(group) if note.time_us - group[0].time_us <= epsilon_us => group.push(note),
+            _ => groups.push(vec![note]),
+        }
+    }
+
+    groups
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_group_within_epsilon() {
+        let notes = vec![
+            Note::tap(1_000_000, 0),
+            Note::tap(1_000_500, 1), // 500µs later, same chord
+            Note::tap(1_200_000, 2),
+        ];
+
+        let groups = group_by_time(&notes, DEFAULT_EPSILON_US);
+        assert_eq!(groups.len(), 2);
+        assert_eq!(groups[0].len(), 2);
+        assert_eq!(groups[1].len(), 1);
+
+        // Exact grouping keeps them apart
+        assert_eq!(group_by_time(&notes, 0).len(), 3);
+    }
+
+    #[test]
+    fn test_group_does_not_chain() {
+        // Each note is within 1ms of the previous, but not of the first
+        let notes = vec![Note::tap(0, 0), Note::tap(800, 1), Note::tap(1_600, 2)];
+
+        let groups = group_by_time(&notes, DEFAULT_EPSILON_US);
+        assert_eq!(groups.len(), 2);
+        assert_eq!(groups[0].len(), 2);
+    }
+}
@@ -0,0 +1,76 @@
+use crate::model::RoxChart;
+
+/// Window used to measure sustained density (seconds).
+///
+/// Long enough that a single burst doesn't decide the tier on its own.
+const SUSTAINED_WINDOW_S: f64 = 4.0;
+
+/// Upper bounds of 4K-equivalent sustained NPS for each name, easiest first.
+/// Anything denser is "Expert".
+const TIERS: &[(f64, &str)] = &[
+    (2.0, "Beginner"),
+    (4.0, "Easy"),
+    (7.0, "Normal"),
+    (11.0, "Hard"),
+    (16.0, "Insane"),
+];
+
+/// Suggest a difficulty name from note density.
+///
+/// Uses the densest 4-second window rather than the average NPS, so breaks
+/// don't drag a hard chart down a tier. Density is scaled to a 4K equivalent
+/// (`nps * sqrt(4 / key_count)`) since more columns spread the same
+/// difficulty over more notes. This is only a rough default for formats that
+/// carry no difficulty name, not a rating.
+#[must_use]
+pub fn suggest_difficulty_name(chart: &RoxChart) -> &'static str {
+    let key_count = f64::from(chart.key_count().max(1));
+    let nps = super::nps::highest_nps(chart, SUSTAINED_WINDOW_S) * (4.0 / key_count).sqrt();
+
+    TIERS
+        .iter()
+        .find(|&&(max_nps, _)| nps < max_nps)
+        .map_or("Expert", |&(_, name)| name)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model::{Note, TimingPoint};
+
+    #[test]
+    fn test_dense_stream_is_high_tier() {
+        // 300 BPM 1/4 stream: 20 notes per second for 30 seconds
+        let mut chart = RoxChart::new(4);
+        chart.timing_points.push(TimingPoint::bpm(0, 300.0));
+        for i in 0..600 {
+            chart.notes.push(Note::tap(i * 50_000, (i % 4) as u8));
+        }
+
+        assert_eq!(suggest_difficulty_name(&chart), "Expert");
+    }
+
+    #[test]
+    fn test_sparse_chart_is_beginner() {
+        assert_eq!(suggest_difficulty_name(&RoxChart::new(4)), "Beginner");
+
+        let mut chart = RoxChart::new(4);
+        for i in 0..30 {
+            chart.notes.push(Note::tap(i * 2_000_000, (i % 4) as u8));
+        }
+        assert_eq!(suggest_difficulty_name(&chart), "Beginner");
+    }
+
+    #[test]
+    fn test_key_count_scaling() {
+        // 8 NPS is "Hard" on 4K but only "Normal" on 7K
+        let mut chart = RoxChart::new(4);
+        for i in 0..80 {
+            chart.notes.push(Note::tap(i * 125_000, (i % 4) as u8));
+        }
+        assert_eq!(suggest_difficulty_name(&chart), "Hard");
+
+        chart.metadata.key_count = 7;
+        assert_eq!(suggest_difficulty_name(&chart), "Normal");
+    }
+}
@@ -17,3 +17,73 @@ pub fn timings_hash(chart: &RoxChart) -> String {
     let encoded = rkyv::to_bytes::<rkyv::rancor::Error>(&chart.timing_points).unwrap_or_default();
     blake3::hash(&encoded).to_hex().to_string()
 }
+
+/// Grid the gaps between notes are rounded to by [`fuzzy_fingerprint`].
+const FINGERPRINT_GRID_US: i64 = 5_000;
+
+/// Fingerprint of a chart's rhythm and column pattern that survives a global
+/// offset.
+///
+/// Only the gaps between consecutive notes (rounded to a 5ms grid) and the
+/// columns are hashed, so two rips of the same chart shifted by a few
+/// milliseconds collide, while [`notes_hash`] tells them apart. Mines,
+/// fakes, hold lengths, hitsounds and timing points are ignored.
+///
+/// This is a similarity hint for deduplication, not a proof: a gap close to
+/// a grid boundary can round differently in two rips, and a rate change or
+/// a mirrored chart gives another fingerprint.
+#[must_use]
+pub fn fuzzy_fingerprint(chart: &RoxChart) -> u64 {
+    let mut notes: Vec<(i64, u8)> = chart
+        .notes
+        .iter()
+        .filter(|n| !n.is_mine() && !n.fake)
+        .map(|n| (n.time_us, n.column))
+        .collect();
+    notes.sort_unstable();
+
+    let mut hasher = blake3::Hasher::new();
+    hasher.update(&[chart.key_count()]);
+    let mut previous = notes.first().map_or(0, |&(time_us, _)| time_us);
+    for (time_us, column) in notes {
+        let gap = (time_us - previous + FINGERPRINT_GRID_US / 2).div_euclid(FINGERPRINT_GRID_US);
+        previous = time_us;
+        hasher.update(&gap.to_le_bytes());
+        hasher.update(&[column]);
+    }
+
+    let digest = hasher.finalize();
+    let mut bytes = [0; 8];
+    bytes.copy_from_slice(&digest.as_bytes()[..8]);
+    u64::from_le_bytes(bytes)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model::Note;
+
+    fn chart(offset_us: i64) -> RoxChart {
+        let mut chart = RoxChart::new(4);
+        for (i, column) in [0, 1, 2, 3, 2, 1].into_iter().enumerate() {
+            let i = i64::try_from(i).unwrap();
+            chart.notes.push(Note::tap(offset_us + i * 125_000, column));
+        }
+        chart
+    }
+
+    #[test]
+    fn test_fuzzy_fingerprint() {
+        let base = fuzzy_fingerprint(&chart(0));
+        assert_eq!(fuzzy_fingerprint(&chart(2_000)), base);
+        assert_eq!(fuzzy_fingerprint(&chart(-1_000_000)), base);
+
+        let mut different = chart(0);
+        different.notes[3].column = 0;
+        assert_ne!(fuzzy_fingerprint(&different), base);
+
+        let mut faster = chart(0);
+        faster.notes[5].time_us -= 60_000;
+        assert_ne!(fuzzy_fingerprint(&faster), base);
+    }
+}
@@ -1,14 +1,29 @@
 pub mod bpm;
+pub mod chords;
+pub mod difficulty;
 pub mod hash;
 pub mod nps;
 pub mod pattern;
 pub mod pattern_recognition;
-
-pub use bpm::{bpm_max, bpm_min, bpm_mode};
-pub use hash::{hash, notes_hash, timings_hash};
-pub use nps::{density, highest_drain_time, highest_nps, lowest_nps, nps};
-pub use pattern::{lane_balance, polyphony};
+pub mod rest;
+pub mod snap;
+pub mod stats;
+pub mod timing;
+
+pub use crate::model::DisplayBpm;
+pub use bpm::{DISPLAY_BPM_TOLERANCE, bpm_max, bpm_min, bpm_mode, display_bpm};
+pub use difficulty::suggest_difficulty_name;
+pub use hash::{fuzzy_fingerprint, hash, notes_hash, timings_hash};
+pub use nps::{
+    NpsPercentiles, density, highest_drain_time, highest_nps, highest_nps_at, lowest_nps,
+    lowest_nps_at, nps, nps_percentiles,
+};
+pub use pattern::{dead_lanes, hand_balance, lane_balance, left_right_balance, polyphony};
 pub use pattern_recognition::analyze as pattern_analysis;
+pub use rest::rest_sections;
+pub use snap::{BEAT_SNAP_TOLERANCE_US, BeatSnap, beat_snap, off_grid_notes};
+pub use stats::{ChartStats, stats};
+pub use timing::{TimingComplexity, timing_complexity};
 
 use crate::model::RoxChart;
 use std::collections::HashMap;
@@ -18,22 +33,37 @@ pub trait RoxAnalysis {
     fn bpm_min(&self) -> f64;
     fn bpm_max(&self) -> f64;
     fn bpm_mode(&self) -> f64;
+    fn display_bpm(&self) -> DisplayBpm;
 
     fn nps(&self) -> f64;
     fn density(&self, segments: usize) -> Vec<f64>;
     fn highest_nps(&self, window_size_s: f64) -> f64;
     fn lowest_nps(&self, window_size_s: f64) -> f64;
+    fn highest_nps_at(&self, window_size_s: f64) -> (f64, i64);
+    fn lowest_nps_at(&self, window_size_s: f64) -> (f64, i64);
+    fn nps_percentiles(&self, window_ms: u32) -> NpsPercentiles;
     fn highest_drain_time(&self) -> f64;
+    fn rest_sections(&self, min_gap_ms: u32) -> Vec<(i64, i64)>;
+    fn suggest_difficulty_name(&self) -> &'static str;
 
     fn polyphony(&self) -> HashMap<u32, u32>;
     fn lane_balance(&self) -> Vec<u32>;
+    fn dead_lanes(&self) -> Vec<u8>;
+    fn hand_balance(&self) -> f64;
+    fn left_right_balance(&self) -> (f64, f64);
 
     fn hash(&self) -> String;
     fn notes_hash(&self) -> String;
     fn timings_hash(&self) -> String;
     fn short_hash(&self) -> String;
+    fn fuzzy_fingerprint(&self) -> u64;
 
     fn pattern_analysis(&self) -> pattern_recognition::AnalysisResult;
+
+    fn stats(&self) -> ChartStats;
+    fn timing_complexity(&self) -> TimingComplexity;
+    fn off_grid_notes(&self, divisor: u32, tolerance_us: i64) -> Vec<usize>;
+    fn beat_snap(&self, time_us: i64) -> BeatSnap;
 }
 
 impl RoxAnalysis for RoxChart {
@@ -46,6 +76,9 @@ impl RoxAnalysis for RoxChart {
     fn bpm_mode(&self) -> f64 {
         bpm::bpm_mode(self)
     }
+    fn display_bpm(&self) -> DisplayBpm {
+        bpm::display_bpm(self)
+    }
 
     fn nps(&self) -> f64 {
         nps::nps(self)
@@ -59,9 +92,24 @@ impl RoxAnalysis for RoxChart {
     fn lowest_nps(&self, window_size_s: f64) -> f64 {
         nps::lowest_nps(self, window_size_s)
     }
+    fn highest_nps_at(&self, window_size_s: f64) -> (f64, i64) {
+        nps::highest_nps_at(self, window_size_s)
+    }
+    fn lowest_nps_at(&self, window_size_s: f64) -> (f64, i64) {
+        nps::lowest_nps_at(self, window_size_s)
+    }
+    fn nps_percentiles(&self, window_ms: u32) -> NpsPercentiles {
+        nps::nps_percentiles(self, window_ms)
+    }
     fn highest_drain_time(&self) -> f64 {
         nps::highest_drain_time(self)
     }
+    fn rest_sections(&self, min_gap_ms: u32) -> Vec<(i64, i64)> {
+        rest::rest_sections(self, min_gap_ms)
+    }
+    fn suggest_difficulty_name(&self) -> &'static str {
+        difficulty::suggest_difficulty_name(self)
+    }
 
     fn polyphony(&self) -> HashMap<u32, u32> {
         pattern::polyphony(self)
@@ -69,6 +117,15 @@ impl RoxAnalysis for RoxChart {
     fn lane_balance(&self) -> Vec<u32> {
         pattern::lane_balance(self)
     }
+    fn dead_lanes(&self) -> Vec<u8> {
+        pattern::dead_lanes(self)
+    }
+    fn hand_balance(&self) -> f64 {
+        pattern::hand_balance(self)
+    }
+    fn left_right_balance(&self) -> (f64, f64) {
+        pattern::left_right_balance(self)
+    }
 
     fn hash(&self) -> String {
         hash::hash(self)
@@ -79,6 +136,9 @@ impl RoxAnalysis for RoxChart {
     fn timings_hash(&self) -> String {
         hash::timings_hash(self)
     }
+    fn fuzzy_fingerprint(&self) -> u64 {
+        hash::fuzzy_fingerprint(self)
+    }
     fn short_hash(&self) -> String {
         let h = self.hash();
         if h.len() >= 16 {
@@ -91,6 +151,19 @@ impl RoxAnalysis for RoxChart {
     fn pattern_analysis(&self) -> pattern_recognition::AnalysisResult {
         pattern_recognition::analyze(self)
     }
+
+    fn stats(&self) -> ChartStats {
+        stats::stats(self)
+    }
+    fn timing_complexity(&self) -> TimingComplexity {
+        timing::timing_complexity(self)
+    }
+    fn off_grid_notes(&self, divisor: u32, tolerance_us: i64) -> Vec<usize> {
+        snap::off_grid_notes(self, divisor, tolerance_us)
+    }
+    fn beat_snap(&self, time_us: i64) -> BeatSnap {
+        snap::beat_snap(self, time_us)
+    }
 }
 
 #[cfg(test)]
@@ -120,6 +193,24 @@ mod tests {
         // 20-30s: 100bpm (10s)
         // Total 100bpm: 20s. Total 200bpm: 10s. Mode should be 100.
         assert_eq!(chart.bpm_mode(), 100.0);
+        assert_eq!(chart.display_bpm(), DisplayBpm::Range(100.0, 200.0));
+    }
+
+    #[test]
+    fn test_display_bpm_single() {
+        let mut chart = RoxChart::new(4);
+        assert_eq!(chart.display_bpm(), DisplayBpm::Single(0.0));
+
+        chart.timing_points.push(TimingPoint::bpm(0, 180.0));
+        // Drift from a converted chart stays a single BPM
+        chart
+            .timing_points
+            .push(TimingPoint::bpm(15_000_000, 180.2));
+        chart.notes.push(Note::tap(20_000_000, 0));
+        assert_eq!(chart.display_bpm(), DisplayBpm::Single(180.0));
+
+        chart.notes.clear();
+        assert_eq!(chart.display_bpm(), DisplayBpm::Single(180.0));
     }
 
     #[test]
@@ -169,6 +260,76 @@ mod tests {
         assert_eq!(peak, 10.0);
     }
 
+    #[test]
+    fn test_highest_nps_at() {
+        let mut chart = RoxChart::new(4);
+        // One note per second for 20s
+        for i in 0..=20 {
+            chart.notes.push(Note::tap(i * 1_000_000, 0));
+        }
+        // Burst of 10 notes at 10s
+        for i in 0..10 {
+            chart.notes.push(Note::tap(10_000_000 + i * 50_000, 1));
+        }
+
+        let (peak, center) = chart.highest_nps_at(1.0);
+        assert_eq!(peak, 11.0);
+        assert!(
+            (10_000_000..=10_450_000).contains(&center),
+            "Peak located at {center}µs"
+        );
+        assert_eq!(chart.highest_nps(1.0), peak);
+    }
+
+    #[test]
+    fn test_lowest_nps_at() {
+        let mut chart = RoxChart::new(4);
+        chart.notes.push(Note::tap(0, 0));
+        chart.notes.push(Note::tap(1_000_000, 0));
+        // 9s gap
+        chart.notes.push(Note::tap(10_000_000, 0));
+
+        assert_eq!(chart.lowest_nps_at(2.0), (0.0, 5_500_000));
+    }
+
+    #[test]
+    fn test_nps_at_all_fake() {
+        let mut chart = RoxChart::new(4);
+        for i in 0..4 {
+            let mut note = Note::tap(i * 250_000, 0);
+            note.fake = true;
+            chart.notes.push(note);
+        }
+
+        assert_eq!(chart.highest_nps_at(1.0), (0.0, 0));
+        assert_eq!(chart.lowest_nps_at(1.0), (0.0, 0));
+    }
+
+    #[test]
+    fn test_nps_percentiles() {
+        let mut chart = RoxChart::new(4);
+        // 2 NPS for 20s, with a 1s burst of 16 notes in the middle
+        for i in 0..40 {
+            chart.notes.push(Note::tap(i * 500_000, 0));
+        }
+        for i in 0..16 {
+            chart.notes.push(Note::tap(10_000_000 + i * 62_500, 1));
+        }
+        chart.notes.sort_by_key(|n| n.time_us);
+
+        let p = chart.nps_percentiles(1_000);
+        assert_eq!(p.p50, 2.0);
+        assert!(p.p99 > p.p50);
+        assert!(p.p90 <= p.p95 && p.p95 <= p.p99 && p.p99 <= p.max);
+        assert_eq!(p.max, chart.highest_nps(1.0));
+
+        assert_eq!(chart.nps_percentiles(0), NpsPercentiles::default());
+        assert_eq!(
+            RoxChart::new(4).nps_percentiles(1_000),
+            NpsPercentiles::default()
+        );
+    }
+
     #[test]
     fn test_lowest_nps() {
         let mut chart = RoxChart::new(4);
@@ -215,17 +376,18 @@ mod tests {
         chart.notes.push(Note::tap(2_000_000, 1));
         chart.notes.push(Note::hold(3_000_000, 500_000, 2)); // ends at 3.5s
 
-        // Known hash values for this specific chart configuration
+        // Known hash values for this specific chart configuration. They cover
+        // the archived layout, so they change with every ROX_VERSION bump.
         let hash = chart.hash();
         assert_eq!(
-            hash, "2c0964de10711f16489a3db2796afe320ace9cbe2e28a142e226e28620d1e8d4",
+            hash, "c21c4bb968dd816021592c47e192fa8f660b29c35c5cda58432db2cc09dcc054",
             "Hash verification failed"
         );
         assert_eq!(
             chart.notes_hash(),
-            "dcdccda1c57c13043c67373c71bc17769a5d77e3c7eb2f258549125b87162ea5"
+            "b7cf95d3e05ad528f828d383cc07892134d2ad0025135d43b1fe09d382bc55ba"
         );
-        assert_eq!(chart.short_hash(), "2c0964de10711f16");
+        assert_eq!(chart.short_hash(), "c21c4bb968dd8160");
     }
 
     #[test]
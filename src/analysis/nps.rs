@@ -1,13 +1,30 @@
+use serde::Serialize;
+
 use crate::model::Note;
 use crate::model::RoxChart;
 
+/// Spacing of the windows sampled by [`nps_percentiles`].
+const PERCENTILE_STEP_US: i64 = 100_000;
+
+/// Distribution of NPS over sliding windows, from [`nps_percentiles`].
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize)]
+pub struct NpsPercentiles {
+    /// Median window NPS.
+    pub p50: f64,
+    pub p90: f64,
+    pub p95: f64,
+    pub p99: f64,
+    /// Densest window, as [`highest_nps`].
+    pub max: f64,
+}
+
 /// Calculate the average Notes Per Second (NPS).
 pub fn nps(chart: &RoxChart) -> f64 {
     let duration_s = chart.duration_us() as f64 / 1_000_000.0;
     if duration_s <= 0.0 {
         return 0.0;
     }
-    chart.note_count() as f64 / duration_s
+    chart.notes.iter().filter(|n| !n.fake).count() as f64 / duration_s
 }
 
 /// Calculate NPS density divided into `segments`.
@@ -25,7 +42,7 @@ pub fn density(chart: &RoxChart, segments: usize) -> Vec<f64> {
     let segment_duration_us = duration_us as f64 / segments as f64;
     let mut segment_counts = vec![0; segments];
 
-    for note in &chart.notes {
+    for note in chart.notes.iter().filter(|n| !n.fake) {
         // Find which segment this note belongs to
         // We use note.time_us.
         // Clamping to ensure it falls within range 0..segments-1
@@ -50,15 +67,28 @@ pub fn density(chart: &RoxChart, segments: usize) -> Vec<f64> {
 /// Calculate the highest peak NPS using a sliding window.
 /// `window_size_s` is in seconds (e.g. 1.0).
 pub fn highest_nps(chart: &RoxChart, window_size_s: f64) -> f64 {
+    highest_nps_at(chart, window_size_s).0
+}
+
+/// Like [`highest_nps`], but also locate the densest window.
+///
+/// Returns `(nps, center_time_us)`, where the center is the midpoint between
+/// the first and last note of the first densest window.
+#[must_use]
+pub fn highest_nps_at(chart: &RoxChart, window_size_s: f64) -> (f64, i64) {
     let window_us = (window_size_s * 1_000_000.0) as i64;
     if window_us <= 0 || chart.notes.is_empty() {
-        return 0.0;
+        return (0.0, 0);
     }
 
-    let mut notes: Vec<&Note> = chart.notes.iter().collect();
+    let mut notes: Vec<&Note> = chart.notes.iter().filter(|n| !n.fake).collect();
     notes.sort_by_key(|n| n.time_us);
+    if notes.is_empty() {
+        return (0.0, 0);
+    }
 
     let mut max_notes_in_window = 0;
+    let mut center_time_us = 0;
     let mut left = 0;
 
     for right in 0..notes.len() {
@@ -74,22 +104,36 @@ pub fn highest_nps(chart: &RoxChart, window_size_s: f64) -> f64 {
         let count = right - left + 1;
         if count > max_notes_in_window {
             max_notes_in_window = count;
+            center_time_us = midpoint(notes[left].time_us, window_end);
         }
     }
 
-    max_notes_in_window as f64 / window_size_s
+    (max_notes_in_window as f64 / window_size_s, center_time_us)
 }
 
 /// Calculate the lowest NPS using a sliding window, starting from the first note.
 /// `window_size_s` is in seconds (e.g. 1.0).
 pub fn lowest_nps(chart: &RoxChart, window_size_s: f64) -> f64 {
+    lowest_nps_at(chart, window_size_s).0
+}
+
+/// Like [`lowest_nps`], but also locate the sparsest window.
+///
+/// Returns `(nps, center_time_us)`. For a gap longer than the window the
+/// center is the middle of that gap, otherwise the midpoint between the first
+/// and last note of the first sparsest window.
+#[must_use]
+pub fn lowest_nps_at(chart: &RoxChart, window_size_s: f64) -> (f64, i64) {
     let window_us = (window_size_s * 1_000_000.0) as i64;
     if window_us <= 0 || chart.notes.is_empty() {
-        return 0.0;
+        return (0.0, 0);
     }
 
-    let mut notes: Vec<&Note> = chart.notes.iter().collect();
+    let mut notes: Vec<&Note> = chart.notes.iter().filter(|n| !n.fake).collect();
     notes.sort_by_key(|n| n.time_us);
+    if notes.is_empty() {
+        return (0.0, 0);
+    }
 
     let first_note_time = notes[0].time_us;
 
@@ -97,11 +141,12 @@ pub fn lowest_nps(chart: &RoxChart, window_size_s: f64) -> f64 {
     for window in notes.windows(2) {
         let gap = window[1].time_us - window[0].time_us;
         if gap > window_us {
-            return 0.0;
+            return (0.0, midpoint(window[0].time_us, window[1].time_us));
         }
     }
 
     let mut min_notes_in_window = usize::MAX;
+    let mut center_time_us = 0;
     let mut left = 0;
 
     for right in 0..notes.len() {
@@ -120,14 +165,75 @@ pub fn lowest_nps(chart: &RoxChart, window_size_s: f64) -> f64 {
         let count = right - left + 1;
         if count < min_notes_in_window {
             min_notes_in_window = count;
+            center_time_us = midpoint(notes[left].time_us, window_end);
         }
     }
 
     if min_notes_in_window == usize::MAX {
-        return 0.0;
+        return (0.0, 0);
     }
 
-    min_notes_in_window as f64 / window_size_s
+    (min_notes_in_window as f64 / window_size_s, center_time_us)
+}
+
+/// NPS percentiles over windows of `window_ms`, sampled every 100ms from the
+/// first note until the window reaches the last one.
+///
+/// A chart shorter than the window is a single sample. Percentiles use the
+/// nearest rank, so they are always the NPS of an actual window. A spike
+/// shows up as p99 (and `max`) well above p50. All zeros for a chart without
+/// notes or a `window_ms` of 0.
+#[must_use]
+#[allow(
+    clippy::cast_possible_truncation,
+    clippy::cast_precision_loss,
+    clippy::cast_sign_loss
+)]
+pub fn nps_percentiles(chart: &RoxChart, window_ms: u32) -> NpsPercentiles {
+    let window_len_us = i64::from(window_ms) * 1_000;
+    let mut notes: Vec<i64> = chart
+        .notes
+        .iter()
+        .filter(|n| !n.fake)
+        .map(|n| n.time_us)
+        .collect();
+    if window_len_us == 0 || notes.is_empty() {
+        return NpsPercentiles::default();
+    }
+    notes.sort_unstable();
+
+    let window_secs = f64::from(window_ms) / 1_000.0;
+    let first = notes[0];
+    let last_start = (notes[notes.len() - 1] - window_len_us).max(first);
+    let mut samples = Vec::new();
+    let (mut left, mut right) = (0, 0);
+    let mut start = first;
+    while start <= last_start {
+        // Window is [start, start + window_len_us)
+        while right < notes.len() && notes[right] < start + window_len_us {
+            right += 1;
+        }
+        while notes[left] < start {
+            left += 1;
+        }
+        samples.push((right - left) as f64 / window_secs);
+        start += PERCENTILE_STEP_US;
+    }
+    samples.sort_unstable_by(f64::total_cmp);
+
+    let rank =
+        |p: f64| samples[((p * samples.len() as f64).ceil() as usize).clamp(1, samples.len()) - 1];
+    NpsPercentiles {
+        p50: rank(0.50),
+        p90: rank(0.90),
+        p95: rank(0.95),
+        p99: rank(0.99),
+        max: highest_nps(chart, window_secs),
+    }
+}
+
+fn midpoint(start_us: i64, end_us: i64) -> i64 {
+    start_us + (end_us - start_us) / 2
 }
 
 /// Calculate the longest duration where NPS is maintained above 90% of the max NPS.
@@ -154,7 +260,12 @@ pub fn highest_drain_time(chart: &RoxChart) -> f64 {
     let mut current_contiguous_ticks = 0;
 
     // Sort notes for efficient window checks
-    let mut notes: Vec<_> = chart.notes.iter().map(|n| n.time_us).collect();
+    let mut notes: Vec<_> = chart
+        .notes
+        .iter()
+        .filter(|n| !n.fake)
+        .map(|n| n.time_us)
+        .collect();
     notes.sort_unstable();
 
     if notes.is_empty() {
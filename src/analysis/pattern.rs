@@ -1,42 +1,22 @@
-use crate::model::{Note, RoxChart};
+use super::chords::{DEFAULT_EPSILON_US, group_by_time};
+use crate::model::RoxChart;
 use std::collections::HashMap;
 
 /// Calculate chord density (polyphony) distribution.
 ///
+/// Notes starting within [`DEFAULT_EPSILON_US`] of each other count as one
+/// chord (see [`group_by_time`]).
+///
 /// Returns a map where:
 /// - Key = Chord size (1 = Single, 2 = Jump, 3 = Hand, 4 = Quad...)
 /// - Value = Count of occurrences
 pub fn polyphony(chart: &RoxChart) -> HashMap<u32, u32> {
-    if chart.notes.is_empty() {
-        return HashMap::new();
-    }
+    let notes = chart.notes.iter().filter(|n| !n.is_mine() && !n.fake);
 
     let mut distribution = HashMap::new();
-    let mut current_time: Option<i64> = None;
-    let mut current_cluster_size = 0;
-
-    let mut refs: Vec<&Note> = chart.notes.iter().collect();
-    refs.sort_by_key(|n| n.time_us);
-
-    for note in refs {
-        if note.is_mine() {
-            continue;
-        }
-
-        if Some(note.time_us) != current_time {
-            // New cluster, commit previous one
-            if current_cluster_size > 0 {
-                *distribution.entry(current_cluster_size).or_insert(0) += 1;
-            }
-            current_time = Some(note.time_us);
-            current_cluster_size = 0;
-        }
-        current_cluster_size += 1;
-    }
-
-    // Commit last cluster
-    if current_cluster_size > 0 {
-        *distribution.entry(current_cluster_size).or_insert(0) += 1;
+    for chord in group_by_time(notes, DEFAULT_EPSILON_US) {
+        let size = u32::try_from(chord.len()).unwrap_or(u32::MAX);
+        *distribution.entry(size).or_insert(0) += 1;
     }
 
     distribution
@@ -49,7 +29,7 @@ pub fn lane_balance(chart: &RoxChart) -> Vec<u32> {
     let mut counts = vec![0; chart.key_count() as usize];
 
     for note in &chart.notes {
-        if note.is_mine() {
+        if note.is_mine() || note.fake {
             continue;
         }
         let col = note.column as usize;
@@ -61,6 +41,73 @@ pub fn lane_balance(chart: &RoxChart) -> Vec<u32> {
     counts
 }
 
+/// Columns that never get a note ("dead lanes"), in ascending order.
+///
+/// Mines and fakes don't count as use, as in [`lane_balance`].
+#[must_use]
+pub fn dead_lanes(chart: &RoxChart) -> Vec<u8> {
+    lane_balance(chart)
+        .iter()
+        .enumerate()
+        .filter(|&(_, &count)| count == 0)
+        .filter_map(|(col, _)| u8::try_from(col).ok())
+        .collect()
+}
+
+/// Score how evenly notes are spread across columns.
+///
+/// Returns the normalized Shannon entropy of the per-column note counts
+/// from [`lane_balance`], in `0.0..=1.0`: `1.0` means every column carries
+/// the same load, `0.0` means every note sits in a single column. Charts with
+/// no notes or a single column are considered perfectly balanced.
+#[must_use]
+#[allow(clippy::cast_precision_loss)]
+pub fn hand_balance(chart: &RoxChart) -> f64 {
+    let counts = lane_balance(chart);
+    let total: u32 = counts.iter().sum();
+    if total == 0 || counts.len() < 2 {
+        return 1.0;
+    }
+
+    let total = f64::from(total);
+    let entropy: f64 = counts
+        .iter()
+        .filter(|&&c| c > 0)
+        .map(|&c| {
+            let p = f64::from(c) / total;
+            -p * p.ln()
+        })
+        .sum();
+
+    entropy / (counts.len() as f64).ln()
+}
+
+/// Split the note load between the left and right halves of the layout.
+///
+/// Returns `(left, right)` as fractions of all non-mine notes, summing to
+/// `1.0`. On odd key counts the middle column is shared equally between both
+/// hands. Charts with no notes return `(0.5, 0.5)`.
+#[must_use]
+pub fn left_right_balance(chart: &RoxChart) -> (f64, f64) {
+    let counts = lane_balance(chart);
+    let total: u32 = counts.iter().sum();
+    if total == 0 {
+        return (0.5, 0.5);
+    }
+
+    let half = counts.len() / 2;
+    let mut left = f64::from(counts[..half].iter().sum::<u32>());
+    let mut right = f64::from(counts[counts.len() - half..].iter().sum::<u32>());
+    if counts.len() % 2 == 1 {
+        let middle = f64::from(counts[half]) / 2.0;
+        left += middle;
+        right += middle;
+    }
+
+    let total = f64::from(total);
+    (left / total, right / total)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -77,6 +124,16 @@ mod tests {
         assert_eq!(balance, vec![2, 0, 0, 1]);
     }
 
+    #[test]
+    fn test_dead_lanes() {
+        let mut chart = RoxChart::new(4);
+        chart.notes.push(Note::tap(0, 0));
+        chart.notes.push(Note::tap(100, 1));
+        chart.notes.push(Note::mine(200, 2));
+
+        assert_eq!(dead_lanes(&chart), vec![2, 3]);
+    }
+
     #[test]
     fn test_polyphony() {
         let mut chart = RoxChart::new(4);
@@ -84,17 +141,17 @@ mod tests {
         // Single at 0
         chart.notes.push(Note::tap(0, 0));
 
-        // Jump at 100
-        chart.notes.push(Note::tap(100, 0));
-        chart.notes.push(Note::tap(100, 1));
+        // Jump at 100ms
+        chart.notes.push(Note::tap(100_000, 0));
+        chart.notes.push(Note::tap(100_000, 1));
 
-        // Hand at 200
-        chart.notes.push(Note::tap(200, 0));
-        chart.notes.push(Note::tap(200, 1));
-        chart.notes.push(Note::tap(200, 2));
+        // Hand at 200ms, with one note rounded 500µs late
+        chart.notes.push(Note::tap(200_000, 0));
+        chart.notes.push(Note::tap(200_000, 1));
+        chart.notes.push(Note::tap(200_500, 2));
 
-        // Another Single at 300
-        chart.notes.push(Note::tap(300, 3));
+        // Another Single at 300ms
+        chart.notes.push(Note::tap(300_000, 3));
 
         let dist = polyphony(&chart);
 
@@ -103,4 +160,37 @@ mod tests {
         assert_eq!(dist.get(&3), Some(&1)); // 1 Hand
         assert_eq!(dist.get(&4), None);
     }
+
+    #[test]
+    fn test_hand_balance() {
+        let mut lopsided = RoxChart::new(4);
+        for i in 0..16 {
+            lopsided.notes.push(Note::tap(i * 100, 0));
+        }
+        assert!(hand_balance(&lopsided) < 0.01);
+
+        let mut even = RoxChart::new(4);
+        for i in 0..16 {
+            even.notes.push(Note::tap(i * 100, (i % 4) as u8));
+        }
+        assert!((hand_balance(&even) - 1.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_left_right_balance() {
+        let mut chart = RoxChart::new(4);
+        chart.notes.push(Note::tap(0, 0));
+        chart.notes.push(Note::tap(100, 1));
+        chart.notes.push(Note::tap(200, 1));
+        chart.notes.push(Note::tap(300, 3));
+        assert_eq!(left_right_balance(&chart), (0.75, 0.25));
+
+        // Middle column of an odd layout counts for both hands
+        let mut odd = RoxChart::new(5);
+        odd.notes.push(Note::tap(0, 2));
+        odd.notes.push(Note::tap(100, 4));
+        assert_eq!(left_right_balance(&odd), (0.25, 0.75));
+
+        assert_eq!(left_right_balance(&RoxChart::new(4)), (0.5, 0.5));
+    }
 }
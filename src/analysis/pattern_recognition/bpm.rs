@@ -41,7 +41,7 @@ impl TimingAnalyzer {
         let mut notes: Vec<_> = chart
             .notes
             .iter()
-            .filter(|n| !ignore_holds || !n.is_hold())
+            .filter(|n| !n.fake && (!ignore_holds || !n.is_hold()))
             .collect();
         notes.sort_by_key(|n| n.time_us);
         notes
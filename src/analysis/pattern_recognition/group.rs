@@ -0,0 +1,146 @@
+use super::types::PatternType;
+use serde::{Deserialize, Serialize};
+
+/// Coarse pattern families for display.
+///
+/// # Why not `PatternCategory`?
+/// `PatternCategory` describes a single 2x2 quad-tree cell and drives merging.
+/// `PatternGroup` is the UI-facing view of a finished `PatternType`: few enough
+/// buckets that every frontend can give each one a fixed color.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum PatternGroup {
+    Empty,
+    Stream,
+    Jump,
+    Jack,
+    Chord,
+    Mixed,
+}
+
+impl PatternGroup {
+    #[must_use]
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Empty => "Empty",
+            Self::Stream => "Stream",
+            Self::Jump => "Jump",
+            Self::Jack => "Jack",
+            Self::Chord => "Chord",
+            Self::Mixed => "Mixed",
+        }
+    }
+
+    /// Suggested RGB color, stable across releases so timelines look the same everywhere.
+    #[must_use]
+    pub fn color_hint(&self) -> (u8, u8, u8) {
+        match self {
+            Self::Empty => (128, 128, 128),
+            Self::Stream => (66, 135, 245),
+            Self::Jump => (46, 204, 113),
+            Self::Jack => (231, 76, 60),
+            Self::Chord => (155, 89, 182),
+            Self::Mixed => (241, 196, 15),
+        }
+    }
+}
+
+impl PatternType {
+    /// Coarse family of this pattern, for grouping in UIs.
+    #[must_use]
+    pub fn group(&self) -> PatternGroup {
+        match self {
+            Self::EmptyRegion | Self::VerySparse => PatternGroup::Empty,
+
+            Self::SingleNotes
+            | Self::Scattered
+            | Self::SparseSingles
+            | Self::Stream
+            | Self::ReverseStream
+            | Self::StreamSection
+            | Self::SparseStream
+            | Self::StreamWithSingles
+            | Self::StreamDense => PatternGroup::Stream,
+
+            Self::JumpSection
+            | Self::SparseJumps
+            | Self::JumpWithSingles
+            | Self::LightJumps
+            | Self::DenseJumps
+            | Self::AlternatingJumps
+            | Self::Jumpstream
+            | Self::JumpstreamDense
+            | Self::JumpstreamWithSingles => PatternGroup::Jump,
+
+            Self::JackSection
+            | Self::ExtendedJackLeft
+            | Self::ExtendedJackRight
+            | Self::SplitJack
+            | Self::SparseJacks
+            | Self::JackWithSingles
+            | Self::LightJacks
+            | Self::Chordjack
+            | Self::ChordjackDense => PatternGroup::Jack,
+
+            Self::ChordSection
+            | Self::SparseChords
+            | Self::ChordWithSingles
+            | Self::LightChords
+            | Self::DenseChord
+            | Self::TripleSection
+            | Self::TripleWithSingles
+            | Self::Handstream
+            | Self::HandstreamDense => PatternGroup::Chord,
+
+            Self::TechnicalHybrid
+            | Self::TechnicalWithSingles
+            | Self::SparseTechnical
+            | Self::Mixed
+            | Self::ComplexMixed
+            | Self::ComplexDense
+            | Self::Dense
+            | Self::Moderate
+            | Self::Light => PatternGroup::Mixed,
+        }
+    }
+
+    /// Suggested RGB color for this pattern (the color of its group).
+    #[must_use]
+    pub fn color_hint(&self) -> (u8, u8, u8) {
+        self.group().color_hint()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_jumpstream_variants_share_group() {
+        let variants = [
+            PatternType::Jumpstream,
+            PatternType::JumpstreamDense,
+            PatternType::JumpstreamWithSingles,
+        ];
+        for variant in variants {
+            assert_eq!(variant.group(), PatternGroup::Jump, "{}", variant.as_str());
+            assert_eq!(variant.color_hint(), PatternGroup::Jump.color_hint());
+        }
+    }
+
+    #[test]
+    fn test_group_colors_are_distinct() {
+        let groups = [
+            PatternGroup::Empty,
+            PatternGroup::Stream,
+            PatternGroup::Jump,
+            PatternGroup::Jack,
+            PatternGroup::Chord,
+            PatternGroup::Mixed,
+        ];
+        for (i, a) in groups.iter().enumerate() {
+            for b in &groups[i + 1..] {
+                assert_ne!(a.color_hint(), b.color_hint());
+            }
+        }
+    }
+}
@@ -1,5 +1,6 @@
 pub mod bpm;
 pub mod grid;
+pub mod group;
 pub mod merger;
 pub mod timeline;
 pub mod tree;
@@ -8,6 +9,7 @@ pub mod window;
 
 pub use bpm::TimingAnalyzer;
 pub use grid::PatternGrid;
+pub use group::PatternGroup;
 pub use timeline::{PatternTimeline, PatternTimelineEntry};
 pub use tree::{QuadTreeBuilder, QuadTreeNode};
 pub use types::{PatternCategory, PatternClassification, PatternType};
@@ -37,27 +39,53 @@ impl Serialize for AnalysisResult {
     }
 }
 
+/// Tuning knobs for [`analyze_with_options`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct AnalysisOptions {
+    /// Maximum number of time slices per grid.
+    pub max_time_slices: usize,
+    /// Ignore hold bodies when building grids.
+    pub ignore_holds: bool,
+    /// Rows per classification window.
+    pub window_size: usize,
+    /// Rows to advance between windows. `window_size / 2` gives Quattern's
+    /// 50% overlap; `window_size` gives non-overlapping windows.
+    pub window_step: usize,
+}
+
+impl Default for AnalysisOptions {
+    /// Quattern defaults.
+    fn default() -> Self {
+        Self {
+            max_time_slices: 20,
+            ignore_holds: true,
+            window_size: 4,
+            window_step: 2,
+        }
+    }
+}
+
 /// Perform full pattern recognition analysis on a chart.
 pub fn analyze(chart: &RoxChart) -> AnalysisResult {
+    analyze_with_options(chart, &AnalysisOptions::default())
+}
+
+/// Perform full pattern recognition analysis with custom [`AnalysisOptions`].
+#[must_use]
+pub fn analyze_with_options(chart: &RoxChart, options: &AnalysisOptions) -> AnalysisResult {
     let key_count = chart.key_count();
-    // Quattern defaults
-    let max_time_slices = 20;
-    let ignore_holds = true;
-    let window_size = 4;
+    let ignore_holds = options.ignore_holds;
 
-    let (grids, timestamps) = PatternGrid::from_chart(chart, max_time_slices, ignore_holds);
+    let (grids, timestamps) = PatternGrid::from_chart(chart, options.max_time_slices, ignore_holds);
 
-    let mut trees = Vec::new();
-    for grid in &grids {
-        let builder = QuadTreeBuilder::new(grid);
-        trees.push(builder.build());
-    }
+    let trees = build_trees(&grids);
 
     // New Window-based Analysis (Quattern 1:1 match)
     let timing_analyzer = TimingAnalyzer::new(chart, ignore_holds);
     let cross_analyzer =
         CrossSegmentAnalyzer::new(&grids, &timestamps, &timing_analyzer, key_count as usize);
-    let cross_results = cross_analyzer.analyze_cross_segment(window_size);
+    let cross_results =
+        cross_analyzer.analyze_cross_segment_with_step(options.window_size, options.window_step);
 
     let timeline = PatternTimeline::build_from_cross_analysis(
         &cross_results,
@@ -73,6 +101,34 @@ pub fn analyze(chart: &RoxChart) -> AnalysisResult {
     }
 }
 
+/// Build one quad-tree per grid.
+///
+/// Grids are independent, so with the `parallel` feature the work is spread
+/// across rayon's thread pool. `collect` on an indexed parallel iterator keeps
+/// the original order, so the result is identical to the sequential path.
+#[cfg(feature = "parallel")]
+fn build_trees(grids: &[PatternGrid]) -> Vec<QuadTreeNode> {
+    use rayon::prelude::*;
+
+    grids
+        .par_iter()
+        .map(|grid| QuadTreeBuilder::new(grid).build())
+        .collect()
+}
+
+#[cfg(not(feature = "parallel"))]
+fn build_trees(grids: &[PatternGrid]) -> Vec<QuadTreeNode> {
+    build_trees_sequential(grids)
+}
+
+#[cfg_attr(all(feature = "parallel", not(test)), allow(dead_code))]
+fn build_trees_sequential(grids: &[PatternGrid]) -> Vec<QuadTreeNode> {
+    grids
+        .iter()
+        .map(|grid| QuadTreeBuilder::new(grid).build())
+        .collect()
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -132,4 +188,64 @@ mod tests {
             assert!(entry.get("note_count").is_some(), "Missing 'note_count'");
         }
     }
+
+    #[test]
+    fn test_non_overlapping_windows() {
+        use crate::codec::Decoder;
+        use crate::codec::formats::OsuDecoder;
+
+        let data = crate::test_utils::get_test_asset("osu/mania_7k.osu");
+        let chart = OsuDecoder::decode(&data).expect("Failed to decode 7K asset");
+        let options = AnalysisOptions {
+            window_step: 4,
+            ..AnalysisOptions::default()
+        };
+
+        let (grids, timestamps) = PatternGrid::from_chart(&chart, 20, true);
+        let timing = TimingAnalyzer::new(&chart, true);
+        let windows = CrossSegmentAnalyzer::new(&grids, &timestamps, &timing, 7)
+            .analyze_cross_segment_with_step(options.window_size, options.window_step);
+        assert!(windows.len() > 1);
+        for pair in windows.windows(2) {
+            assert!(pair[0].1 < pair[1].0, "windows overlap: {pair:?}");
+        }
+
+        let result = analyze_with_options(&chart, &options);
+        assert!(!result.timeline.entries.is_empty());
+        for pair in result.timeline.entries.windows(2) {
+            assert!(pair[0].end_time < pair[1].start_time);
+        }
+    }
+
+    #[test]
+    fn test_default_options_match_analyze() {
+        let chart = create_test_chart();
+        assert_eq!(
+            serde_json::to_string(&analyze(&chart)).unwrap(),
+            serde_json::to_string(&analyze_with_options(&chart, &AnalysisOptions::default()))
+                .unwrap()
+        );
+    }
+
+    #[test]
+    fn test_parallel_trees_match_sequential() {
+        use crate::codec::Decoder;
+        use crate::codec::formats::OsuDecoder;
+
+        let data = crate::test_utils::get_test_asset("osu/mania_7k.osu");
+        let chart = OsuDecoder::decode(&data).expect("Failed to decode 7K asset");
+
+        let (grids, _) = PatternGrid::from_chart(&chart, 20, true);
+        let sequential = build_trees_sequential(&grids);
+        let result = analyze(&chart);
+
+        // QuadTreeNode has no PartialEq; the Debug output covers every field.
+        assert_eq!(format!("{:?}", result.tree), format!("{sequential:?}"));
+
+        let again = analyze(&chart);
+        assert_eq!(
+            serde_json::to_string(&result).expect("Failed to serialize"),
+            serde_json::to_string(&again).expect("Failed to serialize")
+        );
+    }
 }
@@ -1,7 +1,7 @@
 use super::grid::PatternGrid;
 use super::types::{PatternCategory, PatternClassification, PatternType};
 use serde::{Deserialize, Serialize};
-use std::collections::{HashMap, HashSet};
+use std::collections::{BTreeMap, HashSet};
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct QuadTreeNode {
@@ -366,7 +366,8 @@ impl<'a> QuadTreeBuilder<'a> {
         categories: &[PatternCategory],
     ) -> PatternType {
         let n = children.len();
-        let mut cat_counts = HashMap::new();
+        // Ordered map so ties between categories resolve the same way on every run
+        let mut cat_counts = BTreeMap::new();
         for &cat in categories {
             *cat_counts.entry(cat).or_insert(0) += 1;
         }
@@ -381,7 +382,7 @@ impl<'a> QuadTreeBuilder<'a> {
             return PatternType::VerySparse;
         }
 
-        let non_empty_counts: HashMap<_, _> = cat_counts
+        let non_empty_counts: BTreeMap<_, _> = cat_counts
             .iter()
             .filter(|&(&cat, _)| cat != PatternCategory::Empty)
             .collect();
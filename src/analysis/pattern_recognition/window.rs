@@ -182,7 +182,23 @@ impl<'a> CrossSegmentAnalyzer<'a> {
         PatternType::Mixed
     }
 
+    /// Classify windows of `window_size` rows, advancing by half a window
+    /// (50% overlap, as Quattern does).
     pub fn analyze_cross_segment(&self, window_size: usize) -> Vec<(i64, i64, PatternType, f64)> {
+        self.analyze_cross_segment_with_step(window_size, window_size / 2)
+    }
+
+    /// Classify windows of `window_size` rows, advancing by `step` rows.
+    ///
+    /// `step == window_size` tiles the chart without overlap, so entry
+    /// durations can be summed. A `step` of 0 is treated as 1.
+    #[must_use]
+    pub fn analyze_cross_segment_with_step(
+        &self,
+        window_size: usize,
+        step: usize,
+    ) -> Vec<(i64, i64, PatternType, f64)> {
+        let step = step.max(1);
         let mut results = Vec::new();
         let mut i = 0;
 
@@ -235,7 +251,7 @@ impl<'a> CrossSegmentAnalyzer<'a> {
 
             results.push((start_time, end_time, pattern, avg_bpm));
 
-            i += window_size / 2; // Overlap
+            i += step;
         }
 
         results
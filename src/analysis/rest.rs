@@ -0,0 +1,80 @@
+use crate::model::{Note, RoxChart};
+
+/// Find rest sections: ranges of at least `min_gap_ms` with nothing to play.
+///
+/// Returns `(gap_start_us, gap_end_us)` pairs in time order. A gap starts when
+/// the last active note (including hold tails) ends and lasts until the next
+/// note starts, so a long hold is never a rest. Mines and fakes are ignored
+/// since they are not played. Only gaps between notes are reported, not the lead-in
+/// before the first note.
+#[must_use]
+pub fn rest_sections(chart: &RoxChart, min_gap_ms: u32) -> Vec<(i64, i64)> {
+    let threshold_us = i64::from(min_gap_ms) * 1000;
+
+    let mut notes: Vec<&Note> = chart
+        .notes
+        .iter()
+        .filter(|n| !n.is_mine() && !n.fake)
+        .collect();
+    notes.sort_by_key(|n| n.time_us);
+
+    let mut rests = Vec::new();
+    let mut busy_until: Option<i64> = None;
+
+    for note in notes {
+        if let Some(end) = busy_until
+            && note.time_us - end >= threshold_us
+        {
+            rests.push((end, note.time_us));
+        }
+        busy_until = Some(busy_until.map_or(note.end_time_us(), |end| end.max(note.end_time_us())));
+    }
+
+    rests
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_gap_between_clusters() {
+        let mut chart = RoxChart::new(4);
+        for i in 0..8 {
+            chart.notes.push(Note::tap(i * 100_000, (i % 4) as u8));
+        }
+        // 3s gap after the last note of the first cluster (0.7s)
+        for i in 0..8 {
+            chart
+                .notes
+                .push(Note::tap(3_700_000 + i * 100_000, (i % 4) as u8));
+        }
+
+        assert_eq!(rest_sections(&chart, 2000), vec![(700_000, 3_700_000)]);
+        assert_eq!(rest_sections(&chart, 3000), vec![(700_000, 3_700_000)]);
+        assert!(rest_sections(&chart, 3001).is_empty());
+    }
+
+    #[test]
+    fn test_hold_is_not_a_rest() {
+        let mut chart = RoxChart::new(4);
+        chart.notes.push(Note::tap(0, 0));
+        chart.notes.push(Note::hold(100_000, 4_000_000, 1));
+        chart.notes.push(Note::tap(3_000_000, 2));
+        chart.notes.push(Note::tap(7_100_000, 3));
+
+        // Only the gap after the hold's tail (4.1s) counts
+        assert_eq!(rest_sections(&chart, 2000), vec![(4_100_000, 7_100_000)]);
+    }
+
+    #[test]
+    fn test_mines_are_ignored() {
+        let mut chart = RoxChart::new(4);
+        chart.notes.push(Note::tap(0, 0));
+        chart.notes.push(Note::mine(1_500_000, 1));
+        chart.notes.push(Note::tap(3_000_000, 0));
+
+        assert_eq!(rest_sections(&chart, 2000), vec![(0, 3_000_000)]);
+        assert!(rest_sections(&RoxChart::new(4), 0).is_empty());
+    }
+}
@@ -0,0 +1,184 @@
+use crate::model::{RoxChart, TimingPoint};
+
+/// How far a note may be from a beat line and still count as on it for
+/// [`beat_snap`]. Covers the 1ms rounding of millisecond-based formats.
+pub const BEAT_SNAP_TOLERANCE_US: i64 = 2_000;
+
+/// The beat division a note is snapped to, as colored by rhythm game editors.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum BeatSnap {
+    /// On the beat (1/1).
+    Whole,
+    /// 1/2.
+    Half,
+    /// 1/3.
+    Third,
+    /// 1/4.
+    Quarter,
+    /// 1/6.
+    Sixth,
+    /// 1/8.
+    Eighth,
+    /// 1/12.
+    Twelfth,
+    /// 1/16.
+    Sixteenth,
+    /// On none of the divisions above.
+    Unsnapped,
+}
+
+impl BeatSnap {
+    /// Snaps from coarsest to finest, the order [`beat_snap`] tries them in.
+    pub const ALL: [BeatSnap; 8] = [
+        BeatSnap::Whole,
+        BeatSnap::Half,
+        BeatSnap::Third,
+        BeatSnap::Quarter,
+        BeatSnap::Sixth,
+        BeatSnap::Eighth,
+        BeatSnap::Twelfth,
+        BeatSnap::Sixteenth,
+    ];
+
+    /// Denominator of the division (4 for 1/4), or `None` when unsnapped.
+    #[must_use]
+    pub fn divisor(self) -> Option<u32> {
+        match self {
+            BeatSnap::Whole => Some(1),
+            BeatSnap::Half => Some(2),
+            BeatSnap::Third => Some(3),
+            BeatSnap::Quarter => Some(4),
+            BeatSnap::Sixth => Some(6),
+            BeatSnap::Eighth => Some(8),
+            BeatSnap::Twelfth => Some(12),
+            BeatSnap::Sixteenth => Some(16),
+            BeatSnap::Unsnapped => None,
+        }
+    }
+}
+
+/// Beat snap of a note at `time_us`: the coarsest division whose beat line
+/// is within [`BEAT_SNAP_TOLERANCE_US`].
+///
+/// Beats are counted from the BPM point active at `time_us`, as in
+/// [`off_grid_notes`]. A chart without BPM points gives
+/// [`BeatSnap::Unsnapped`].
+#[must_use]
+pub fn beat_snap(chart: &RoxChart, time_us: i64) -> BeatSnap {
+    let bpms = bpm_points(chart);
+    if bpms.is_empty() {
+        return BeatSnap::Unsnapped;
+    }
+    let tp = active_bpm(&bpms, time_us);
+    BeatSnap::ALL
+        .into_iter()
+        .find(|snap| {
+            snap.divisor().is_some_and(|divisor| {
+                grid_deviation_us(tp, time_us, divisor) <= BEAT_SNAP_TOLERANCE_US
+            })
+        })
+        .unwrap_or(BeatSnap::Unsnapped)
+}
+
+/// Indices of notes further than `tolerance_us` from the nearest `1/divisor`
+/// beat line.
+///
+/// The grid restarts at every BPM point, so each note is measured against the
+/// BPM active at its time (the first BPM point for notes before it). Use this
+/// to find notes that would be mis-snapped when exporting to beat-based
+/// formats. Returns nothing for a `divisor` of 0 or a chart without BPM
+/// points.
+#[must_use]
+pub fn off_grid_notes(chart: &RoxChart, divisor: u32, tolerance_us: i64) -> Vec<usize> {
+    let bpms = bpm_points(chart);
+    if divisor == 0 || bpms.is_empty() {
+        return Vec::new();
+    }
+
+    chart
+        .notes
+        .iter()
+        .enumerate()
+        .filter(|(_, note)| {
+            let tp = active_bpm(&bpms, note.time_us);
+            grid_deviation_us(tp, note.time_us, divisor) > tolerance_us
+        })
+        .map(|(i, _)| i)
+        .collect()
+}
+
+/// BPM points with a usable tempo, in chart order.
+fn bpm_points(chart: &RoxChart) -> Vec<&TimingPoint> {
+    chart
+        .timing_points
+        .iter()
+        .filter(|tp| !tp.is_inherited && tp.bpm > 0.0)
+        .collect()
+}
+
+/// The BPM point in effect at `time_us` (the first one before any of them).
+fn active_bpm<'a>(bpms: &[&'a TimingPoint], time_us: i64) -> &'a TimingPoint {
+    let active = bpms.partition_point(|tp| tp.time_us <= time_us);
+    bpms[active.saturating_sub(1)]
+}
+
+/// Distance from `time_us` to the nearest `1/divisor` beat line of `tp`.
+#[allow(clippy::cast_possible_truncation, clippy::cast_precision_loss)]
+fn grid_deviation_us(tp: &TimingPoint, time_us: i64, divisor: u32) -> i64 {
+    let step_us = 60_000_000.0 / f64::from(tp.bpm) / f64::from(divisor);
+    let offset = (time_us - tp.time_us) as f64;
+    let deviation = offset - (offset / step_us).round() * step_us;
+    deviation.abs().round() as i64
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model::Note;
+
+    #[test]
+    fn test_off_grid_notes() {
+        let mut chart = RoxChart::new(4);
+        chart.timing_points.push(TimingPoint::bpm(0, 120.0));
+        // 1/16 of a beat at 120 BPM is 31.25ms
+        chart.notes.push(Note::tap(93_750, 0));
+        chart.notes.push(Note::tap(1_004_000, 1));
+        chart.notes.push(Note::tap(1_500_000, 2));
+
+        assert_eq!(off_grid_notes(&chart, 16, 2_000), vec![1]);
+        assert!(off_grid_notes(&chart, 16, 5_000).is_empty());
+        assert!(off_grid_notes(&chart, 0, 0).is_empty());
+    }
+
+    #[test]
+    fn test_off_grid_notes_per_bpm_section() {
+        let mut chart = RoxChart::new(4);
+        chart.timing_points.push(TimingPoint::bpm(0, 120.0));
+        // Off the 120 BPM grid, but the 150 BPM grid starts here
+        chart.timing_points.push(TimingPoint::bpm(1_010_000, 150.0));
+        chart.notes.push(Note::tap(1_010_000 + 400_000, 0));
+
+        assert!(off_grid_notes(&chart, 4, 1_000).is_empty());
+    }
+
+    #[test]
+    fn test_beat_snap() {
+        let mut chart = RoxChart::new(4);
+        chart.timing_points.push(TimingPoint::bpm(0, 120.0));
+
+        // One beat is 500ms at 120 BPM
+        assert_eq!(beat_snap(&chart, 1_000_000), BeatSnap::Whole);
+        assert_eq!(beat_snap(&chart, 1_250_000), BeatSnap::Half);
+        assert_eq!(beat_snap(&chart, 1_125_000), BeatSnap::Quarter);
+        assert_eq!(beat_snap(&chart, 1_166_667), BeatSnap::Third);
+        assert_eq!(beat_snap(&chart, 1_041_667), BeatSnap::Twelfth);
+        assert_eq!(beat_snap(&chart, 1_031_250), BeatSnap::Sixteenth);
+        // Rounded to the millisecond, as in an .osu file
+        assert_eq!(beat_snap(&chart, 1_167_000), BeatSnap::Third);
+        assert_eq!(beat_snap(&chart, 1_010_000), BeatSnap::Unsnapped);
+
+        assert_eq!(BeatSnap::Half.divisor(), Some(2));
+        assert_eq!(BeatSnap::Unsnapped.divisor(), None);
+        assert_eq!(beat_snap(&RoxChart::new(4), 0), BeatSnap::Unsnapped);
+    }
+}
@@ -0,0 +1,96 @@
+use crate::model::RoxChart;
+use serde::Serialize;
+
+/// One-call summary of the values chart lists usually display.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ChartStats {
+    pub key_count: u8,
+    pub duration_us: i64,
+    pub note_count: usize,
+    pub nps: f64,
+    pub bpm_min: f64,
+    pub bpm_max: f64,
+    pub bpm_mode: f64,
+    pub short_hash: String,
+}
+
+/// Compute all summary stats at once.
+///
+/// The chart duration is computed once and shared with the NPS, and BPM min/max
+/// come from a single pass over the timing points, so this is cheaper than
+/// calling each `RoxAnalysis` method (and much cheaper than one FFI call each).
+#[must_use]
+#[allow(clippy::cast_precision_loss)]
+pub fn stats(chart: &RoxChart) -> ChartStats {
+    let duration_us = chart.duration_us();
+    let seconds = duration_us as f64 / 1_000_000.0;
+    // Fakes are never hit, so they count towards neither
+    let note_count = chart.notes.iter().filter(|n| !n.fake).count();
+    let nps = if seconds <= 0.0 {
+        0.0
+    } else {
+        note_count as f64 / seconds
+    };
+
+    let (bpm_min, bpm_max) = chart
+        .timing_points
+        .iter()
+        .filter(|tp| !tp.is_inherited)
+        .map(|tp| f64::from(tp.bpm))
+        .fold((f64::INFINITY, f64::NEG_INFINITY), |(min, max), bpm| {
+            (min.min(bpm), max.max(bpm))
+        });
+
+    let mut short_hash = super::hash::hash(chart);
+    short_hash.truncate(16);
+
+    ChartStats {
+        key_count: chart.key_count(),
+        duration_us,
+        note_count,
+        nps,
+        bpm_min,
+        bpm_max,
+        bpm_mode: super::bpm::bpm_mode(chart),
+        short_hash,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::analysis::RoxAnalysis;
+    use crate::model::{Note, TimingPoint};
+
+    #[test]
+    fn test_stats_matches_individual_methods() {
+        let mut chart = RoxChart::new(7);
+        chart.timing_points.push(TimingPoint::bpm(0, 150.0));
+        chart.timing_points.push(TimingPoint::sv(1_000_000, 0.5));
+        chart.timing_points.push(TimingPoint::bpm(4_000_000, 200.0));
+        for i in 0..40 {
+            chart.notes.push(Note::tap(i * 125_000, (i % 7) as u8));
+        }
+        chart.notes.push(Note::hold(5_000_000, 1_000_000, 3));
+
+        let stats = chart.stats();
+        assert_eq!(stats.key_count, chart.key_count());
+        assert_eq!(stats.duration_us, chart.duration_us());
+        assert_eq!(stats.note_count, chart.note_count());
+        assert_eq!(stats.nps, chart.nps());
+        assert_eq!(stats.bpm_min, chart.bpm_min());
+        assert_eq!(stats.bpm_max, chart.bpm_max());
+        assert_eq!(stats.bpm_mode, chart.bpm_mode());
+        assert_eq!(stats.short_hash, chart.short_hash());
+    }
+
+    #[test]
+    fn test_stats_empty_chart() {
+        let chart = RoxChart::new(4);
+        let stats = chart.stats();
+        assert_eq!(stats.note_count, 0);
+        assert_eq!(stats.nps, 0.0);
+        assert_eq!(stats.bpm_min, chart.bpm_min());
+        assert_eq!(stats.bpm_max, chart.bpm_max());
+    }
+}
@@ -0,0 +1,102 @@
+use crate::model::RoxChart;
+use serde::Serialize;
+
+/// How much a chart's timing moves around, for tagging "variable BPM" or
+/// gimmick charts.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct TimingComplexity {
+    /// BPM points whose BPM differs from the previous one. The first BPM point
+    /// is not a change.
+    pub bpm_change_count: usize,
+    /// Scroll velocity (inherited) points.
+    pub sv_change_count: usize,
+    /// Whether scrolling ever freezes: an SV point with a speed of 0, which is
+    /// how formats without stops keep them.
+    pub has_stops: bool,
+    /// Lowest BPM, or 0 without BPM points.
+    pub min_bpm: f64,
+    /// Highest BPM, or 0 without BPM points.
+    pub max_bpm: f64,
+    /// `max_bpm / min_bpm`, or 1 when it is undefined.
+    pub bpm_ratio: f64,
+}
+
+/// Summarize the timing points in a single pass.
+#[must_use]
+pub fn timing_complexity(chart: &RoxChart) -> TimingComplexity {
+    let mut bpm_change_count = 0;
+    let mut sv_change_count = 0;
+    let mut has_stops = false;
+    let mut last_bpm: Option<f32> = None;
+    let (mut min_bpm, mut max_bpm) = (f64::INFINITY, f64::NEG_INFINITY);
+
+    for tp in &chart.timing_points {
+        if tp.is_inherited {
+            sv_change_count += 1;
+            has_stops |= tp.scroll_speed == 0.0;
+            continue;
+        }
+        if last_bpm.is_some_and(|bpm| (bpm - tp.bpm).abs() > f32::EPSILON) {
+            bpm_change_count += 1;
+        }
+        last_bpm = Some(tp.bpm);
+        min_bpm = min_bpm.min(f64::from(tp.bpm));
+        max_bpm = max_bpm.max(f64::from(tp.bpm));
+    }
+
+    if last_bpm.is_none() {
+        (min_bpm, max_bpm) = (0.0, 0.0);
+    }
+    let bpm_ratio = if min_bpm > 0.0 {
+        max_bpm / min_bpm
+    } else {
+        1.0
+    };
+
+    TimingComplexity {
+        bpm_change_count,
+        sv_change_count,
+        has_stops,
+        min_bpm,
+        max_bpm,
+        bpm_ratio,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::analysis::RoxAnalysis;
+    use crate::model::TimingPoint;
+
+    #[test]
+    fn test_timing_complexity() {
+        let mut chart = RoxChart::new(4);
+        chart.timing_points.push(TimingPoint::bpm(0, 120.0));
+        chart.timing_points.push(TimingPoint::sv(1_000_000, 0.5));
+        chart.timing_points.push(TimingPoint::bpm(2_000_000, 240.0));
+        // Same BPM again is not a change
+        chart.timing_points.push(TimingPoint::bpm(3_000_000, 240.0));
+        chart.timing_points.push(TimingPoint::bpm(4_000_000, 180.0));
+
+        let timing = chart.timing_complexity();
+        assert_eq!(timing.bpm_change_count, 2);
+        assert_eq!(timing.sv_change_count, 1);
+        assert!(!timing.has_stops);
+        assert_eq!(timing.min_bpm, 120.0);
+        assert_eq!(timing.max_bpm, 240.0);
+        assert_eq!(timing.bpm_ratio, 2.0);
+
+        chart.timing_points.push(TimingPoint::sv(5_000_000, 0.0));
+        assert!(chart.timing_complexity().has_stops);
+    }
+
+    #[test]
+    fn test_timing_complexity_empty() {
+        let timing = timing_complexity(&RoxChart::new(4));
+        assert_eq!(timing.bpm_change_count, 0);
+        assert_eq!(timing.min_bpm, 0.0);
+        assert_eq!(timing.max_bpm, 0.0);
+        assert_eq!(timing.bpm_ratio, 1.0);
+    }
+}
@@ -145,12 +145,12 @@ fn cmd_info(args: &[String]) -> ExitCode {
         println!("  Level:      {:.2}", val);
     }
     println!("  Audio:      {}", chart.metadata.audio_file);
-    if chart.metadata.is_coop {
+    if let Some(split) = chart.coop_split() {
         println!(
             "  Mode:       {}K Coop ({}K + {}K)",
             chart.key_count(),
-            chart.key_count() / 2,
-            chart.key_count() / 2
+            split,
+            chart.key_count() - split
         );
     } else {
         println!("  Mode:       {}K", chart.key_count());
@@ -0,0 +1,244 @@
+//! What each output format can store, for warning about lossy conversions.
+
+use serde::Serialize;
+
+use crate::model::{MAX_KEY_COUNT, RoxChart};
+
+use super::types::OutputFormat;
+
+/// Every key count ROX accepts.
+const ALL_KEY_COUNTS: [u8; MAX_KEY_COUNT as usize] = [
+    1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12, 13, 14, 15, 16, 17, 18,
+];
+
+/// Chart features an output format can store.
+///
+/// Features a format lacks are dropped or approximated by its encoder (e.g.
+/// osu! writes mines and bursts as taps).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[allow(clippy::struct_excessive_bools)]
+pub struct FormatCapabilities {
+    /// Hold notes.
+    pub supports_holds: bool,
+    /// Burst/roll notes.
+    pub supports_bursts: bool,
+    /// Mines.
+    pub supports_mines: bool,
+    /// Scroll velocity (inherited) timing points.
+    pub supports_sv: bool,
+    /// Per-note keysound samples.
+    pub supports_keysounds: bool,
+    /// Fake (never judged) notes.
+    pub supports_fakes: bool,
+    /// Key counts the format can represent.
+    pub allowed_key_counts: &'static [u8],
+}
+
+/// What a conversion loses, from [`FormatCapabilities::report`] or
+/// [`convert_with_report`](crate::codec::convert_with_report).
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize)]
+pub struct ConversionReport {
+    /// Holds written as something else.
+    pub dropped_holds: usize,
+    /// Bursts/rolls written as something else (usually taps).
+    pub dropped_bursts: usize,
+    /// Mines dropped or written as taps.
+    pub dropped_mines: usize,
+    /// SV timing points dropped.
+    pub dropped_sv_points: usize,
+    /// Notes whose keysounds were dropped.
+    pub dropped_keysounds: usize,
+    /// Fake notes dropped.
+    pub dropped_fakes: usize,
+    /// The key count can't be represented as-is.
+    pub key_count_changed: bool,
+}
+
+impl ConversionReport {
+    /// Whether nothing is lost.
+    #[must_use]
+    pub fn is_lossless(&self) -> bool {
+        *self == Self::default()
+    }
+}
+
+impl FormatCapabilities {
+    /// Count what `chart` would lose in this format.
+    #[must_use]
+    pub fn report(&self, chart: &RoxChart) -> ConversionReport {
+        let counts = chart.note_counts();
+        let unless = |supported: bool, count: usize| if supported { 0 } else { count };
+
+        ConversionReport {
+            dropped_holds: unless(self.supports_holds, counts.holds),
+            dropped_bursts: unless(self.supports_bursts, counts.bursts),
+            dropped_mines: unless(self.supports_mines, counts.mines),
+            dropped_sv_points: unless(
+                self.supports_sv,
+                chart
+                    .timing_points
+                    .iter()
+                    .filter(|tp| tp.is_inherited)
+                    .count(),
+            ),
+            dropped_keysounds: unless(
+                self.supports_keysounds,
+                chart
+                    .notes
+                    .iter()
+                    .filter(|n| n.hitsound_index.is_some() || n.tail_hitsound_index.is_some())
+                    .count(),
+            ),
+            dropped_fakes: unless(self.supports_fakes, counts.fakes),
+            key_count_changed: !self.allowed_key_counts.contains(&chart.key_count()),
+        }
+    }
+
+    /// Human-readable descriptions of what `chart` would lose in this format,
+    /// e.g. `"3 mines"`. Empty for a lossless conversion.
+    #[must_use]
+    pub fn losses(&self, chart: &RoxChart) -> Vec<String> {
+        let report = self.report(chart);
+        let mut losses: Vec<String> = [
+            (report.dropped_holds, "holds"),
+            (report.dropped_bursts, "bursts"),
+            (report.dropped_mines, "mines"),
+            (report.dropped_sv_points, "SV points"),
+            (report.dropped_keysounds, "keysounded notes"),
+            (report.dropped_fakes, "fake notes"),
+        ]
+        .into_iter()
+        .filter(|&(count, _)| count > 0)
+        .map(|(count, what)| format!("{count} {what}"))
+        .collect();
+
+        if report.key_count_changed {
+            losses.push(format!("{}K key count", chart.key_count()));
+        }
+        losses
+    }
+}
+
+impl OutputFormat {
+    /// Chart features this format can store.
+    #[must_use]
+    pub fn capabilities(&self) -> FormatCapabilities {
+        let lossless = FormatCapabilities {
+            supports_holds: true,
+            supports_bursts: true,
+            supports_mines: true,
+            supports_sv: true,
+            supports_keysounds: true,
+            supports_fakes: true,
+            allowed_key_counts: &ALL_KEY_COUNTS,
+        };
+
+        match self {
+            #[cfg(feature = "compression")]
+            Self::Rox => lossless,
+            Self::Jrox | Self::Yrox => lossless,
+            Self::Osu => FormatCapabilities {
+                supports_bursts: false,
+                supports_mines: false,
+                supports_keysounds: false,
+                supports_fakes: false,
+                ..lossless
+            },
+            Self::Sm => FormatCapabilities {
+                supports_sv: false,
+                supports_keysounds: false,
+                allowed_key_counts: &[4, 6, 8],
+                ..lossless
+            },
+            Self::Qua => FormatCapabilities {
+                supports_bursts: false,
+                supports_mines: false,
+                supports_keysounds: false,
+                allowed_key_counts: &[4, 7],
+                supports_fakes: false,
+                ..lossless
+            },
+            Self::Fnf => FormatCapabilities {
+                supports_bursts: false,
+                supports_mines: false,
+                supports_sv: false,
+                supports_keysounds: false,
+                allowed_key_counts: &[4, 8],
+                supports_fakes: false,
+                ..lossless
+            },
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model::RoxChartBuilder;
+
+    #[test]
+    fn test_capabilities() {
+        assert!(!OutputFormat::Osu.capabilities().supports_mines);
+        assert!(OutputFormat::Sm.capabilities().supports_mines);
+        assert!(!OutputFormat::Sm.capabilities().supports_sv);
+        assert_eq!(OutputFormat::Fnf.capabilities().allowed_key_counts, &[4, 8]);
+        assert!(
+            OutputFormat::Jrox
+                .capabilities()
+                .allowed_key_counts
+                .contains(&MAX_KEY_COUNT)
+        );
+    }
+
+    #[test]
+    fn test_fakes_report() {
+        let mut chart = RoxChartBuilder::new(4)
+            .bpm(0, 120.0)
+            .tap(0, 0)
+            .build()
+            .unwrap();
+        chart.notes.push(crate::model::Note {
+            fake: true,
+            ..crate::model::Note::tap(0, 0)
+        });
+
+        assert_eq!(
+            OutputFormat::Osu
+                .capabilities()
+                .report(&chart)
+                .dropped_fakes,
+            1
+        );
+        assert_eq!(
+            OutputFormat::Sm.capabilities().report(&chart).dropped_fakes,
+            0
+        );
+        assert_eq!(
+            OutputFormat::Qua.capabilities().losses(&chart),
+            vec!["1 fake notes".to_string()]
+        );
+    }
+
+    #[test]
+    fn test_losses() {
+        let chart = RoxChartBuilder::new(7)
+            .bpm(0, 120.0)
+            .sv(0, 1.5)
+            .tap(0, 0)
+            .mine(100_000, 1)
+            .mine(200_000, 2)
+            .mine(300_000, 3)
+            .build()
+            .unwrap();
+
+        assert_eq!(
+            OutputFormat::Osu.capabilities().losses(&chart),
+            vec!["3 mines".to_string()]
+        );
+        assert_eq!(
+            OutputFormat::Sm.capabilities().losses(&chart),
+            vec!["1 SV points".to_string(), "7K key count".to_string()]
+        );
+        assert!(OutputFormat::Yrox.capabilities().losses(&chart).is_empty());
+    }
+}
@@ -1,14 +1,15 @@
+use std::borrow::Cow;
 use std::path::Path;
 
 use crate::error::{RoxError, RoxResult};
 use crate::model::RoxChart;
 
-use super::super::Decoder;
 #[cfg(feature = "compression")]
 use super::super::formats::RoxCodec;
 use super::super::formats::{
     FnfDecoder, JroxDecoder, OsuDecoder, QuaDecoder, SmDecoder, TaikoDecoder, YroxDecoder,
 };
+use super::super::{ChartSelector, DecodeLimits, DecodeOptions, Decoder};
 use super::types::InputFormat;
 
 /// Decode a chart from a file, auto-detecting the format from the extension.
@@ -31,59 +32,134 @@ use super::types::InputFormat;
 ///
 /// Returns an error if decoding fails or the extension is not recognized.
 pub fn auto_decode(path: impl AsRef<Path>) -> RoxResult<RoxChart> {
+    auto_decode_select(path, &ChartSelector::First)
+}
+
+/// Like [`auto_decode`], but picks the chart with `selector` from formats
+/// that bundle several difficulties in one file (`.sm`).
+///
+/// Every other format holds a single chart, which is returned as-is, except
+/// that [`ChartSelector::ByName`] must match its difficulty name.
+///
+/// # Errors
+///
+/// Returns an error if decoding fails, the extension is not recognized, or
+/// no chart matches the selector.
+pub fn auto_decode_select(path: impl AsRef<Path>, selector: &ChartSelector) -> RoxResult<RoxChart> {
     let path = path.as_ref();
-    let format = InputFormat::from_path(path)?;
+    // `chart.osu.gz` is decoded as `chart.osu`
+    let format = match path.extension() {
+        Some(ext) if ext.eq_ignore_ascii_case("gz") => {
+            InputFormat::from_path(path.file_stem().unwrap_or_default())?
+        }
+        _ => InputFormat::from_path(path)?,
+    };
+    // Attributes parser warnings to the file they came from
+    let _span = tracing::info_span!("decode", format = ?format, path = ?path).entered();
 
     let file = std::fs::File::open(path)?;
     // SAFETY: We assume the file is not modified concurrently.
     let mmap = unsafe { memmap2::Mmap::map(&file)? };
-    let data = &*mmap;
+    let data = gunzip(&mmap, usize::MAX)?;
+    let data = &*data;
 
-    match format {
+    let chart = match format {
         #[cfg(feature = "compression")]
         InputFormat::Rox => RoxCodec::decode(data),
         InputFormat::Jrox => JroxDecoder::decode(data),
         InputFormat::Yrox => YroxDecoder::decode(data),
         InputFormat::Osu | InputFormat::Taiko => decode_osu_by_mode(data),
-        InputFormat::Sm => SmDecoder::decode(data),
+        InputFormat::Sm => return SmDecoder::decode_select(data, selector),
         InputFormat::Qua => QuaDecoder::decode(data),
         InputFormat::Fnf => FnfDecoder::decode(data),
+    }?;
+
+    match selector {
+        ChartSelector::ByName(name)
+            if !chart.metadata.difficulty_name.eq_ignore_ascii_case(name) =>
+        {
+            Err(RoxError::InvalidFormat(format!(
+                "No chart matching {selector:?} found in {}",
+                path.display()
+            )))
+        }
+        _ => Ok(chart),
     }
 }
 
 /// Decode an osu! file by detecting its mode and using the appropriate decoder.
-fn decode_osu_by_mode(data: &[u8]) -> RoxResult<RoxChart> {
-    match detect_osu_mode(data) {
+///
+/// Standard (0) and catch (2) maps are valid files we choose not to support, so
+/// they get a distinct `WrongGameMode` error instead of looking corrupt. Files of
+/// those modes without any hit objects (storyboard-only difficulties) are
+/// reported as invalid, since there is nothing to play in any mode.
+pub(super) fn decode_osu_by_mode(data: &[u8]) -> RoxResult<RoxChart> {
+    let info = detect_osu_mode(data);
+    match info.mode {
         1 => TaikoDecoder::decode(data),
         3 => OsuDecoder::decode(data),
-        mode => Err(RoxError::UnsupportedFormat(format!(
-            "osu! mode {mode} is not supported (only taiko=1 and mania=3)"
-        ))),
+        _ if !info.has_hit_objects => Err(RoxError::InvalidFormat(
+            "osu! beatmap has no hit objects (storyboard-only?)".into(),
+        )),
+        found => Err(RoxError::WrongGameMode { found, expected: 3 }),
     }
 }
 
+/// Game mode summary of an osu! file.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct OsuModeInfo {
+    /// Mode number: 0=std, 1=taiko, 2=catch, 3=mania.
+    pub mode: u8,
+    /// Whether `[HitObjects]` contains at least one entry.
+    ///
+    /// Only checked for standard and catch maps; always `false` for the modes
+    /// we decode, whose files are not scanned past `Mode`.
+    pub has_hit_objects: bool,
+}
+
 /// Detect the osu! game mode from file content.
-/// Returns the mode number: 0=std, 1=taiko, 2=catch, 3=mania.
-/// Defaults to 3 (mania) if not found.
-pub(crate) fn detect_osu_mode(data: &[u8]) -> u8 {
+/// Defaults to mode 3 (mania) if not found.
+///
+/// Stops as soon as the mode is known to be taiko or mania. Only standard and
+/// catch files are scanned on to `[HitObjects]`.
+pub(crate) fn detect_osu_mode(data: &[u8]) -> OsuModeInfo {
+    let decodable = |mode| OsuModeInfo {
+        mode,
+        has_hit_objects: false,
+    };
     let Ok(content) = std::str::from_utf8(data) else {
-        return 3; // Default to mania on invalid UTF-8
+        // Default to mania on invalid UTF-8
+        return decodable(3);
     };
 
-    for line in content.lines() {
-        let line = line.trim();
+    let mut lines = content.lines().map(str::trim);
+    let mut mode = 3; // Default to mania if Mode not found
+    for line in lines.by_ref() {
         if let Some(value) = line.strip_prefix("Mode:")
-            && let Ok(mode) = value.trim().parse::<u8>()
+            && let Ok(parsed) = value.trim().parse::<u8>()
         {
-            return mode;
-        }
-        // Stop parsing after [Metadata] section to avoid scanning entire file
-        if line == "[Metadata] section" {
+            mode = parsed;
             break;
         }
+        // `Mode` lives in [General]; once [Metadata] starts it can no longer appear
+        if line == "[Metadata]" || line == "[HitObjects]" {
+            return decodable(3);
+        }
     }
+    if matches!(mode, 1 | 3) {
+        return decodable(mode);
+    }
+
+    let has_hit_objects = lines
+        .skip_while(|line| *line != "[HitObjects]")
+        .skip(1)
+        .take_while(|line| !line.starts_with('['))
+        .any(|line| !line.is_empty() && !line.starts_with("//"));
 
-    3 // Default to mania if Mode not found
+    OsuModeInfo {
+        mode,
+        has_hit_objects,
+    }
 }
 
 /// Decode chart data with a specific format.
@@ -92,6 +168,7 @@ pub(crate) fn detect_osu_mode(data: &[u8]) -> u8 {
 ///
 /// Returns an error if decoding fails.
 pub fn decode_with_format(data: &[u8], format: InputFormat) -> RoxResult<RoxChart> {
+    let _span = tracing::info_span!("decode", format = ?format).entered();
     match format {
         #[cfg(feature = "compression")]
         InputFormat::Rox => <RoxCodec as Decoder>::decode(data),
@@ -105,10 +182,159 @@ pub fn decode_with_format(data: &[u8], format: InputFormat) -> RoxResult<RoxChar
     }
 }
 
+/// Decode chart data with a specific format and explicit [`DecodeOptions`].
+///
+/// osu!mania and `StepMania` input uses the options while parsing; every
+/// format gets the same [`DecodeOptions::post_process`] cleanup afterwards.
+///
+/// # Errors
+///
+/// Returns an error if decoding fails.
+pub fn decode_with_options(
+    data: &[u8],
+    format: InputFormat,
+    options: &DecodeOptions,
+) -> RoxResult<RoxChart> {
+    match format {
+        InputFormat::Osu => OsuDecoder::decode_with_options(data, options),
+        InputFormat::Sm => SmDecoder::decode_with_options(data, options),
+        _ => {
+            let mut chart = decode_with_format(data, format)?;
+            options.post_process(&mut chart);
+            Ok(chart)
+        }
+    }
+}
+
+/// Decode untrusted data with a specific format, within caller-controlled
+/// [`DecodeLimits`].
+///
+/// ROX, osu!mania and `StepMania` input is checked while decoding (see their
+/// `decode_with_limits`). The other formats are decoded in full and their
+/// note and timing point counts checked afterwards. Every format is decoded
+/// with the default [`DecodeOptions`], so [`DecodeOptions::post_process`] is
+/// left to the caller.
+///
+/// # Errors
+///
+/// Returns [`RoxError::LimitExceeded`] if a limit is exceeded, or an error if
+/// decoding fails.
+pub fn decode_with_limits(
+    data: &[u8],
+    format: InputFormat,
+    limits: &DecodeLimits,
+) -> RoxResult<RoxChart> {
+    limits.check_file_size(data.len())?;
+    let chart = match format {
+        #[cfg(feature = "compression")]
+        InputFormat::Rox => return RoxCodec::decode_with_limits(data, limits),
+        InputFormat::Osu => return OsuDecoder::decode_with_limits(data, limits),
+        InputFormat::Sm => return SmDecoder::decode_with_limits(data, limits),
+        _ => decode_with_format(data, format)?,
+    };
+    limits.check_counts(chart.notes.len(), chart.timing_points.len())?;
+    Ok(chart)
+}
+
+/// Gzip magic bytes, for charts shipped as `.osu.gz`/`.sm.gz`.
+#[cfg(feature = "compression")]
+const GZIP_MAGIC: [u8; 2] = [0x1f, 0x8b];
+
+/// Decompress gzip data (at most `max` bytes), or borrow anything else as-is.
+///
+/// ROX files start with their own magic and are never mistaken for gzip.
+#[cfg(feature = "compression")]
+fn gunzip(data: &[u8], max: usize) -> RoxResult<Cow<'_, [u8]>> {
+    use std::io::Read;
+
+    if !data.starts_with(&GZIP_MAGIC) {
+        return Ok(Cow::Borrowed(data));
+    }
+    let mut decompressed = Vec::new();
+    // One byte past the cap tells "exactly max" apart from "more than max"
+    let cap = u64::try_from(max).unwrap_or(u64::MAX).saturating_add(1);
+    flate2::read::GzDecoder::new(data)
+        .take(cap)
+        .read_to_end(&mut decompressed)?;
+    if decompressed.len() > max {
+        return Err(RoxError::LimitExceeded {
+            what: "decompressed bytes",
+            found: decompressed.len(),
+            max,
+        });
+    }
+    Ok(Cow::Owned(decompressed))
+}
+
+/// Without the `compression` feature gzip input is passed through and fails
+/// format detection like any other unknown data.
+#[cfg(not(feature = "compression"))]
+#[allow(clippy::unnecessary_wraps)]
+fn gunzip(data: &[u8], _max: usize) -> RoxResult<Cow<'_, [u8]>> {
+    Ok(Cow::Borrowed(data))
+}
+
+/// A format's [`Decoder::probe`].
+type Probe = fn(&[u8]) -> bool;
+
+/// Auto-detection order: each format's [`Decoder::probe`] and the format to
+/// decode with when it matches. Taiko is reached through `Osu`, which picks the
+/// decoder from the file's mode.
+const PROBES: &[(Probe, InputFormat)] = &[
+    #[cfg(feature = "compression")]
+    (<RoxCodec as Decoder>::probe, InputFormat::Rox),
+    (<OsuDecoder as Decoder>::probe, InputFormat::Osu),
+    (<SmDecoder as Decoder>::probe, InputFormat::Sm),
+    (<QuaDecoder as Decoder>::probe, InputFormat::Qua),
+    (<FnfDecoder as Decoder>::probe, InputFormat::Fnf),
+    (<JroxDecoder as Decoder>::probe, InputFormat::Jrox),
+    (<YroxDecoder as Decoder>::probe, InputFormat::Yrox),
+];
+
+/// Formats whose probe accepts `data`, in auto-detection order.
+pub(super) fn probe_formats(data: &[u8]) -> Vec<InputFormat> {
+    PROBES
+        .iter()
+        .filter(|(probe, _)| probe(data))
+        .map(|&(_, format)| format)
+        .collect()
+}
+
+/// Decode with the first probed format that succeeds.
+///
+/// If every matching decoder fails, the error of the best match (the first
+/// one) is returned, so e.g. an osu!standard map reports `WrongGameMode`.
+fn decode_probed(data: &[u8]) -> RoxResult<RoxChart> {
+    decode_probed_with(data, |format| match format {
+        InputFormat::Osu | InputFormat::Taiko => decode_osu_by_mode(data),
+        _ => decode_with_format(data, format),
+    })
+}
+
+fn decode_probed_with(
+    data: &[u8],
+    mut decode: impl FnMut(InputFormat) -> RoxResult<RoxChart>,
+) -> RoxResult<RoxChart> {
+    let mut first_error = None;
+    for format in probe_formats(data) {
+        match decode(format) {
+            Ok(chart) => return Ok(chart),
+            Err(e) => {
+                tracing::debug!("Failed to auto-decode as {:?}: {}", format, e);
+                first_error.get_or_insert(e);
+            }
+        }
+    }
+
+    Err(first_error.unwrap_or_else(|| {
+        RoxError::InvalidFormat("Failed to decode chart: no format recognized".into())
+    }))
+}
+
 /// Decode a chart from a string, auto-detecting the format.
 ///
-/// Attempts to decode the chart data from the provided string using all available decoders
-/// until one succeeds. Tries formats in the following order:
+/// Each format's [`Decoder::probe`] sniffs the content first (header lines,
+/// characteristic keys) and only matching decoders are run, in this order:
 /// 1. Osu (with mode detection)
 /// 2. `StepMania`
 /// 3. Quaver (YAML)
@@ -126,55 +352,17 @@ pub fn decode_with_format(data: &[u8], format: InputFormat) -> RoxResult<RoxChar
 ///
 /// # Errors
 ///
-/// Returns an error if all decoders fail to parse the input.
+/// Returns an error if no format matches, or the first matching decoder's
+/// error if all of them fail.
 pub fn from_string(data: &str) -> RoxResult<RoxChart> {
-    let bytes = data.as_bytes();
-
-    // Try osu format (with mode detection)
-    match decode_osu_by_mode(bytes) {
-        Ok(chart) => return Ok(chart),
-        Err(e) => tracing::debug!("Failed to auto-decode as osu: {}", e),
-    }
-
-    // Try StepMania
-    match SmDecoder::decode(bytes) {
-        Ok(chart) => return Ok(chart),
-        Err(e) => tracing::debug!("Failed to auto-decode as StepMania: {}", e),
-    }
-
-    // Try Quaver
-    match QuaDecoder::decode(bytes) {
-        Ok(chart) => return Ok(chart),
-        Err(e) => tracing::debug!("Failed to auto-decode as Quaver: {}", e),
-    }
-
-    // Try FNF
-    match FnfDecoder::decode(bytes) {
-        Ok(chart) => return Ok(chart),
-        Err(e) => tracing::debug!("Failed to auto-decode as FNF: {}", e),
-    }
-
-    // Try JROX
-    match JroxDecoder::decode(bytes) {
-        Ok(chart) => return Ok(chart),
-        Err(e) => tracing::debug!("Failed to auto-decode as JROX: {}", e),
-    }
-
-    // Try YROX
-    match YroxDecoder::decode(bytes) {
-        Ok(chart) => return Ok(chart),
-        Err(e) => tracing::debug!("Failed to auto-decode as YROX: {}", e),
-    }
-
-    Err(RoxError::InvalidFormat(
-        "Failed to decode chart: no format decoder succeeded".into(),
-    ))
+    decode_probed(data.as_bytes())
 }
 
 /// Decode a chart from bytes, auto-detecting the format.
 ///
-/// Attempts to decode the chart data from the provided bytes using all available decoders
-/// until one succeeds. Tries formats in the following order:
+/// Each format's [`Decoder::probe`] sniffs the content first (magic bytes,
+/// header lines, characteristic keys) and only matching decoders are run, in
+/// this order:
 /// 1. ROX binary format
 /// 2. Osu (with mode detection)
 /// 3. `StepMania`
@@ -193,52 +381,29 @@ pub fn from_string(data: &str) -> RoxResult<RoxChart> {
 ///
 /// # Errors
 ///
-/// Returns an error if all decoders fail to parse the input.
+/// Returns an error if no format matches, or the first matching decoder's
+/// error if all of them fail.
 pub fn from_bytes(data: &[u8]) -> RoxResult<RoxChart> {
-    // Try ROX binary format first
-    #[cfg(feature = "compression")]
-    match RoxCodec::decode(data) {
-        Ok(chart) => return Ok(chart),
-        Err(e) => tracing::debug!("Failed to auto-decode as ROX: {}", e),
-    }
-
-    // Try osu format (with mode detection)
-    match decode_osu_by_mode(data) {
-        Ok(chart) => return Ok(chart),
-        Err(e) => tracing::debug!("Failed to auto-decode as osu: {}", e),
-    }
-
-    // Try StepMania
-    match SmDecoder::decode(data) {
-        Ok(chart) => return Ok(chart),
-        Err(e) => tracing::debug!("Failed to auto-decode as StepMania: {}", e),
-    }
-
-    // Try Quaver
-    match QuaDecoder::decode(data) {
-        Ok(chart) => return Ok(chart),
-        Err(e) => tracing::debug!("Failed to auto-decode as Quaver: {}", e),
-    }
-
-    // Try FNF
-    match FnfDecoder::decode(data) {
-        Ok(chart) => return Ok(chart),
-        Err(e) => tracing::debug!("Failed to auto-decode as FNF: {}", e),
-    }
-
-    // Try JROX
-    match JroxDecoder::decode(data) {
-        Ok(chart) => return Ok(chart),
-        Err(e) => tracing::debug!("Failed to auto-decode as JROX: {}", e),
-    }
-
-    // Try YROX
-    match YroxDecoder::decode(data) {
-        Ok(chart) => return Ok(chart),
-        Err(e) => tracing::debug!("Failed to auto-decode as YROX: {}", e),
-    }
+    decode_probed(&gunzip(data, usize::MAX)?)
+}
 
-    Err(RoxError::InvalidFormat(
-        "Failed to decode chart: no format decoder succeeded".into(),
-    ))
+/// Like [`from_bytes`], but for untrusted data: every candidate decoder runs
+/// through [`decode_with_limits`], with the default [`DecodeOptions`].
+///
+/// # Errors
+///
+/// Returns [`RoxError::LimitExceeded`] if the input is too large or the
+/// chart exceeds a limit, otherwise the same errors as [`from_bytes`].
+pub fn from_bytes_with_limits(data: &[u8], limits: &DecodeLimits) -> RoxResult<RoxChart> {
+    limits.check_file_size(data.len())?;
+    let data = &*gunzip(data, limits.max_decompressed_bytes)?;
+    decode_probed_with(data, |format| match format {
+        // Taiko maps (and osu! modes we reject) go through mode detection
+        InputFormat::Osu if detect_osu_mode(data).mode != 3 => {
+            let chart = decode_osu_by_mode(data)?;
+            limits.check_counts(chart.notes.len(), chart.timing_points.len())?;
+            Ok(chart)
+        }
+        _ => decode_with_limits(data, format, limits),
+    })
 }
@@ -1,16 +1,18 @@
 use std::path::Path;
 
-use crate::error::RoxResult;
+use crate::error::{RoxError, RoxResult};
 use crate::model::RoxChart;
 
+use super::super::DecodeOptions;
 use super::super::Encoder;
 #[cfg(feature = "compression")]
 use super::super::formats::RoxCodec;
 use super::super::formats::{
     FnfEncoder, JroxEncoder, OsuEncoder, QuaEncoder, SmEncoder, YroxEncoder,
 };
-use super::decode::auto_decode;
-use super::types::OutputFormat;
+use super::capabilities::ConversionReport;
+use super::decode::{auto_decode, decode_with_options};
+use super::types::{InputFormat, OutputFormat};
 
 /// Encode a chart to a file, auto-detecting the format from the extension.
 ///
@@ -29,6 +31,11 @@ use super::types::OutputFormat;
 pub fn auto_encode(chart: &RoxChart, path: impl AsRef<Path>) -> RoxResult<()> {
     let path = path.as_ref();
     let format = OutputFormat::from_path(path)?;
+    let _span = tracing::info_span!("encode", format = ?format, path = ?path).entered();
+
+    for loss in format.capabilities().losses(chart) {
+        tracing::warn!("Writing {} as {format:?} drops {loss}", path.display());
+    }
 
     let data = match format {
         #[cfg(feature = "compression")]
@@ -63,6 +70,83 @@ pub fn encode_with_format(chart: &RoxChart, format: OutputFormat) -> RoxResult<V
     }
 }
 
+/// Encode a chart with a specific format and report what the format could
+/// not store.
+///
+/// The report is computed from the format's
+/// [`capabilities`](OutputFormat::capabilities), so frontends can show
+/// e.g. "3 mines dropped" next to the result.
+///
+/// # Errors
+///
+/// Returns an error if encoding fails.
+pub fn convert_with_report(
+    chart: &RoxChart,
+    format: OutputFormat,
+) -> RoxResult<(Vec<u8>, ConversionReport)> {
+    let report = format.capabilities().report(chart);
+    let data = encode_with_format(chart, format)?;
+    Ok((data, report))
+}
+
+/// Encode a chart with a specific format, then decode the output again and
+/// check that no notes went missing or appeared.
+///
+/// The expected count allows for what the format is documented to drop:
+/// fakes in formats without them, and mines in FNF. osu! and Quaver write
+/// mines and bursts as notes of their own, so those still count. Meant as a
+/// safety net in CI, or for callers that can't tolerate silent data loss;
+/// it costs a full decode on top of the encode.
+///
+/// # Errors
+///
+/// Returns [`RoxError::NoteCountMismatch`] if the re-decoded chart has a
+/// different number of notes, or an error if encoding or decoding fails.
+pub fn convert_checked(chart: &RoxChart, format: OutputFormat) -> RoxResult<Vec<u8>> {
+    let data = encode_with_format(chart, format)?;
+
+    let counts = chart.note_counts();
+    let capabilities = format.capabilities();
+    let mut expected = chart.notes.len();
+    if !capabilities.supports_fakes {
+        expected -= counts.fakes;
+    }
+    if format == OutputFormat::Fnf {
+        expected -= counts.mines;
+    }
+
+    // Fakes are only written by formats that can read them back
+    let options = DecodeOptions {
+        keep_fakes: true,
+        ..DecodeOptions::default()
+    };
+    let found = decode_with_options(&data, reader_for(format), &options)?
+        .notes
+        .len();
+    if found != expected {
+        return Err(RoxError::NoteCountMismatch {
+            format: format.extension(),
+            expected,
+            found,
+        });
+    }
+    Ok(data)
+}
+
+/// The decoder for files written as `format`.
+fn reader_for(format: OutputFormat) -> InputFormat {
+    match format {
+        #[cfg(feature = "compression")]
+        OutputFormat::Rox => InputFormat::Rox,
+        OutputFormat::Jrox => InputFormat::Jrox,
+        OutputFormat::Yrox => InputFormat::Yrox,
+        OutputFormat::Osu => InputFormat::Osu,
+        OutputFormat::Sm => InputFormat::Sm,
+        OutputFormat::Qua => InputFormat::Qua,
+        OutputFormat::Fnf => InputFormat::Fnf,
+    }
+}
+
 /// Convert a file from one format to another, auto-detecting both formats.
 ///
 /// # Example
@@ -81,3 +165,30 @@ pub fn auto_convert(input: impl AsRef<Path>, output: impl AsRef<Path>) -> RoxRes
     let chart = auto_decode(input)?;
     auto_encode(&chart, output)
 }
+
+/// Convert many files, reporting `(files_done, total_files)` after each one.
+///
+/// Every job is attempted even if an earlier one fails; the returned results
+/// are in the same order as `jobs`.
+///
+/// # Example
+/// ```ignore
+/// use rox::codec::batch_convert;
+///
+/// let jobs = [("a.osu", "a.rox"), ("b.sm", "b.rox")];
+/// let results = batch_convert(&jobs, |done, total| println!("{done}/{total}"));
+/// ```
+pub fn batch_convert<I: AsRef<Path>, O: AsRef<Path>>(
+    jobs: &[(I, O)],
+    mut progress: impl FnMut(usize, usize),
+) -> Vec<RoxResult<()>> {
+    let total = jobs.len();
+    jobs.iter()
+        .enumerate()
+        .map(|(i, (input, output))| {
+            let result = auto_convert(input, output);
+            progress(i + 1, total);
+            result
+        })
+        .collect()
+}
@@ -2,13 +2,21 @@
 //!
 //! Provides automatic decoding and encoding based on file extensions.
 
+mod capabilities;
 mod decode;
 mod encode;
 mod types;
 
-pub use decode::{auto_decode, decode_with_format, from_bytes, from_string};
-pub use encode::{auto_convert, auto_encode, encode_with_format};
-pub use types::{InputFormat, OutputFormat};
+pub use capabilities::{ConversionReport, FormatCapabilities};
+pub use decode::{
+    auto_decode, auto_decode_select, decode_with_format, decode_with_limits, decode_with_options,
+    from_bytes, from_bytes_with_limits, from_string,
+};
+pub use encode::{
+    auto_convert, auto_encode, batch_convert, convert_checked, convert_with_report,
+    encode_with_format,
+};
+pub use types::{InputFormat, OutputFormat, supported_input_formats, supported_output_formats};
 
 #[cfg(test)]
 mod tests {
@@ -35,6 +43,63 @@ mod tests {
         assert!(InputFormat::from_extension("mp3").is_err());
     }
 
+    #[test]
+    fn test_supported_formats() {
+        let inputs: Vec<&str> = supported_input_formats().iter().map(|&(n, _)| n).collect();
+        let outputs: Vec<&str> = supported_output_formats().iter().map(|&(n, _)| n).collect();
+        assert!(inputs.contains(&"osu"));
+        assert!(outputs.contains(&"osu"));
+        assert_eq!(inputs.contains(&"rox"), cfg!(feature = "compression"));
+        assert_eq!(outputs.contains(&"rox"), cfg!(feature = "compression"));
+        for &(name, format) in supported_input_formats() {
+            assert_eq!(InputFormat::from_extension(name).unwrap(), format);
+        }
+    }
+
+    #[test]
+    #[cfg(feature = "compression")]
+    fn test_convert_checked_rox_qua_rox() {
+        use crate::codec::{Decoder, Encoder, formats::RoxCodec};
+        use crate::model::{Note, RoxChartBuilder};
+
+        let mut builder = RoxChartBuilder::new(7).bpm(0, 150.0);
+        for i in 0..70u8 {
+            let time_us = i64::from(i) * 100_000;
+            builder = if i % 5 == 0 {
+                builder.hold(time_us, 50_000, i % 7)
+            } else {
+                builder.tap(time_us, i % 7)
+            };
+        }
+        let rox = RoxCodec::encode(&builder.build().unwrap()).unwrap();
+        let chart = RoxCodec::decode(&rox).unwrap();
+
+        let qua = convert_checked(&chart, OutputFormat::Qua).unwrap();
+        let back = decode_with_format(&qua, InputFormat::Qua).unwrap();
+        let rox = convert_checked(&back, OutputFormat::Rox).unwrap();
+        assert_eq!(RoxCodec::decode(&rox).unwrap().note_count(), 70);
+
+        // FNF drops mines by design, which isn't a mismatch
+        let mut with_mine = chart.clone();
+        with_mine.set_key_count(4);
+        with_mine.notes.retain(|n| n.column < 4);
+        with_mine.notes.push(Note::mine(7_050_000, 0));
+        assert!(convert_checked(&with_mine, OutputFormat::Fnf).is_ok());
+    }
+
+    #[test]
+    fn test_output_format_extension_and_mime() {
+        assert_eq!(OutputFormat::Osu.extension(), "osu");
+        assert_eq!(OutputFormat::Fnf.extension(), "json");
+        assert_eq!(OutputFormat::Fnf.mime_type(), "application/json");
+        assert_eq!(OutputFormat::Osu.mime_type(), "text/plain");
+        #[cfg(feature = "compression")]
+        assert_eq!(OutputFormat::Rox.mime_type(), "application/octet-stream");
+        for &(name, format) in supported_output_formats() {
+            assert_eq!(format.extension(), name);
+        }
+    }
+
     #[test]
     fn test_output_format_detection() {
         assert_eq!(
@@ -50,6 +115,14 @@ mod tests {
             OutputFormat::from_extension("rox").unwrap(),
             OutputFormat::Rox
         );
+        assert_eq!(
+            OutputFormat::from_extension("jrox").unwrap(),
+            OutputFormat::Jrox
+        );
+        assert_eq!(
+            OutputFormat::from_extension("yrox").unwrap(),
+            OutputFormat::Yrox
+        );
         assert!(OutputFormat::from_extension("mp3").is_err());
     }
 
@@ -75,6 +148,100 @@ mod tests {
         assert_eq!(chart.key_count(), 4);
     }
 
+    #[test]
+    fn test_auto_decode_select() {
+        use crate::codec::ChartSelector;
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("two.sm");
+        let sm = String::from_utf8(crate::test_utils::get_test_asset("stepmania/4k.sm")).unwrap();
+        let hard = "\n#NOTES:\n     dance-single:\n     :\n     Challenge:\n     99:\n     0,0,0,0,0:\n1111\n0000\n0000\n0000\n;\n";
+        std::fs::write(&path, format!("{sm}{hard}")).unwrap();
+
+        let hardest = auto_decode_select(&path, &ChartSelector::Hardest).unwrap();
+        assert_eq!(hardest.metadata.difficulty_name, "Challenge");
+        assert_eq!(hardest.notes.len(), 4);
+        assert_ne!(auto_decode(&path).unwrap(), hardest);
+
+        // Single-chart formats only check the name
+        let osu = dir.path().join("test.osu");
+        std::fs::write(&osu, crate::test_utils::get_test_asset("osu/mania_7k.osu")).unwrap();
+        assert!(auto_decode_select(&osu, &ChartSelector::Hardest).is_ok());
+        assert!(auto_decode_select(&osu, &ChartSelector::ByName("Nope".into())).is_err());
+    }
+
+    /// Records the name and fields of every span that is entered.
+    #[derive(Default)]
+    struct SpanRecorder {
+        spans: std::sync::Mutex<Vec<String>>,
+        entered: std::sync::Mutex<Vec<String>>,
+    }
+
+    impl tracing::Subscriber for SpanRecorder {
+        fn enabled(&self, _: &tracing::Metadata<'_>) -> bool {
+            true
+        }
+
+        fn new_span(&self, span: &tracing::span::Attributes<'_>) -> tracing::span::Id {
+            struct Fields(String);
+            impl tracing::field::Visit for Fields {
+                fn record_debug(
+                    &mut self,
+                    field: &tracing::field::Field,
+                    value: &dyn std::fmt::Debug,
+                ) {
+                    use std::fmt::Write;
+                    let _ = write!(self.0, " {}={value:?}", field.name());
+                }
+            }
+            let mut fields = Fields(span.metadata().name().to_string());
+            span.record(&mut fields);
+            let mut spans = self.spans.lock().unwrap();
+            spans.push(fields.0);
+            tracing::span::Id::from_u64(spans.len() as u64)
+        }
+
+        fn record(&self, _: &tracing::span::Id, _: &tracing::span::Record<'_>) {}
+
+        fn record_follows_from(&self, _: &tracing::span::Id, _: &tracing::span::Id) {}
+
+        fn event(&self, _: &tracing::Event<'_>) {}
+
+        fn enter(&self, span: &tracing::span::Id) {
+            let index = usize::try_from(span.into_u64()).unwrap() - 1;
+            let name = self.spans.lock().unwrap()[index].clone();
+            self.entered.lock().unwrap().push(name);
+        }
+
+        fn exit(&self, _: &tracing::span::Id) {}
+    }
+
+    #[test]
+    fn test_decode_and_encode_spans() {
+        let recorder = std::sync::Arc::new(SpanRecorder::default());
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("test.osu");
+        let data = crate::test_utils::get_test_asset("osu/mania_7k.osu");
+
+        tracing::subscriber::with_default(recorder.clone(), || {
+            decode_with_format(&data, InputFormat::Osu).unwrap();
+            assert!(auto_decode(dir.path().join("missing.sm")).is_err());
+            auto_encode(&RoxChart::new(4), &path).unwrap();
+        });
+
+        let entered = recorder.entered.lock().unwrap();
+        assert!(entered.contains(&"decode format=Osu".to_string()));
+        assert!(
+            entered
+                .iter()
+                .any(|s| s.starts_with("decode format=Sm path=") && s.contains("missing.sm"))
+        );
+        assert!(
+            entered
+                .iter()
+                .any(|s| s.starts_with("encode format=Osu path="))
+        );
+    }
+
     #[test]
     fn test_auto_encode_osu() {
         let dir = tempdir().unwrap();
@@ -102,17 +269,245 @@ mod tests {
         assert_eq!(chart.key_count(), 7);
     }
 
+    #[test]
+    #[cfg(feature = "compression")]
+    fn test_decode_gzipped_osu() {
+        use crate::codec::DecodeLimits;
+        use std::io::Write;
+
+        let data = crate::test_utils::get_test_asset("osu/mania_7k.osu");
+        let mut gz = flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::default());
+        gz.write_all(&data).unwrap();
+        let gzipped = gz.finish().unwrap();
+
+        let chart = from_bytes(&gzipped).unwrap();
+        assert_eq!(chart, from_bytes(&data).unwrap());
+
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("test.osu.gz");
+        std::fs::write(&path, &gzipped).unwrap();
+        assert_eq!(auto_decode(&path).unwrap(), chart);
+
+        let limits = DecodeLimits {
+            max_decompressed_bytes: data.len() - 1,
+            ..DecodeLimits::default()
+        };
+        assert!(matches!(
+            from_bytes_with_limits(&gzipped, &limits),
+            Err(crate::error::RoxError::LimitExceeded { .. })
+        ));
+    }
+
+    #[test]
+    fn test_from_bytes_probes_osu_only() {
+        let data = crate::test_utils::get_test_asset("osu/mania_7k.osu");
+        // Only the osu decoder runs; the sm parser is never attempted
+        assert_eq!(super::decode::probe_formats(&data), vec![InputFormat::Osu]);
+        assert!(from_bytes(&data).is_ok());
+    }
+
+    #[test]
+    fn test_probe_formats() {
+        use super::decode::probe_formats;
+
+        let sm = crate::test_utils::get_test_asset("stepmania/4k.sm");
+        assert_eq!(probe_formats(&sm), vec![InputFormat::Sm]);
+
+        let qua = crate::test_utils::get_test_asset("quaver/4K.qua");
+        assert_eq!(probe_formats(&qua), vec![InputFormat::Qua]);
+
+        let fnf = crate::test_utils::get_test_asset("fnf/test-song.json");
+        assert_eq!(probe_formats(&fnf), vec![InputFormat::Fnf]);
+
+        assert!(probe_formats(b"not a chart").is_empty());
+        assert!(from_bytes(b"not a chart").is_err());
+    }
+
+    #[test]
+    fn test_from_bytes_reports_wrong_mode() {
+        let data = b"osu file format v14\n\n[General]\nMode: 0\n\n[HitObjects]\n256,192,1000,1,0,0:0:0:0:\n";
+        assert!(matches!(
+            from_bytes(data),
+            Err(crate::RoxError::WrongGameMode { found: 0, .. })
+        ));
+    }
+
+    #[test]
+    fn test_from_bytes_with_limits() {
+        use crate::codec::DecodeLimits;
+        let sm = crate::test_utils::get_test_asset("stepmania/4k.sm");
+        let chart = from_bytes_with_limits(&sm, &DecodeLimits::default()).unwrap();
+        assert_eq!(chart, from_bytes(&sm).unwrap());
+
+        let limits = DecodeLimits {
+            max_notes: 1,
+            ..DecodeLimits::default()
+        };
+        assert!(matches!(
+            from_bytes_with_limits(&sm, &limits),
+            Err(crate::RoxError::LimitExceeded { found: 2, .. })
+        ));
+
+        // Formats without parse-time checks are counted after decoding
+        let qua = crate::test_utils::get_test_asset("quaver/4K.qua");
+        assert!(matches!(
+            decode_with_limits(&qua, InputFormat::Qua, &limits),
+            Err(crate::RoxError::LimitExceeded { what: "notes", .. })
+        ));
+    }
+
     #[test]
     fn test_detect_osu_mode() {
         use super::decode::detect_osu_mode;
         let mania_data = b"Mode: 3\n[Metadata]";
-        assert_eq!(detect_osu_mode(mania_data), 3);
+        assert_eq!(detect_osu_mode(mania_data).mode, 3);
 
         let taiko_data = b"Mode: 1\n[Metadata]";
-        assert_eq!(detect_osu_mode(taiko_data), 1);
+        assert_eq!(detect_osu_mode(taiko_data).mode, 1);
 
         let empty_data = b"";
-        assert_eq!(detect_osu_mode(empty_data), 3); // Default
+        assert_eq!(detect_osu_mode(empty_data).mode, 3); // Default
+
+        // Mode after [Metadata] is not a [General] field and must be ignored
+        let late_mode = b"[General]\n[Metadata]\nMode: 1\n";
+        assert_eq!(detect_osu_mode(late_mode).mode, 3);
+    }
+
+    #[test]
+    fn test_detect_osu_hit_objects() {
+        use super::decode::detect_osu_mode;
+        let with_objects = b"[General]\nMode: 0\n\n[HitObjects]\n256,192,1000,1,0,0:0:0:0:\n";
+        assert!(detect_osu_mode(with_objects).has_hit_objects);
+
+        let storyboard_only = b"[General]\nMode: 0\n\n[HitObjects]\n\n";
+        assert!(!detect_osu_mode(storyboard_only).has_hit_objects);
+
+        // Taiko and mania are decided at `Mode`; nothing after it is read
+        let mania = b"[General]\nMode: 3\n\n[HitObjects]\n64,192,1000,1,0,0:0:0:0:\n";
+        assert_eq!(
+            detect_osu_mode(mania),
+            super::decode::OsuModeInfo {
+                mode: 3,
+                has_hit_objects: false
+            }
+        );
+    }
+
+    #[test]
+    fn test_decode_osu_by_mode_standard_is_wrong_mode() {
+        let data = b"osu file format v14\n\n[General]\nMode: 0\n\n[HitObjects]\n256,192,1000,1,0,0:0:0:0:\n";
+        let result = super::decode::decode_osu_by_mode(data);
+        assert!(matches!(
+            result,
+            Err(crate::RoxError::WrongGameMode {
+                found: 0,
+                expected: 3
+            })
+        ));
+    }
+
+    #[test]
+    fn test_decode_osu_by_mode_catch_is_wrong_mode() {
+        let data = b"osu file format v14\n\n[General]\nMode: 2\n\n[HitObjects]\n256,192,1000,1,0,0:0:0:0:\n";
+        let result = super::decode::decode_osu_by_mode(data);
+        assert!(matches!(
+            result,
+            Err(crate::RoxError::WrongGameMode {
+                found: 2,
+                expected: 3
+            })
+        ));
+    }
+
+    #[test]
+    fn test_osu_storyboard_only_is_invalid() {
+        let data = b"osu file format v14\n\n[General]\nMode: 0\n\n[HitObjects]\n";
+        let result = super::decode::decode_osu_by_mode(data);
+        assert!(matches!(result, Err(crate::RoxError::InvalidFormat(_))));
+    }
+
+    /// rox -> text -> rox must reproduce the chart exactly.
+    #[cfg(feature = "compression")]
+    fn assert_text_roundtrip(format: OutputFormat, input: InputFormat) {
+        use crate::codec::{Decoder, Encoder, RoxCodec};
+
+        let mut original = crate::model::RoxChartBuilder::new(7)
+            .title("宙の旋律")
+            .artist("Artist")
+            .bpm(0, 174.5)
+            .sv(2_000_000, 0.75)
+            .tap(1_000_000, 0)
+            .hold(1_250_000, 500_000, 3)
+            .burst(1_500_000, 300_000, 6)
+            .mine(2_000_000, 1)
+            .build()
+            .unwrap();
+        original.metadata.title_romanized = Some("Sora no Senritsu".into());
+        original.metadata.overall_difficulty = Some(8.3);
+        original.metadata.tags = vec!["tech".into(), "stream".into()];
+        original.metadata.bookmarks_us = vec![1_000_000];
+        original.notes[0].accent = true;
+        original.notes[0].hitsound_index = Some(0);
+        original
+            .hitsounds
+            .push(crate::model::Hitsound::new("kick.wav"));
+        let rox = RoxCodec::decode(&RoxCodec::encode(&original).unwrap()).unwrap();
+
+        let text = encode_with_format(&rox, format).unwrap();
+        let decoded = decode_with_format(&text, input).unwrap();
+        let back = RoxCodec::decode(&RoxCodec::encode(&decoded).unwrap()).unwrap();
+
+        assert_eq!(back, rox);
+    }
+
+    #[test]
+    #[cfg(feature = "compression")]
+    fn test_rox_jrox_roundtrip() {
+        assert_text_roundtrip(OutputFormat::Jrox, InputFormat::Jrox);
+    }
+
+    #[test]
+    #[cfg(feature = "compression")]
+    fn test_rox_yrox_roundtrip() {
+        assert_text_roundtrip(OutputFormat::Yrox, InputFormat::Yrox);
+    }
+
+    #[test]
+    fn test_auto_encode_text_formats() {
+        let dir = tempdir().unwrap();
+        let mut chart = RoxChart::new(4);
+        chart.metadata.title = "Text".into();
+
+        for ext in ["jrox", "yrox"] {
+            let path = dir.path().join(format!("output.{ext}"));
+            auto_encode(&chart, &path).unwrap();
+            assert_eq!(auto_decode(&path).unwrap(), chart);
+        }
+    }
+
+    #[test]
+    fn test_batch_convert_progress() {
+        let dir = tempdir().unwrap();
+        let input = dir.path().join("input.osu");
+        std::fs::write(
+            &input,
+            crate::test_utils::get_test_asset("osu/mania_7k.osu"),
+        )
+        .unwrap();
+
+        let jobs = [
+            (input.clone(), dir.path().join("a.sm")),
+            (dir.path().join("missing.osu"), dir.path().join("b.sm")),
+            (input, dir.path().join("c.qua")),
+        ];
+        let mut reports = Vec::new();
+        let results = batch_convert(&jobs, |done, total| reports.push((done, total)));
+
+        assert_eq!(reports, vec![(1, 3), (2, 3), (3, 3)]);
+        assert!(results[0].is_ok());
+        assert!(results[1].is_err());
+        assert!(results[2].is_ok());
+        assert!(dir.path().join("c.qua").exists());
     }
 
     #[test]
@@ -129,4 +524,34 @@ mod tests {
         let content = std::fs::read_to_string(&output).unwrap();
         assert!(content.contains("#TITLE:"));
     }
+
+    #[test]
+    fn test_convert_with_report() {
+        let chart = crate::model::RoxChartBuilder::new(4)
+            .bpm(0, 120.0)
+            .sv(500_000, 0.5)
+            .sv(1_000_000, 1.0)
+            .tap(0, 0)
+            .mine(250_000, 1)
+            .mine(750_000, 2)
+            .build()
+            .unwrap();
+
+        let (data, report) = convert_with_report(&chart, OutputFormat::Osu).unwrap();
+        assert!(
+            String::from_utf8(data)
+                .unwrap()
+                .starts_with("osu file format")
+        );
+        assert_eq!(report.dropped_mines, 2);
+        assert_eq!(report.dropped_sv_points, 0);
+        assert!(!report.key_count_changed);
+
+        let (_, report) = convert_with_report(&chart, OutputFormat::Sm).unwrap();
+        assert_eq!(report.dropped_mines, 0);
+        assert_eq!(report.dropped_sv_points, 2);
+
+        let (_, report) = convert_with_report(&chart, OutputFormat::Jrox).unwrap();
+        assert!(report.is_lossless());
+    }
 }
@@ -43,6 +43,23 @@ pub enum OutputFormat {
     Fnf,
 }
 
+/// Canonical names (file extensions) of the formats this build can decode,
+/// including ROX when the `compression` feature is enabled.
+///
+/// Meant for bindings and CLIs to enumerate formats instead of keeping their
+/// own lists.
+#[must_use]
+pub fn supported_input_formats() -> &'static [(&'static str, InputFormat)] {
+    InputFormat::EXTENSIONS
+}
+
+/// Canonical names (file extensions) of the formats this build can encode,
+/// including ROX when the `compression` feature is enabled.
+#[must_use]
+pub fn supported_output_formats() -> &'static [(&'static str, OutputFormat)] {
+    OutputFormat::EXTENSIONS
+}
+
 impl InputFormat {
     /// All supported input extensions.
     pub const EXTENSIONS: &'static [(&'static str, Self)] = &[
@@ -131,4 +148,36 @@ impl OutputFormat {
             .ok_or_else(|| RoxError::InvalidFormat("No file extension".into()))?;
         Self::from_extension(ext)
     }
+
+    /// Preferred file extension, without the dot (the reverse of
+    /// [`from_extension`](Self::from_extension)).
+    #[must_use]
+    pub fn extension(&self) -> &'static str {
+        match self {
+            #[cfg(feature = "compression")]
+            Self::Rox => "rox",
+            Self::Jrox => "jrox",
+            Self::Yrox => "yrox",
+            Self::Osu => "osu",
+            Self::Sm => "sm",
+            Self::Qua => "qua",
+            Self::Fnf => "json",
+        }
+    }
+
+    /// MIME type to serve encoded output with, e.g. as an HTTP
+    /// `Content-Type`.
+    ///
+    /// The text formats without a registered type (`.osu`, `.sm`) are
+    /// `text/plain`; Quaver files and YAML ROX are `application/yaml`.
+    #[must_use]
+    pub fn mime_type(&self) -> &'static str {
+        match self {
+            #[cfg(feature = "compression")]
+            Self::Rox => "application/octet-stream",
+            Self::Jrox | Self::Fnf => "application/json",
+            Self::Yrox | Self::Qua => "application/yaml",
+            Self::Osu | Self::Sm => "text/plain",
+        }
+    }
 }
@@ -10,7 +10,35 @@ use super::types::{FnfChart, FnfSide};
 /// Decoder for Friday Night Funkin' charts.
 pub struct FnfDecoder;
 
+/// Difficulty suffixes of FNF chart files (`song-hard.json`), with the name
+/// to give the decoded chart. A file without one is the Normal chart.
+const DIFFICULTY_SUFFIXES: [(&str, &str); 3] =
+    [("easy", "Easy"), ("normal", "Normal"), ("hard", "Hard")];
+
 impl FnfDecoder {
+    /// Decode a song's difficulty files (`song-easy.json`, `song.json`,
+    /// `song-hard.json`) given as `(file_name, data)` pairs, one chart per
+    /// file in the same order.
+    ///
+    /// Each chart's `difficulty_name` comes from its file name's suffix;
+    /// a name without a known suffix is the Normal difficulty. Directories
+    /// in the names are ignored. Player notes are decoded, as with
+    /// [`decode`](Decoder::decode).
+    ///
+    /// # Errors
+    ///
+    /// Returns the error of the first file that fails to parse.
+    pub fn decode_difficulty_set(files: &[(&str, &[u8])]) -> RoxResult<Vec<RoxChart>> {
+        files
+            .iter()
+            .map(|&(name, data)| {
+                let mut chart = Self::decode_with_side(data, FnfSide::Player)?;
+                chart.metadata.difficulty_name = difficulty_from_file_name(name).into();
+                Ok(chart)
+            })
+            .collect()
+    }
+
     /// Decode with a specific side selection.
     ///
     /// # Errors
@@ -48,27 +76,31 @@ impl FnfDecoder {
             ..Default::default()
         };
 
-        // Track current BPM for timing points
+        // BPM changes land on section boundaries. Section start times are
+        // accumulated from section lengths, since the first note of a section
+        // is not necessarily on its boundary.
         let mut current_bpm = fnf.song.bpm;
-        let mut added_initial_bpm = false;
+        let mut section_start_ms = 0.0;
+        chart.timing_points.push(TimingPoint::bpm(0, current_bpm));
 
         // Process each section
         for section in &fnf.song.notes {
             // Handle BPM changes
             if section.change_bpm && section.bpm > 0.0 {
-                // Find the first note time in this section for the timing point
-                if let Some(first_note) = section.section_notes.first() {
-                    #[allow(clippy::cast_possible_truncation)]
-                    let time_us = (first_note.time_ms() * 1000.0) as i64;
-                    chart
+                current_bpm = section.bpm;
+                #[allow(clippy::cast_possible_truncation)]
+                let time_us = (section_start_ms * 1000.0) as i64;
+                match chart.timing_points.last_mut() {
+                    Some(tp) if tp.time_us == time_us => tp.bpm = current_bpm,
+                    _ => chart
                         .timing_points
-                        .push(TimingPoint::bpm(time_us, section.bpm));
-                    current_bpm = section.bpm;
+                        .push(TimingPoint::bpm(time_us, current_bpm)),
                 }
-            } else if !added_initial_bpm {
-                // Add initial BPM at time 0
-                chart.timing_points.push(TimingPoint::bpm(0, current_bpm));
-                added_initial_bpm = true;
+            }
+            if current_bpm > 0.0 {
+                // 4 steps per beat
+                section_start_ms +=
+                    f64::from(section.length_in_steps) / 4.0 * 60_000.0 / f64::from(current_bpm);
             }
 
             // Process notes in this section
@@ -128,11 +160,6 @@ impl FnfDecoder {
             }
         }
 
-        // Add initial BPM if no sections had notes
-        if !added_initial_bpm {
-            chart.timing_points.push(TimingPoint::bpm(0, fnf.song.bpm));
-        }
-
         // Sort notes and timing points by time
         chart.notes.sort_by_key(|n| n.time_us);
         chart.timing_points.sort_by_key(|tp| tp.time_us);
@@ -141,7 +168,28 @@ impl FnfDecoder {
     }
 }
 
+/// Difficulty name for an FNF chart file name, from its `-suffix`.
+fn difficulty_from_file_name(name: &str) -> &'static str {
+    let file = name.rsplit(['/', '\\']).next().unwrap_or(name);
+    // "song-hard.json" -> "hard"
+    let suffix = file
+        .rsplit_once('-')
+        .and_then(|(_, rest)| rest.split('.').next());
+    suffix
+        .and_then(|suffix| {
+            DIFFICULTY_SUFFIXES
+                .iter()
+                .find(|(known, _)| suffix.eq_ignore_ascii_case(known))
+        })
+        .map_or("Normal", |&(_, difficulty)| difficulty)
+}
+
 impl Decoder for FnfDecoder {
+    fn probe(data: &[u8]) -> bool {
+        data.trim_ascii_start().starts_with(b"{")
+            && memchr::memmem::find(data, b"\"song\"").is_some()
+    }
+
     /// Decode FNF chart, extracting player notes only (4K).
     fn decode(data: &[u8]) -> RoxResult<RoxChart> {
         Self::decode_with_side(data, FnfSide::Player)
@@ -153,6 +201,72 @@ mod tests {
     use super::*;
     use crate::codec::Decoder;
 
+    #[test]
+    fn test_bpm_change_on_section_boundary() {
+        // 120 BPM section (4 beats = 2s), then a 240 BPM section whose first
+        // note is a quarter beat late, then a note one measure later at 3s
+        let data = br#"{"song": {"song": "Test", "bpm": 120, "notes": [
+            {"sectionNotes": [[0, 0, 0], [500, 1, 0]], "mustHitSection": true},
+            {"sectionNotes": [[2250, 2, 0]], "mustHitSection": true, "changeBPM": true, "bpm": 240},
+            {"sectionNotes": [[3000, 3, 0]], "mustHitSection": true}
+        ]}}"#;
+        let chart = <FnfDecoder as Decoder>::decode(data).unwrap();
+
+        let timing: Vec<(i64, f32)> = chart
+            .timing_points
+            .iter()
+            .map(|tp| (tp.time_us, tp.bpm))
+            .collect();
+        assert_eq!(timing, vec![(0, 120.0), (2_000_000, 240.0)]);
+
+        // The last note is on beat 8: 4 beats at 120 BPM + 4 beats at 240 BPM
+        let last = chart.notes.last().unwrap();
+        assert_eq!(last.time_us, 3_000_000);
+        let beat = crate::model::us_to_beat(&chart.timing_points, last.time_us);
+        assert!((beat - 8.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_decode_difficulty_set() {
+        let normal = br#"{"song": {"song": "Bop-Beep", "bpm": 100, "notes": [
+            {"sectionNotes": [[0, 0, 0]], "mustHitSection": true}
+        ]}}"#;
+        let hard = br#"{"song": {"song": "Bop-Beep", "bpm": 100, "notes": [
+            {"sectionNotes": [[0, 0, 0], [300, 1, 0]], "mustHitSection": true}
+        ]}}"#;
+        let files: [(&str, &[u8]); 2] = [
+            ("data/bop-beep/bop-beep.json", normal),
+            ("data/bop-beep/bop-beep-HARD.json", hard),
+        ];
+
+        let charts = FnfDecoder::decode_difficulty_set(&files).unwrap();
+
+        assert_eq!(charts.len(), 2);
+        assert_eq!(charts[0].metadata.difficulty_name, "Normal");
+        assert_eq!(charts[0].note_count(), 1);
+        assert_eq!(charts[1].metadata.difficulty_name, "Hard");
+        assert_eq!(charts[1].note_count(), 2);
+        assert_eq!(difficulty_from_file_name("song-easy.JSON"), "Easy");
+        assert_eq!(
+            difficulty_from_file_name("C:\\fnf\\song-normal.json"),
+            "Normal"
+        );
+
+        let broken: [(&str, &[u8]); 1] = [("song-easy.json", b"{")];
+        assert!(FnfDecoder::decode_difficulty_set(&broken).is_err());
+    }
+
+    #[test]
+    fn test_bpm_change_in_first_section() {
+        let data = br#"{"song": {"song": "Test", "bpm": 100, "notes": [
+            {"sectionNotes": [[250, 0, 0]], "mustHitSection": true, "changeBPM": true, "bpm": 150}
+        ]}}"#;
+        let chart = <FnfDecoder as Decoder>::decode(data).unwrap();
+        assert_eq!(chart.timing_points.len(), 1);
+        assert_eq!(chart.timing_points[0].time_us, 0);
+        assert_eq!(chart.timing_points[0].bpm, 150.0);
+    }
+
     #[test]
     #[ignore = "FNF is currently WIP/Unstable"]
     fn test_decode_asset_fnf_player() {
@@ -5,62 +5,123 @@
 
 use crate::codec::Encoder;
 use crate::error::RoxResult;
-use crate::model::RoxChart;
+use crate::model::{DEFAULT_BPM, NoteType, RoxChart, beat_to_us, us_to_beat};
 
 use super::types::{FnfChart, FnfNote, FnfSection, FnfSong};
 
+/// Beats per section (one 4/4 measure, `lengthInSteps` = 16).
+const BEATS_PER_SECTION: f64 = 4.0;
+
+/// Steps (16th notes) per section.
+const STEPS_PER_SECTION: i32 = 16;
+
 /// Encoder for Friday Night Funkin' charts.
+///
+/// Produces the legacy `song.json` layout: notes are grouped into one-measure
+/// sections, every section is `mustHitSection`, and player notes use lanes
+/// 0-3. For coop charts (`is_coop`, 8K) columns 0-3 are the opponent and are
+/// written to lanes 4-7, matching [`FnfSide::Both`](super::FnfSide::Both).
 pub struct FnfEncoder;
 
+impl FnfEncoder {
+    /// Map a chart column to an FNF lane within a `mustHitSection`.
+    fn lane(column: u8, is_coop: bool) -> u8 {
+        if !is_coop {
+            return column;
+        }
+        if column >= 4 {
+            // Player side
+            column - 4
+        } else {
+            // Opponent side
+            column + 4
+        }
+    }
+
+    /// BPM in effect at `time_us`, falling back to `base_bpm`.
+    fn bpm_at(chart: &RoxChart, time_us: i64, base_bpm: f32) -> f32 {
+        chart
+            .timing_points
+            .iter()
+            .rev()
+            .find(|tp| !tp.is_inherited && tp.time_us <= time_us)
+            .map_or(base_bpm, |tp| tp.bpm)
+    }
+}
+
 impl Encoder for FnfEncoder {
+    /// Validates the chart first (see [`RoxChart::validate`]), so an invalid
+    /// chart is reported instead of written as a broken file.
     fn encode(chart: &RoxChart) -> RoxResult<Vec<u8>> {
+        chart.validate()?;
+        Self::encode_unchecked(chart)
+    }
+
+    fn encode_unchecked(chart: &RoxChart) -> RoxResult<Vec<u8>> {
         // Get base BPM from first timing point
         let base_bpm = chart
             .timing_points
             .iter()
             .find(|tp| !tp.is_inherited)
-            .map_or(120.0, |tp| tp.bpm);
+            .map_or(DEFAULT_BPM, |tp| tp.bpm);
 
-        // Determine if this is 8K (both sides) or 4K (player only)
-        let is_8k = chart.key_count() >= 8;
+        let is_coop = chart.key_count() == 8 && chart.coop_split() == Some(4);
 
-        // Create a single large section with all notes
-        // This matches the JS converter approach
-        let mut section_notes: Vec<FnfNote> = Vec::new();
+        // FNF has no offset: sections are laid out from time 0, like the decoder
+        // expects, even if the first BPM point is later
+        let origin_beat = us_to_beat(&chart.timing_points, 0);
 
+        // Bucket notes into one-measure sections by beat position
+        let mut buckets: Vec<Vec<FnfNote>> = Vec::new();
         for note in &chart.notes {
+            // FNF has no mines or fakes
+            if note.is_mine() || note.fake {
+                continue;
+            }
+
             #[allow(clippy::cast_precision_loss)]
             let time_ms = note.time_us as f64 / 1000.0;
-
-            // Map columns to FNF lanes
-            let lane = if is_8k {
-                // 8K: columns 0-3 = opponent (lanes 0-3), columns 4-7 = player (lanes 4-7)
-                note.column
-            } else {
-                // 4K: all notes go to player side (lanes 0-3)
-                note.column
-            };
-
-            let fnf_note = match &note.note_type {
-                crate::model::NoteType::Hold { duration_us } => {
+            let lane = Self::lane(note.column, is_coop);
+            let fnf_note = match note.note_type {
+                NoteType::Hold { duration_us } => {
                     #[allow(clippy::cast_precision_loss)]
-                    let duration_ms = *duration_us as f64 / 1000.0;
+                    let duration_ms = duration_us as f64 / 1000.0;
                     FnfNote::hold(time_ms, lane, duration_ms)
                 }
                 _ => FnfNote::tap(time_ms, lane),
             };
 
-            section_notes.push(fnf_note);
+            let beat = us_to_beat(&chart.timing_points, note.time_us) - origin_beat;
+            #[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+            let index = (beat / BEATS_PER_SECTION).floor().max(0.0) as usize;
+            if buckets.len() <= index {
+                buckets.resize_with(index + 1, Vec::new);
+            }
+            buckets[index].push(fnf_note);
         }
 
-        let section = FnfSection {
-            section_notes,
-            length_in_steps: 160_000, // Large number to contain all notes
-            must_hit_section: !is_8k, // true for 4K (player), false for 8K
-            change_bpm: true,
-            bpm: base_bpm,
-            type_of_section: 0,
-        };
+        let mut previous_bpm = base_bpm;
+        let mut sections = Vec::with_capacity(buckets.len());
+        for (index, mut section_notes) in buckets.into_iter().enumerate() {
+            #[allow(clippy::cast_precision_loss)]
+            let start_us = beat_to_us(
+                &chart.timing_points,
+                origin_beat + index as f64 * BEATS_PER_SECTION,
+            );
+            let bpm = Self::bpm_at(chart, start_us, base_bpm);
+            let change_bpm = (bpm - previous_bpm).abs() > f32::EPSILON;
+            previous_bpm = bpm;
+
+            section_notes.sort_by(|a, b| a.time_ms().total_cmp(&b.time_ms()));
+            sections.push(FnfSection {
+                section_notes,
+                length_in_steps: STEPS_PER_SECTION,
+                must_hit_section: true,
+                change_bpm,
+                bpm,
+                type_of_section: 0,
+            });
+        }
 
         // Create FNF chart structure
         let fnf = FnfChart {
@@ -72,8 +133,8 @@ impl Encoder for FnfEncoder {
                 player2: chart.metadata.creator.to_string(),
                 needs_voices: false,
                 valid_score: true,
-                notes: vec![section], // Assuming fnf_sections should be vec![section]
-                sections: 0,          // Will be calculated by FNF game
+                notes: sections,
+                sections: 0, // Will be calculated by FNF game
                 section_lengths: Vec::new(),
             },
         };
@@ -88,15 +149,87 @@ impl Encoder for FnfEncoder {
 
 #[cfg(test)]
 mod tests {
+    use super::*;
+    use crate::codec::Decoder;
+    use crate::codec::formats::fnf::{FnfDecoder, FnfSide};
+    use crate::model::{Note, TimingPoint};
+
+    #[test]
+    fn test_roundtrip_player_note_count() {
+        let data = crate::test_utils::get_test_asset("fnf/test-song.json");
+        let chart1 = FnfDecoder::decode(&data).unwrap();
+        let encoded = FnfEncoder::encode(&chart1).unwrap();
+        let chart2 = FnfDecoder::decode(&encoded).unwrap();
+
+        assert!(!chart1.notes.is_empty());
+        assert_eq!(chart1.notes.len(), chart2.notes.len());
+    }
+
+    #[test]
+    fn test_encode_sections() {
+        let mut chart = RoxChart::new(4);
+        chart.timing_points.push(TimingPoint::bpm(0, 120.0));
+        // 120 BPM: one 4-beat section every 2s
+        chart.notes.push(Note::tap(0, 0));
+        chart.notes.push(Note::hold(500_000, 250_000, 1));
+        chart.notes.push(Note::tap(4_500_000, 3));
+
+        let encoded = FnfEncoder::encode(&chart).unwrap();
+        let fnf: FnfChart = serde_json::from_slice(&encoded).unwrap();
+
+        assert!((fnf.song.bpm - 120.0).abs() < f32::EPSILON);
+        let sections = &fnf.song.notes;
+        assert_eq!(sections.len(), 3);
+        assert_eq!(sections[0].section_notes.len(), 2);
+        assert!(sections[1].section_notes.is_empty());
+        assert_eq!(sections[2].section_notes.len(), 1);
+        assert!(sections.iter().all(|s| s.must_hit_section));
+        assert!((sections[0].section_notes[1].duration_ms() - 250.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_bpm_change_roundtrip() {
+        let mut chart = RoxChart::new(4);
+        chart.timing_points.push(TimingPoint::bpm(0, 120.0));
+        chart.timing_points.push(TimingPoint::bpm(2_000_000, 240.0));
+        chart.notes.push(Note::tap(500_000, 0));
+        chart.notes.push(Note::tap(2_250_000, 1));
+        chart.notes.push(Note::tap(3_000_000, 2));
+
+        let encoded = FnfEncoder::encode(&chart).unwrap();
+        let decoded = FnfDecoder::decode(&encoded).unwrap();
+
+        assert_eq!(decoded.timing_points, chart.timing_points);
+        assert_eq!(decoded.notes, chart.notes);
+    }
+
+    #[test]
+    fn test_encode_coop_lanes() {
+        let mut chart = RoxChart::new(8);
+        chart.metadata.is_coop = true;
+        chart.timing_points.push(TimingPoint::bpm(0, 120.0));
+        chart.notes.push(Note::tap(0, 1)); // opponent
+        chart.notes.push(Note::tap(100_000, 6)); // player
+
+        let encoded = FnfEncoder::encode(&chart).unwrap();
+        let fnf: FnfChart = serde_json::from_slice(&encoded).unwrap();
+        let lanes: Vec<u8> = fnf.song.notes[0]
+            .section_notes
+            .iter()
+            .map(FnfNote::lane)
+            .collect();
+        assert_eq!(lanes, vec![5, 2]);
+
+        let both = FnfDecoder::from_fnf(&fnf, FnfSide::Both);
+        let columns: Vec<u8> = both.notes.iter().map(|n| n.column).collect();
+        assert_eq!(columns, vec![1, 6]);
+    }
 
     #[test]
     #[cfg(feature = "analysis")]
     #[ignore = "FNF is currently WIP/Unstable"]
     fn test_roundtrip_both() {
-        use super::*;
         use crate::analysis::RoxAnalysis;
-        use crate::codec::Decoder;
-        use crate::codec::formats::fnf::FnfDecoder;
         let data = crate::test_utils::get_test_asset("fnf/test-song.json");
         // Decode both sides (8K)
         let chart1 = FnfDecoder::decode(&data).unwrap();
@@ -82,7 +82,9 @@ pub struct FnfSection {
     #[serde(default)]
     pub must_hit_section: bool,
     /// Whether BPM changes in this section.
-    #[serde(default)]
+    ///
+    /// Charts spell it `changeBPM`, which `camelCase` would not produce.
+    #[serde(default, rename = "changeBPM", alias = "changeBpm")]
     pub change_bpm: bool,
     /// New BPM if `change_bpm` is true.
     #[serde(default)]
@@ -16,6 +16,11 @@ pub struct JroxEncoder;
 const MAX_FILE_SIZE: usize = 100 * 1024 * 1024;
 
 impl Decoder for JroxDecoder {
+    fn probe(data: &[u8]) -> bool {
+        data.trim_ascii_start().starts_with(b"{")
+            && memchr::memmem::find(data, b"\"timing_points\"").is_some()
+    }
+
     fn decode(data: &[u8]) -> RoxResult<RoxChart> {
         if data.len() > MAX_FILE_SIZE {
             return Err(RoxError::InvalidFormat(format!(
@@ -22,7 +22,16 @@ pub use jrox::{JroxDecoder, JroxEncoder};
 pub use osu::{OsuDecoder, OsuEncoder};
 pub use qua::{QuaDecoder, QuaEncoder};
 #[cfg(feature = "compression")]
-pub use rox::RoxCodec;
+pub use rox::{ArchivedChartView, RoxCodec};
 pub use sm::{SmDecoder, SmEncoder};
 pub use taiko::TaikoDecoder;
 pub use yrox::{YroxDecoder, YroxEncoder};
+
+/// Clean up an audio or background path read from a chart file.
+///
+/// Trims whitespace and surrounding quotes and turns Windows backslashes into
+/// forward slashes, so the path resolves on every platform. It stays relative
+/// to the chart; nothing is resolved against the filesystem.
+pub(crate) fn normalize_asset_path(path: &str) -> String {
+    path.trim().trim_matches('"').trim().replace('\\', "/")
+}
@@ -2,12 +2,15 @@
 
 use std::collections::HashMap;
 
-use crate::codec::Decoder;
+use crate::codec::formats::normalize_asset_path;
+use crate::codec::{DecodeLimits, DecodeOptions, Decoder};
 use crate::error::RoxResult;
-use crate::model::{Hitsound, Metadata, Note, RoxChart, TimingPoint};
+use crate::model::{
+    DEFAULT_BPM, Hitsound, MAX_KEY_COUNT, Metadata, Note, RoxChart, TimingPoint, time,
+};
 
 use super::parser;
-use super::types::OsuBeatmap;
+use super::types::{EFFECT_KIAI, OsuBeatmap};
 
 /// Decoder for osu!mania beatmaps.
 pub struct OsuDecoder;
@@ -16,84 +19,80 @@ impl OsuDecoder {
     /// Convert an `OsuBeatmap` to `RoxChart`.
     #[must_use]
     pub fn from_beatmap(beatmap: &OsuBeatmap) -> RoxChart {
+        let mut chart = RoxChart::new(0);
+        Self::from_beatmap_into(beatmap, &mut chart);
+        chart
+    }
+
+    /// Convert an `OsuBeatmap` into `chart`, replacing its contents but
+    /// reusing its vectors.
+    pub fn from_beatmap_into(beatmap: &OsuBeatmap, chart: &mut RoxChart) {
+        Self::convert_into(beatmap, chart, DEFAULT_BPM);
+    }
+
+    /// [`from_beatmap_into`](Self::from_beatmap_into), with the BPM used when
+    /// the beatmap has no red line.
+    fn convert_into(beatmap: &OsuBeatmap, chart: &mut RoxChart, default_bpm: f32) {
         // Safe: circle_size is always 4-18 for mania which fits in u8
         #[allow(clippy::cast_sign_loss, clippy::cast_possible_truncation)]
         let key_count = beatmap.difficulty.circle_size as u8;
-        let mut chart = RoxChart::new(key_count);
-
-        // Map metadata
-        chart.metadata = Metadata {
-            // Map osu! IDs (osu IDs are always positive in practice)
-            #[allow(clippy::cast_sign_loss)]
-            chart_id: beatmap.metadata.beatmap_id.map(|id| id as u64),
-            #[allow(clippy::cast_sign_loss)]
-            chartset_id: beatmap.metadata.beatmap_set_id.map(|id| id as u64),
-            key_count,
-            title: beatmap
-                .metadata
-                .title_unicode
-                .clone()
-                .unwrap_or_else(|| beatmap.metadata.title.clone())
-                .into(),
-            artist: beatmap
-                .metadata
-                .artist_unicode
-                .clone()
-                .unwrap_or_else(|| beatmap.metadata.artist.clone())
-                .into(),
-            creator: beatmap.metadata.creator.clone().into(),
-            difficulty_name: beatmap.metadata.version.clone().into(),
-            difficulty_value: Some(beatmap.difficulty.overall_difficulty),
-            audio_file: beatmap.general.audio_filename.clone().into(),
-            background_file: beatmap.background.clone().map(Into::into),
-            audio_offset_us: i64::from(beatmap.general.audio_lead_in) * 1000,
-            preview_time_us: if beatmap.general.preview_time > 0 {
-                i64::from(beatmap.general.preview_time) * 1000
-            } else {
-                0
-            },
-            source: beatmap.metadata.source.clone().map(Into::into),
-            tags: beatmap
-                .metadata
-                .tags
-                .iter()
-                .map(|s| s.clone().into())
-                .collect(),
-            ..Default::default()
-        };
+        chart.reset(key_count);
+
+        chart.metadata = metadata(beatmap, key_count);
 
         // Convert timing points
         for tp in &beatmap.timing_points {
-            // Safe: time in ms fits in i64 after multiplying by 1000
-            #[allow(clippy::cast_possible_truncation)]
-            let time_us = (tp.time * 1000.0) as i64;
+            let time_us = time::ms_f64_to_us(tp.time);
+
+            let kiai = tp.effects & EFFECT_KIAI != 0;
 
             if tp.uninherited {
                 // BPM point
                 if let Some(bpm) = tp.bpm() {
                     let mut timing = TimingPoint::bpm(time_us, bpm);
                     timing.signature = tp.meter;
+                    timing.kiai = kiai;
                     chart.timing_points.push(timing);
                 }
             } else {
                 // SV point
-                let sv = tp.scroll_velocity();
-                chart.timing_points.push(TimingPoint::sv(time_us, sv));
+                let mut timing = TimingPoint::sv(time_us, tp.scroll_velocity());
+                timing.kiai = kiai;
+                chart.timing_points.push(timing);
             }
         }
+        ensure_leading_bpm(&mut chart.timing_points, default_bpm);
 
         // Map to track unique hitsound files and their indices
         let mut hitsound_map: HashMap<String, u16> = HashMap::new();
 
         // Convert hit objects to notes
         for ho in &beatmap.hit_objects {
+            // Only the circle (1) and hold (128) bits matter; combo bits alone
+            // don't make a note.
+            if !ho.is_tap() && !ho.is_hold() {
+                tracing::warn!(
+                    time_ms = ho.time,
+                    object_type = ho.object_type,
+                    "Skipping hit object that is neither a tap nor a hold"
+                );
+                continue;
+            }
+
             let column = ho.column(key_count);
-            let time_us = i64::from(ho.time) * 1000;
+            let time_us = time::ms_to_us(i64::from(ho.time));
 
-            let mut note = if ho.is_hold() {
-                let duration_us = i64::from(ho.duration_ms()) * 1000;
+            let mut note = if ho.is_hold() && ho.duration_ms() > 0 {
+                let duration_us = time::ms_to_us(i64::from(ho.duration_ms()));
                 Note::hold(time_us, duration_us, column)
             } else {
+                if ho.is_hold() {
+                    tracing::warn!(
+                        time_ms = ho.time,
+                        end_time = ?ho.end_time,
+                        "Hold note without a valid end time, decoding as tap"
+                    );
+                }
                 Note::tap(time_us, column)
             };
 
@@ -145,24 +144,219 @@ impl OsuDecoder {
 
         // Sort notes by time
         chart.notes.sort_by_key(|n| n.time_us);
+    }
+}
 
-        chart
+/// Most keys a single osu!mania stage has. Beatmaps with more are co-op maps,
+/// played on two stages of `key_count / 2` keys each.
+const MAX_STAGE_KEYS: u8 = 9;
+
+/// Map the beatmap's metadata sections.
+fn metadata(beatmap: &OsuBeatmap, key_count: u8) -> Metadata {
+    Metadata {
+        // Map osu! IDs (osu IDs are always positive in practice)
+        #[allow(clippy::cast_sign_loss)]
+        chart_id: beatmap.metadata.beatmap_id.map(|id| id as u64),
+        #[allow(clippy::cast_sign_loss)]
+        chartset_id: beatmap.metadata.beatmap_set_id.map(|id| id as u64),
+        key_count,
+        title: beatmap
+            .metadata
+            .title_unicode
+            .clone()
+            .unwrap_or_else(|| beatmap.metadata.title.clone())
+            .into(),
+        artist: beatmap
+            .metadata
+            .artist_unicode
+            .clone()
+            .unwrap_or_else(|| beatmap.metadata.artist.clone())
+            .into(),
+        title_romanized: beatmap.metadata.romanized_title().map(Into::into),
+        artist_romanized: beatmap.metadata.romanized_artist().map(Into::into),
+        creator: beatmap.metadata.creator.clone().into(),
+        difficulty_name: beatmap.metadata.version.clone().into(),
+        difficulty_value: Some(beatmap.difficulty.overall_difficulty),
+        hp_drain_rate: Some(beatmap.difficulty.hp_drain_rate),
+        overall_difficulty: Some(beatmap.difficulty.overall_difficulty),
+        approach_rate: beatmap.difficulty.approach_rate,
+        countdown: beatmap.general.countdown,
+        sample_set: beatmap.general.sample_set.clone().map(Into::into),
+        stack_leniency: beatmap.general.stack_leniency,
+        special_style: beatmap.general.special_style,
+        // osu! only creates these through co-op, with an even split
+        is_coop: key_count > MAX_STAGE_KEYS && key_count.is_multiple_of(2),
+        audio_file: normalize_asset_path(&beatmap.general.audio_filename).into(),
+        background_file: beatmap
+            .background
+            .as_deref()
+            .map(|path| normalize_asset_path(path).into()),
+        audio_offset_us: time::ms_to_us(i64::from(beatmap.general.audio_lead_in)),
+        preview_time_us: if beatmap.general.preview_time > 0 {
+            time::ms_to_us(i64::from(beatmap.general.preview_time))
+        } else {
+            0
+        },
+        source: beatmap.metadata.source.clone().map(Into::into),
+        tags: beatmap
+            .metadata
+            .tags
+            .iter()
+            .map(|s| s.clone().into())
+            .collect(),
+        bookmarks_us: beatmap
+            .editor
+            .bookmarks
+            .iter()
+            .map(|&ms| time::ms_to_us(i64::from(ms)))
+            .collect(),
+        ..Default::default()
     }
 }
 
-impl Decoder for OsuDecoder {
-    fn decode(data: &[u8]) -> RoxResult<RoxChart> {
-        let beatmap = parser::parse(data)?;
+/// Make sure the first timing point is a BPM point.
+///
+/// osu! requires a red line before any green (SV) line, but malformed maps
+/// sometimes start with a green one. The first red line's BPM is copied to
+/// the time of the leading green line, which matches how osu! times that stretch. Without a red line
+/// `default_bpm` is used instead.
+pub(crate) fn ensure_leading_bpm(timing_points: &mut Vec<TimingPoint>, default_bpm: f32) {
+    let Some(first) = timing_points.first() else {
+        return;
+    };
+    if !first.is_inherited {
+        return;
+    }
+
+    let time_us = first.time_us;
+    let mut timing = if let Some(red) = timing_points.iter().find(|tp| !tp.is_inherited) {
+        tracing::warn!(
+            time_us,
+            first_bpm_time_us = red.time_us,
+            "Beatmap starts with an inherited timing point, copying the first BPM before it"
+        );
+        red.clone()
+    } else {
+        tracing::warn!(
+            time_us,
+            bpm = default_bpm,
+            "Beatmap has no uninherited timing point, using a fallback BPM"
+        );
+        TimingPoint::bpm(time_us, default_bpm)
+    };
+    timing.time_us = time_us;
+    timing.kiai = false;
+    timing_points.insert(0, timing);
+}
 
+impl OsuDecoder {
+    /// Decode with explicit [`DecodeOptions`] (e.g. legacy encoding detection).
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if parsing fails, the beatmap is not osu!mania, or its
+    /// `CircleSize` is not a supported key count.
+    pub fn decode_with_options(data: &[u8], options: &DecodeOptions) -> RoxResult<RoxChart> {
+        let beatmap = parser::parse(&options.text(data))?;
+        let mut chart = Self::from_parsed(&beatmap, options.default_bpm())?;
+        options.post_process(&mut chart);
+        Ok(chart)
+    }
+
+    /// Decode with explicit [`DecodeOptions`] while reporting
+    /// `(bytes_processed, total_bytes)` to `progress`.
+    ///
+    /// Meant for very large files (marathons) in CLI/GUI tools; the last call
+    /// is always `(total_bytes, total_bytes)`. Bytes count the text after
+    /// any encoding detection.
+    ///
+    /// # Errors
+    ///
+    /// Same as [`decode_with_options`](Self::decode_with_options).
+    pub fn decode_with_progress(
+        data: &[u8],
+        options: &DecodeOptions,
+        progress: impl FnMut(usize, usize),
+    ) -> RoxResult<RoxChart> {
+        let beatmap = parser::parse_with_progress(&options.text(data), progress)?;
+        let mut chart = Self::from_parsed(&beatmap, options.default_bpm())?;
+        options.post_process(&mut chart);
+        Ok(chart)
+    }
+
+    /// Decode untrusted data within caller-controlled [`DecodeLimits`].
+    ///
+    /// Parsing stops at the first hit object or timing point past the
+    /// limits, before the chart is built. Decodes with the default
+    /// [`DecodeOptions`]; apply [`DecodeOptions::post_process`] to the result
+    /// for option-driven cleanup.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`RoxError::LimitExceeded`](crate::error::RoxError::LimitExceeded)
+    /// if a limit is exceeded, or the same errors as
+    /// [`decode_with_options`](Self::decode_with_options).
+    pub fn decode_with_limits(data: &[u8], limits: &DecodeLimits) -> RoxResult<RoxChart> {
+        let beatmap = parser::parse_with_limits(data, limits)?;
+        Self::from_parsed(&beatmap, DEFAULT_BPM)
+    }
+
+    /// Decode into an existing chart, reusing its note and timing point
+    /// allocations.
+    ///
+    /// Meant for library scans that decode many files in a row: keep one
+    /// `RoxChart` around and pass it to every call. The previous contents are
+    /// fully replaced; on error `chart` is left unchanged.
+    ///
+    /// # Errors
+    ///
+    /// Same as [`decode_with_options`](Self::decode_with_options).
+    pub fn decode_into(data: &[u8], chart: &mut RoxChart) -> RoxResult<()> {
+        let beatmap = parser::parse(&DecodeOptions::default().text(data))?;
+        Self::check_beatmap(&beatmap)?;
+        Self::from_beatmap_into(&beatmap, chart);
+        Ok(())
+    }
+
+    /// Check the mode and key count of a parsed beatmap and convert it.
+    fn from_parsed(beatmap: &OsuBeatmap, default_bpm: f32) -> RoxResult<RoxChart> {
+        Self::check_beatmap(beatmap)?;
+        let mut chart = RoxChart::new(0);
+        Self::convert_into(beatmap, &mut chart, default_bpm);
+        Ok(chart)
+    }
+
+    /// Reject beatmaps that are not osu!mania or have an unsupported key count.
+    fn check_beatmap(beatmap: &OsuBeatmap) -> RoxResult<()> {
         // Validate it's mania mode (3)
         if beatmap.general.mode != 3 {
-            return Err(crate::error::RoxError::InvalidFormat(format!(
-                "Not a mania beatmap (mode={}, expected 3)",
-                beatmap.general.mode
-            )));
+            return Err(crate::error::RoxError::WrongGameMode {
+                found: beatmap.general.mode,
+                expected: 3,
+            });
+        }
+
+        // Reject corrupt key counts before building the chart
+        #[allow(clippy::cast_sign_loss, clippy::cast_possible_truncation)]
+        let key_count = beatmap.difficulty.circle_size as u8;
+        if key_count == 0 || key_count > MAX_KEY_COUNT {
+            return Err(crate::error::RoxError::InvalidKeyCount {
+                key_count,
+                max: MAX_KEY_COUNT,
+            });
         }
 
-        Ok(Self::from_beatmap(&beatmap))
+        Ok(())
+    }
+}
+
+impl Decoder for OsuDecoder {
+    fn probe(data: &[u8]) -> bool {
+        parser::has_header(data)
+    }
+
+    fn decode(data: &[u8]) -> RoxResult<RoxChart> {
+        Self::decode_with_options(data, &DecodeOptions::default())
     }
 }
 
@@ -247,4 +441,243 @@ mod tests {
         assert!(hs_files.contains(&"RimShot.wav"));
         assert!(hs_files.contains(&"KICK 2.wav"));
     }
+
+    #[test]
+    fn test_decode_hold_without_end_time() {
+        let data = b"osu file format v14\n\n[General]\nMode: 3\n\n[Difficulty]\nCircleSize:4\n\n[TimingPoints]\n0,500,4,1,0,100,1,0\n\n[HitObjects]\n64,192,1000,128,0\n192,192,2000,128,0,:0:0:0:0:\n320,192,3000,128,0,2500:0:0:0:0:\n448,192,4000,128,0,4500:0:0:0:0:\n";
+        let chart = <OsuDecoder as Decoder>::decode(data).unwrap();
+
+        assert_eq!(chart.notes.len(), 4);
+        assert!(
+            chart.notes[..3]
+                .iter()
+                .all(|n| matches!(n.note_type, crate::model::NoteType::Tap))
+        );
+        assert!(chart.notes[3].is_hold());
+        assert_eq!(chart.notes[3].duration_us(), 500_000);
+        assert!(chart.validate().is_ok());
+    }
+
+    #[test]
+    fn test_decode_with_progress() {
+        let data = crate::test_utils::get_test_asset("osu/mania_7k.osu");
+        let mut reports = Vec::new();
+        let chart =
+            OsuDecoder::decode_with_progress(&data, &DecodeOptions::default(), |done, total| {
+                reports.push((done, total));
+            })
+            .unwrap();
+
+        assert_eq!(chart, <OsuDecoder as Decoder>::decode(&data).unwrap());
+        assert!(reports.len() > 1);
+        assert!(reports.windows(2).all(|w| w[0].0 < w[1].0));
+        assert!(reports.iter().all(|&(_, total)| total == data.len()));
+        assert_eq!(reports.last(), Some(&(data.len(), data.len())));
+    }
+
+    #[test]
+    fn test_decode_invalid_key_count() {
+        for circle_size in ["0", "200"] {
+            let data = format!(
+                "osu file format v14\n\n[General]\nMode: 3\n\n[Difficulty]\nCircleSize:{circle_size}\n\n[TimingPoints]\n0,500,4,1,0,100,1,0\n\n[HitObjects]\n64,192,1000,1,0,0:0:0:0:\n"
+            );
+            assert!(matches!(
+                <OsuDecoder as Decoder>::decode(data.as_bytes()),
+                Err(crate::error::RoxError::InvalidKeyCount { .. })
+            ));
+        }
+    }
+
+    #[test]
+    fn test_decode_leading_inherited_point() {
+        // Green line at 200ms before the first red line at 1000ms
+        let data = b"osu file format v14\n\n[General]\nMode: 3\n\n[Difficulty]\nCircleSize:4\n\n[TimingPoints]\n200,-50,4,1,0,100,0,0\n1000,400,4,1,0,100,1,0\n\n[HitObjects]\n64,192,500,1,0,0:0:0:0:\n192,192,1500,1,0,0:0:0:0:\n";
+        let chart = <OsuDecoder as Decoder>::decode(data).unwrap();
+
+        assert!(chart.validate().is_ok());
+        let first = &chart.timing_points[0];
+        assert!(!first.is_inherited);
+        assert_eq!(first.time_us, 200_000);
+        assert!((first.bpm - 150.0).abs() < f32::EPSILON);
+        assert!(chart.timing_points[1].is_inherited);
+        assert_eq!(chart.timing_points.len(), 3);
+    }
+
+    #[test]
+    fn test_decode_only_inherited_points() {
+        let data = b"osu file format v14\n\n[General]\nMode: 3\n\n[Difficulty]\nCircleSize:4\n\n[TimingPoints]\n0,-100,4,1,0,100,0,0\n\n[HitObjects]\n64,192,500,1,0,0:0:0:0:\n";
+        let chart = <OsuDecoder as Decoder>::decode(data).unwrap();
+
+        assert!(chart.validate().is_ok());
+        assert!(!chart.timing_points[0].is_inherited);
+        assert!((chart.timing_points[0].bpm - DEFAULT_BPM).abs() < f32::EPSILON);
+
+        let options = DecodeOptions {
+            default_bpm: Some(175.0),
+            ..DecodeOptions::default()
+        };
+        let chart = OsuDecoder::decode_with_options(data, &options).unwrap();
+        assert!(!chart.timing_points[0].is_inherited);
+        assert!((chart.timing_points[0].bpm - 175.0).abs() < f32::EPSILON);
+    }
+
+    #[test]
+    fn test_decode_into_reuses_chart() {
+        let mut chart = RoxChart::new(4);
+        let data = crate::test_utils::get_test_asset("osu/mania_7k.osu");
+        OsuDecoder::decode_into(&data, &mut chart).unwrap();
+        assert_eq!(chart, <OsuDecoder as Decoder>::decode(&data).unwrap());
+        let capacity = chart.notes.capacity();
+
+        let data = crate::test_utils::get_test_asset("osu/mania_hitsound.osu");
+        OsuDecoder::decode_into(&data, &mut chart).unwrap();
+        assert_eq!(chart, <OsuDecoder as Decoder>::decode(&data).unwrap());
+        assert!(chart.notes.capacity() >= capacity);
+
+        // A failed decode leaves the chart alone
+        let before = chart.clone();
+        assert!(OsuDecoder::decode_into(b"not a beatmap", &mut chart).is_err());
+        assert_eq!(chart, before);
+    }
+
+    #[test]
+    fn test_decode_with_limits() {
+        let data = crate::test_utils::get_test_asset("osu/mania_7k.osu");
+        let chart = OsuDecoder::decode_with_limits(&data, &DecodeLimits::default()).unwrap();
+        assert_eq!(chart, <OsuDecoder as Decoder>::decode(&data).unwrap());
+
+        let limits = DecodeLimits {
+            max_notes: 10,
+            ..DecodeLimits::default()
+        };
+        assert!(matches!(
+            OsuDecoder::decode_with_limits(&data, &limits),
+            Err(crate::error::RoxError::LimitExceeded {
+                found: 11,
+                max: 10,
+                ..
+            })
+        ));
+
+        let limits = DecodeLimits {
+            max_timing_points: 0,
+            ..DecodeLimits::default()
+        };
+        assert!(matches!(
+            OsuDecoder::decode_with_limits(&data, &limits),
+            Err(crate::error::RoxError::LimitExceeded {
+                what: "timing points",
+                found: 1,
+                ..
+            })
+        ));
+
+        let limits = DecodeLimits {
+            max_file_bytes: 1024,
+            ..DecodeLimits::default()
+        };
+        assert!(OsuDecoder::decode_with_limits(&data, &limits).is_err());
+    }
+
+    #[test]
+    fn test_decode_combo_bits() {
+        // type 5 = circle + new combo, type 4 = new combo only
+        let data = b"osu file format v14\n\n[General]\nMode: 3\n\n[Difficulty]\nCircleSize:4\n\n[TimingPoints]\n0,500,4,1,0,100,1,0\n\n[HitObjects]\n64,192,1000,5,0,0:0:0:0:\n192,192,2000,4,0,0:0:0:0:\n320,192,3000,1,0,0:0:0:0:\n";
+        let chart = <OsuDecoder as Decoder>::decode(data).unwrap();
+
+        assert_eq!(chart.notes.len(), 2);
+        assert_eq!(chart.notes[0].time_us, 1_000_000);
+        assert_eq!(chart.notes[0].column, 0);
+        assert!(matches!(
+            chart.notes[0].note_type,
+            crate::model::NoteType::Tap
+        ));
+        assert_eq!(chart.notes[1].time_us, 3_000_000);
+    }
+
+    #[test]
+    fn test_decode_min_hold_length() {
+        // A 10ms hold and a 500ms hold
+        let data = b"osu file format v14\n\n[General]\nMode: 3\n\n[Difficulty]\nCircleSize:4\n\n[TimingPoints]\n0,500,4,1,0,100,1,0\n\n[HitObjects]\n64,192,1000,128,0,1010:0:0:0:0:\n192,192,2000,128,0,2500:0:0:0:0:\n";
+        let options = DecodeOptions {
+            min_hold_us: 30_000,
+            ..DecodeOptions::default()
+        };
+        let chart = OsuDecoder::decode_with_options(data, &options).unwrap();
+
+        assert!(matches!(
+            chart.notes[0].note_type,
+            crate::model::NoteType::Tap
+        ));
+        assert!(chart.notes[1].is_hold());
+
+        // Off by default
+        let chart = <OsuDecoder as Decoder>::decode(data).unwrap();
+        assert_eq!(chart.notes[0].duration_us(), 10_000);
+
+        // The limits path decodes raw; the caller post-processes
+        let mut chart = OsuDecoder::decode_with_limits(data, &DecodeLimits::default()).unwrap();
+        assert_eq!(chart.notes[0].duration_us(), 10_000);
+        options.post_process(&mut chart);
+        assert!(!chart.notes[0].is_hold());
+    }
+
+    #[test]
+    fn test_decode_special_style() {
+        // 7K+1: CircleSize counts the special lane, which is column 0
+        let data = b"osu file format v14\n\n[General]\nMode: 3\nSpecialStyle: 1\n\n[Difficulty]\nCircleSize:8\n\n[TimingPoints]\n0,500,4,1,0,100,1,0\n\n[HitObjects]\n32,192,1000,1,0,0:0:0:0:\n96,192,2000,1,0,0:0:0:0:\n480,192,3000,1,0,0:0:0:0:\n";
+        let chart = <OsuDecoder as Decoder>::decode(data).unwrap();
+
+        assert_eq!(chart.key_count(), 8);
+        assert!(chart.metadata.special_style);
+        let columns: Vec<u8> = chart.notes.iter().map(|n| n.column).collect();
+        assert_eq!(columns, vec![0, 1, 7]);
+
+        let encoded =
+            <crate::codec::formats::osu::OsuEncoder as crate::codec::Encoder>::encode(&chart)
+                .unwrap();
+        assert!(
+            String::from_utf8(encoded)
+                .unwrap()
+                .contains("SpecialStyle: 1\n")
+        );
+    }
+
+    #[test]
+    fn test_decode_18k_coop() {
+        let data = b"osu file format v14\n\n[General]\nMode: 3\n\n[Difficulty]\nCircleSize:18\n\n[TimingPoints]\n0,500,4,1,0,100,1,0\n\n[HitObjects]\n14,192,1000,1,0,0:0:0:0:\n270,192,2000,1,0,0:0:0:0:\n498,192,3000,1,0,0:0:0:0:\n";
+        let chart = <OsuDecoder as Decoder>::decode(data).unwrap();
+
+        assert_eq!(chart.key_count(), 18);
+        assert!(chart.metadata.is_coop);
+        assert_eq!(chart.coop_split(), Some(9));
+        let columns: Vec<u8> = chart.notes.iter().map(|n| n.column).collect();
+        assert_eq!(columns, vec![0, 9, 17]);
+
+        let encoded =
+            <crate::codec::formats::osu::OsuEncoder as crate::codec::Encoder>::encode(&chart)
+                .unwrap();
+        let decoded = <OsuDecoder as Decoder>::decode(&encoded).unwrap();
+        assert_eq!(decoded.notes, chart.notes);
+        assert!(decoded.metadata.is_coop);
+
+        // Up to 9K is a single stage
+        let chart = <OsuDecoder as Decoder>::decode(
+            b"osu file format v14\n\n[General]\nMode: 3\n\n[Difficulty]\nCircleSize:8\n\n[TimingPoints]\n0,500,4,1,0,100,1,0\n\n[HitObjects]\n",
+        )
+        .unwrap();
+        assert!(!chart.metadata.is_coop);
+    }
+
+    #[test]
+    fn test_decode_normalizes_asset_paths() {
+        let data = b"osu file format v14\n\n[General]\nAudioFilename: audio\\song.mp3\nMode: 3\n\n[Events]\n0,0,\"bg\\cover.jpg\",0,0\n\n[Difficulty]\nCircleSize:4\n\n[TimingPoints]\n0,500,4,1,0,100,1,0\n\n[HitObjects]\n64,192,1000,1,0,0:0:0:0:\n";
+        let chart = <OsuDecoder as Decoder>::decode(data).unwrap();
+
+        assert_eq!(chart.metadata.audio_file, "audio/song.mp3");
+        assert_eq!(
+            chart.metadata.background_file.as_deref(),
+            Some("bg/cover.jpg")
+        );
+    }
 }
@@ -4,23 +4,34 @@ use std::fmt::Write;
 
 use crate::codec::Encoder;
 use crate::error::RoxResult;
-use crate::model::RoxChart;
+use crate::model::{Metadata, RoxChart, time};
+
+use super::types::EFFECT_KIAI;
 
 /// Encoder for osu!mania beatmaps.
 pub struct OsuEncoder;
 
 impl Encoder for OsuEncoder {
+    /// Validates the chart first (see [`RoxChart::validate`]), so an invalid
+    /// chart is reported instead of written as a broken file.
     fn encode(chart: &RoxChart) -> RoxResult<Vec<u8>> {
+        chart.validate()?;
+        Self::encode_unchecked(chart)
+    }
+
+    fn encode_unchecked(chart: &RoxChart) -> RoxResult<Vec<u8>> {
         let mut output = String::new();
 
         // Format version
         output.push_str("osu file format v14\n\n");
 
-        write_general_section(&mut output, chart);
-        write_editor_section(&mut output);
-        write_metadata_section(&mut output, chart);
+        // Strings from the source file must not break the line structure
+        let meta = chart.metadata.sanitized();
+        write_general_section(&mut output, &meta);
+        write_editor_section(&mut output, chart);
+        write_metadata_section(&mut output, &meta);
         write_difficulty_section(&mut output, chart);
-        write_events_section(&mut output, chart);
+        write_events_section(&mut output, &meta);
         write_timing_points_section(&mut output, chart);
         write_hit_objects_section(&mut output, chart);
 
@@ -29,31 +40,48 @@ impl Encoder for OsuEncoder {
 }
 
 /// Write the [General] section.
-fn write_general_section(output: &mut String, chart: &RoxChart) {
+fn write_general_section(output: &mut String, meta: &Metadata) {
     output.push_str("[General]\n");
-    let _ = writeln!(output, "AudioFilename: {}", chart.metadata.audio_file);
+    let _ = writeln!(output, "AudioFilename: {}", meta.audio_file);
     let _ = writeln!(
         output,
         "AudioLeadIn: {}",
-        chart.metadata.audio_offset_us / 1000
+        time::us_to_ms(meta.audio_offset_us)
     );
     let _ = writeln!(
         output,
         "PreviewTime: {}",
-        chart.metadata.preview_time_us / 1000
+        time::us_to_ms(meta.preview_time_us)
+    );
+    let _ = writeln!(output, "Countdown: {}", meta.countdown.unwrap_or(0));
+    let _ = writeln!(
+        output,
+        "SampleSet: {}",
+        meta.sample_set.as_deref().unwrap_or("Normal")
+    );
+    let _ = writeln!(
+        output,
+        "StackLeniency: {}",
+        meta.stack_leniency.unwrap_or(0.7)
     );
-    output.push_str("Countdown: 0\n");
-    output.push_str("SampleSet: Normal\n");
-    output.push_str("StackLeniency: 0.7\n");
     output.push_str("Mode: 3\n");
     output.push_str("LetterboxInBreaks: 0\n");
-    output.push_str("SpecialStyle: 0\n");
+    let _ = writeln!(output, "SpecialStyle: {}", u8::from(meta.special_style));
     output.push_str("WidescreenStoryboard: 0\n\n");
 }
 
 /// Write the [Editor] section.
-fn write_editor_section(output: &mut String) {
+fn write_editor_section(output: &mut String, chart: &RoxChart) {
     output.push_str("[Editor]\n");
+    if !chart.metadata.bookmarks_us.is_empty() {
+        let bookmarks: Vec<String> = chart
+            .metadata
+            .bookmarks_us
+            .iter()
+            .map(|&us| time::us_to_ms(us).to_string())
+            .collect();
+        let _ = writeln!(output, "Bookmarks: {}", bookmarks.join(","));
+    }
     output.push_str("DistanceSpacing: 1\n");
     output.push_str("BeatDivisor: 4\n");
     output.push_str("GridSize: 4\n");
@@ -61,28 +89,30 @@ fn write_editor_section(output: &mut String) {
 }
 
 /// Write the [Metadata] section.
-fn write_metadata_section(output: &mut String, chart: &RoxChart) {
+fn write_metadata_section(output: &mut String, meta: &Metadata) {
     output.push_str("[Metadata]\n");
-    let _ = writeln!(output, "Title:{}", chart.metadata.title);
-    let _ = writeln!(output, "TitleUnicode:{}", chart.metadata.title);
-    let _ = writeln!(output, "Artist:{}", chart.metadata.artist);
-    let _ = writeln!(output, "ArtistUnicode:{}", chart.metadata.artist);
-    let _ = writeln!(output, "Creator:{}", chart.metadata.creator);
-    let _ = writeln!(output, "Version:{}", chart.metadata.difficulty_name);
-    if let Some(source) = &chart.metadata.source {
+    let title = meta.title_romanized.as_ref().unwrap_or(&meta.title);
+    let artist = meta.artist_romanized.as_ref().unwrap_or(&meta.artist);
+    let _ = writeln!(output, "Title:{title}");
+    let _ = writeln!(output, "TitleUnicode:{}", meta.title);
+    let _ = writeln!(output, "Artist:{artist}");
+    let _ = writeln!(output, "ArtistUnicode:{}", meta.artist);
+    let _ = writeln!(output, "Creator:{}", meta.creator);
+    let _ = writeln!(output, "Version:{}", meta.difficulty_name);
+    if let Some(source) = &meta.source {
         let _ = writeln!(output, "Source:{source}");
     }
-    if !chart.metadata.tags.is_empty() {
-        let _ = writeln!(output, "Tags:{}", chart.metadata.tags.join(" "));
+    if !meta.tags.is_empty() {
+        let _ = writeln!(output, "Tags:{}", meta.tags.join(" "));
     }
     // Export chart IDs (default to 0/-1 if not set)
-    let _ = writeln!(output, "BeatmapID:{}", chart.metadata.chart_id.unwrap_or(0));
+    let _ = writeln!(output, "BeatmapID:{}", meta.chart_id.unwrap_or(0));
     // Safe: osu format uses -1 for missing set ID
     #[allow(clippy::cast_possible_wrap)]
     let _ = writeln!(
         output,
         "BeatmapSetID:{}",
-        chart.metadata.chartset_id.map_or(-1, |id| id as i64)
+        meta.chartset_id.map_or(-1, |id| id as i64)
     );
     output.push('\n');
 }
@@ -90,23 +120,37 @@ fn write_metadata_section(output: &mut String, chart: &RoxChart) {
 /// Write the [Difficulty] section.
 fn write_difficulty_section(output: &mut String, chart: &RoxChart) {
     output.push_str("[Difficulty]\n");
-    output.push_str("HPDrainRate:8\n");
+    let _ = writeln!(
+        output,
+        "HPDrainRate:{}",
+        chart.metadata.hp_drain_rate.unwrap_or(8.0)
+    );
     let _ = writeln!(output, "CircleSize:{}", chart.key_count());
+    // Prefer the explicit OD; `difficulty_value` is the fallback for charts
+    // converted from formats that only carry a generic rating.
     let _ = writeln!(
         output,
         "OverallDifficulty:{}",
-        chart.metadata.difficulty_value.unwrap_or(8.0)
+        chart
+            .metadata
+            .overall_difficulty
+            .or(chart.metadata.difficulty_value)
+            .unwrap_or(8.0)
+    );
+    let _ = writeln!(
+        output,
+        "ApproachRate:{}",
+        chart.metadata.approach_rate.unwrap_or(5.0)
     );
-    output.push_str("ApproachRate:5\n");
     output.push_str("SliderMultiplier:1.4\n");
     output.push_str("SliderTickRate:1\n\n");
 }
 
 /// Write the [Events] section.
-fn write_events_section(output: &mut String, chart: &RoxChart) {
+fn write_events_section(output: &mut String, meta: &Metadata) {
     output.push_str("[Events]\n");
     output.push_str("//Background and Video events\n");
-    if let Some(bg) = &chart.metadata.background_file {
+    if let Some(bg) = &meta.background_file {
         let _ = writeln!(output, "0,0,\"{bg}\",0,0");
     }
     output.push_str("//Break Periods\n");
@@ -121,20 +165,22 @@ fn write_events_section(output: &mut String, chart: &RoxChart) {
 fn write_timing_points_section(output: &mut String, chart: &RoxChart) {
     output.push_str("[TimingPoints]\n");
     for tp in &chart.timing_points {
-        #[allow(clippy::cast_precision_loss)]
-        let time_ms = tp.time_us as f64 / 1000.0;
+        // osu! accepts fractional timing point offsets, so keep the precision
+        let time_ms = time::us_to_ms_f64(tp.time_us);
+
+        let effects = if tp.kiai { EFFECT_KIAI } else { 0 };
 
         if tp.is_inherited {
             // SV point: beatLength = -100 / sv
             let beat_length = -100.0 / f64::from(tp.scroll_speed);
-            let _ = writeln!(output, "{time_ms},{beat_length},4,1,0,100,0,0");
+            let _ = writeln!(output, "{time_ms},{beat_length},4,1,0,100,0,{effects}");
         } else {
             // BPM point: beatLength = 60000 / bpm
             let beat_length = 60000.0 / f64::from(tp.bpm);
             let _ = writeln!(
                 output,
-                "{},{},{},1,0,100,1,0",
-                time_ms, beat_length, tp.signature
+                "{},{},{},1,0,100,1,{}",
+                time_ms, beat_length, tp.signature, effects
             );
         }
     }
@@ -144,10 +190,11 @@ fn write_timing_points_section(output: &mut String, chart: &RoxChart) {
 /// Write the [`HitObjects`] section.
 fn write_hit_objects_section(output: &mut String, chart: &RoxChart) {
     output.push_str("[HitObjects]\n");
-    for note in &chart.notes {
-        // Safe: time_us / 1000 fits in i32 for typical beatmaps
+    // osu! has no fakes; writing them would make them playable
+    for note in chart.notes.iter().filter(|n| !n.fake) {
+        // Safe: times in ms fit in i32 for typical beatmaps
         #[allow(clippy::cast_possible_truncation)]
-        let time_ms = (note.time_us / 1000) as i32;
+        let time_ms = time::us_to_ms(note.time_us) as i32;
         let x = column_to_x(note.column, chart.key_count());
 
         match &note.note_type {
@@ -156,8 +203,9 @@ fn write_hit_objects_section(output: &mut String, chart: &RoxChart) {
                 let _ = writeln!(output, "{x},192,{time_ms},1,0,0:0:0:0:");
             }
             crate::model::NoteType::Hold { duration_us } => {
+                // Round the end itself so the hold doesn't drift from the head
                 #[allow(clippy::cast_possible_truncation)]
-                let end_time = time_ms + (*duration_us / 1000) as i32;
+                let end_time = time::us_to_ms(note.time_us + duration_us) as i32;
                 // x,y,time,type,hitSound,endTime:extras
                 let _ = writeln!(output, "{x},192,{time_ms},128,0,{end_time}:0:0:0:0:");
             }
@@ -171,6 +219,10 @@ fn write_hit_objects_section(output: &mut String, chart: &RoxChart) {
 
 /// Convert column index to X position for osu.
 /// For 7K: 36, 109, 182, 256, 329, 402, 475
+///
+/// Uses the column center, which is the exact inverse of the decoder's
+/// [`x_to_column`](super::x_to_column): `x_to_column(column_to_x(c, k), k) == c`
+/// for every `c < k <= MAX_KEY_COUNT`.
 #[must_use]
 pub fn column_to_x(column: u8, key_count: u8) -> i32 {
     // Formula: center of column = (2*column + 1) * 256 / key_count
@@ -183,7 +235,8 @@ pub fn column_to_x(column: u8, key_count: u8) -> i32 {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::model::{Note, TimingPoint};
+    use crate::codec::formats::osu::types::x_to_column;
+    use crate::model::{MAX_KEY_COUNT, Note, TimingPoint};
 
     /// Helper to verify all columns for a key count
     fn verify_columns(key_count: u8, expected: &[i32]) {
@@ -279,11 +332,10 @@ mod tests {
 
     #[test]
     fn test_column_roundtrip() {
-        for key_count in [4, 5, 6, 7, 8, 9, 10] {
+        for key_count in 1..=MAX_KEY_COUNT {
             for col in 0..key_count {
                 let x = column_to_x(col, key_count);
-                #[allow(clippy::cast_possible_truncation)]
-                let decoded_col = ((x * i32::from(key_count)) / 512) as u8;
+                let decoded_col = x_to_column(x, key_count);
                 assert_eq!(
                     decoded_col, col,
                     "Roundtrip failed for {}K column {}",
@@ -293,6 +345,27 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_hold_columns_roundtrip_through_file() {
+        use crate::codec::Decoder;
+        use crate::codec::formats::osu::OsuDecoder;
+
+        for key_count in 1..=MAX_KEY_COUNT {
+            let mut chart = RoxChart::new(key_count);
+            chart.timing_points.push(TimingPoint::bpm(0, 120.0));
+            for col in 0..key_count {
+                let time_us = i64::from(col) * 1_000_000;
+                chart.notes.push(Note::hold(time_us, 500_000, col));
+                chart.notes.push(Note::tap(time_us + 600_000, col));
+            }
+
+            let encoded = OsuEncoder::encode(&chart).unwrap();
+            let decoded = OsuDecoder::decode(&encoded).unwrap();
+            assert_eq!(decoded.key_count(), key_count);
+            assert_eq!(decoded.notes, chart.notes, "{key_count}K");
+        }
+    }
+
     #[test]
     fn test_encode_basic() {
         let mut chart = RoxChart::new(7);
@@ -314,6 +387,248 @@ mod tests {
         assert!(output.contains("CircleSize:7"));
     }
 
+    #[test]
+    fn test_difficulty_settings_roundtrip() {
+        use crate::codec::Decoder;
+        use crate::codec::formats::osu::OsuDecoder;
+
+        let data = b"osu file format v14\n\n[General]\nMode: 3\n\n[Difficulty]\nHPDrainRate:6.5\nCircleSize:4\nOverallDifficulty:7.5\nApproachRate:9.2\n\n[TimingPoints]\n0,500,4,1,0,100,1,0\n\n[HitObjects]\n64,192,1000,1,0,0:0:0:0:\n";
+        let chart = <OsuDecoder as Decoder>::decode(data).unwrap();
+        assert_eq!(chart.metadata.hp_drain_rate, Some(6.5));
+        assert_eq!(chart.metadata.overall_difficulty, Some(7.5));
+        assert_eq!(chart.metadata.approach_rate, Some(9.2));
+
+        let encoded = OsuEncoder::encode(&chart).unwrap();
+        let output = String::from_utf8_lossy(&encoded);
+        assert!(output.contains("HPDrainRate:6.5"));
+        assert!(output.contains("OverallDifficulty:7.5"));
+        assert!(output.contains("ApproachRate:9.2"));
+
+        let decoded = <OsuDecoder as Decoder>::decode(&encoded).unwrap();
+        assert_eq!(decoded.metadata.hp_drain_rate, Some(6.5));
+    }
+
+    #[test]
+    fn test_difficulty_settings_default() {
+        let chart = RoxChart::new(4);
+        let encoded = OsuEncoder::encode(&chart).unwrap();
+        let output = String::from_utf8_lossy(&encoded);
+        assert!(output.contains("HPDrainRate:8"));
+        assert!(output.contains("OverallDifficulty:8"));
+        assert!(output.contains("ApproachRate:5\n"));
+    }
+
+    #[test]
+    fn test_kiai_roundtrip() {
+        use crate::codec::Decoder;
+        use crate::codec::formats::osu::OsuDecoder;
+
+        let data = b"osu file format v14\n\n[General]\nMode: 3\n\n[Difficulty]\nCircleSize:4\n\n[TimingPoints]\n0,500,4,1,0,100,1,0\n2000,500,4,1,0,100,1,1\n3000,-50,4,1,0,100,0,1\n4000,500,4,1,0,100,1,0\n\n[HitObjects]\n64,192,1000,1,0,0:0:0:0:\n";
+        let chart = <OsuDecoder as Decoder>::decode(data).unwrap();
+        let kiai: Vec<bool> = chart.timing_points.iter().map(|tp| tp.kiai).collect();
+        assert_eq!(kiai, vec![false, true, true, false]);
+
+        // osu -> rox -> osu
+        #[cfg(feature = "compression")]
+        let chart = {
+            use crate::codec::formats::RoxCodec;
+            let bytes = RoxCodec::encode(&chart).unwrap();
+            let decoded = <RoxCodec as Decoder>::decode(&bytes).unwrap();
+            assert_eq!(decoded, chart);
+            decoded
+        };
+
+        let encoded = OsuEncoder::encode(&chart).unwrap();
+        let decoded = <OsuDecoder as Decoder>::decode(&encoded).unwrap();
+        assert_eq!(decoded.timing_points, chart.timing_points);
+    }
+
+    #[test]
+    fn test_encode_rounds_to_nearest_ms() {
+        let mut chart = RoxChart::new(4);
+        chart.timing_points.push(TimingPoint::bpm(0, 120.0));
+        chart.notes.push(Note::tap(1_500, 0));
+        chart.notes.push(Note::hold(2_999_600, 1_000_000, 1));
+        chart.metadata.preview_time_us = 10_499;
+
+        let output = String::from_utf8(OsuEncoder::encode(&chart).unwrap()).unwrap();
+
+        assert!(output.contains("64,192,2,1,0,0:0:0:0:"));
+        assert!(output.contains("192,192,3000,128,0,4000:0:0:0:0:"));
+        assert!(output.contains("PreviewTime: 10\n"));
+    }
+
+    #[test]
+    fn test_whole_ms_timing_points_stay_integers() {
+        let mut chart = RoxChart::new(4);
+        chart.timing_points.push(TimingPoint::bpm(1_001_000, 120.0));
+        chart.timing_points.push(TimingPoint::sv(2_003_000, 0.5));
+        chart.timing_points.push(TimingPoint::sv(2_003_500, 1.0));
+        chart.notes.push(Note::tap(1_001_000, 0));
+
+        let output = String::from_utf8(OsuEncoder::encode(&chart).unwrap()).unwrap();
+
+        assert!(output.contains("\n1001,500,4,1,0,100,1,0\n"));
+        assert!(output.contains("\n2003,-200,4,1,0,100,0,0\n"));
+        // Sub-millisecond offsets keep their fraction
+        assert!(output.contains("\n2003.5,-100,"));
+    }
+
+    #[test]
+    fn test_encode_rejects_invalid_column() {
+        let mut chart = RoxChart::new(4);
+        chart.timing_points.push(TimingPoint::bpm(0, 120.0));
+        chart.notes.push(Note::tap(0, 5));
+
+        assert!(matches!(
+            OsuEncoder::encode(&chart),
+            Err(crate::error::RoxError::InvalidColumn { column: 5, .. })
+        ));
+        // The opt-out still writes the file
+        assert!(OsuEncoder::encode_unchecked(&chart).is_ok());
+    }
+
+    #[test]
+    fn test_title_with_newline_keeps_sections() {
+        use crate::codec::Decoder;
+        use crate::codec::formats::osu::OsuDecoder;
+
+        let mut chart = RoxChart::new(4);
+        chart.timing_points.push(TimingPoint::bpm(0, 120.0));
+        chart.notes.push(Note::tap(1_000_000, 0));
+        chart.metadata.title = "Evil\n[HitObjects]\n64,192,0,1,0".into();
+        chart.metadata.artist = "Artist".into();
+
+        let encoded = String::from_utf8(OsuEncoder::encode(&chart).unwrap()).unwrap();
+        assert!(encoded.contains("Title:Evil[HitObjects]64,192,0,1,0\nTitleUnicode:"));
+        assert_eq!(encoded.matches("[HitObjects]\n").count(), 1);
+
+        let decoded = <OsuDecoder as Decoder>::decode(encoded.as_bytes()).unwrap();
+        assert_eq!(decoded.notes.len(), 1);
+        assert_eq!(decoded.metadata.artist, "Artist");
+    }
+
+    #[test]
+    fn test_general_fields_roundtrip() {
+        use crate::codec::Decoder;
+        use crate::codec::formats::osu::OsuDecoder;
+
+        let data = b"osu file format v14\n\n[General]\nCountdown: 2\nSampleSet: Soft\nStackLeniency: 0.5\nMode: 3\n\n[Difficulty]\nCircleSize:4\n\n[TimingPoints]\n0,500,4,1,0,100,1,0\n\n[HitObjects]\n64,192,1000,1,0,0:0:0:0:\n";
+        let chart = <OsuDecoder as Decoder>::decode(data).unwrap();
+        assert_eq!(chart.metadata.countdown, Some(2));
+        assert_eq!(chart.metadata.sample_set.as_deref(), Some("Soft"));
+        assert_eq!(chart.metadata.stack_leniency, Some(0.5));
+
+        #[cfg(feature = "compression")]
+        let chart = {
+            use crate::codec::formats::RoxCodec;
+            let bytes = RoxCodec::encode(&chart).unwrap();
+            <RoxCodec as Decoder>::decode(&bytes).unwrap()
+        };
+
+        let encoded = String::from_utf8(OsuEncoder::encode(&chart).unwrap()).unwrap();
+        assert!(encoded.contains("SampleSet: Soft\n"));
+        let decoded = <OsuDecoder as Decoder>::decode(encoded.as_bytes()).unwrap();
+        assert_eq!(decoded.metadata.countdown, Some(2));
+        assert_eq!(decoded.metadata.sample_set.as_deref(), Some("Soft"));
+        assert_eq!(decoded.metadata.stack_leniency, Some(0.5));
+
+        // Charts from other formats keep the old defaults
+        let encoded = String::from_utf8(OsuEncoder::encode(&RoxChart::new(4)).unwrap()).unwrap();
+        assert!(encoded.contains("Countdown: 0\nSampleSet: Normal\nStackLeniency: 0.7\n"));
+    }
+
+    #[test]
+    fn test_bookmarks_roundtrip() {
+        use crate::codec::Decoder;
+        use crate::codec::formats::osu::OsuDecoder;
+
+        let data = b"osu file format v14\n\n[General]\nMode: 3\n\n[Editor]\nBookmarks: 1000,2500,4000\n\n[Difficulty]\nCircleSize:4\n\n[TimingPoints]\n0,500,4,1,0,100,1,0\n\n[HitObjects]\n64,192,1000,1,0,0:0:0:0:\n";
+        let chart = <OsuDecoder as Decoder>::decode(data).unwrap();
+        assert_eq!(
+            chart.metadata.bookmarks_us,
+            vec![1_000_000, 2_500_000, 4_000_000]
+        );
+
+        // rox -> osu -> rox
+        #[cfg(feature = "compression")]
+        let chart = {
+            use crate::codec::formats::RoxCodec;
+            let bytes = RoxCodec::encode(&chart).unwrap();
+            <RoxCodec as Decoder>::decode(&bytes).unwrap()
+        };
+
+        let encoded = OsuEncoder::encode(&chart).unwrap();
+        let output = String::from_utf8_lossy(&encoded);
+        assert!(output.contains("Bookmarks: 1000,2500,4000"));
+
+        let decoded = <OsuDecoder as Decoder>::decode(&encoded).unwrap();
+        assert_eq!(decoded.metadata.bookmarks_us, chart.metadata.bookmarks_us);
+    }
+
+    #[test]
+    fn test_romanized_metadata_roundtrip() {
+        use crate::codec::Decoder;
+        use crate::codec::formats::osu::OsuDecoder;
+
+        let data = "osu file format v14\n\n[General]\nMode: 3\n\n[Metadata]\nTitle:Sora no Senritsu\nTitleUnicode:宙の旋律\nArtist:Hatsune Miku\nArtistUnicode:初音ミク\n\n[Difficulty]\nCircleSize:4\n\n[TimingPoints]\n0,500,4,1,0,100,1,0\n\n[HitObjects]\n64,192,1000,1,0,0:0:0:0:\n";
+        let chart = <OsuDecoder as Decoder>::decode(data.as_bytes()).unwrap();
+        assert_eq!(chart.metadata.title, "宙の旋律");
+        assert_eq!(
+            chart.metadata.title_romanized.as_deref(),
+            Some("Sora no Senritsu")
+        );
+        assert_eq!(chart.metadata.artist, "初音ミク");
+        assert_eq!(
+            chart.metadata.artist_romanized.as_deref(),
+            Some("Hatsune Miku")
+        );
+
+        let encoded = OsuEncoder::encode(&chart).unwrap();
+        let output = String::from_utf8(encoded.clone()).unwrap();
+        assert!(output.contains("Title:Sora no Senritsu\n"));
+        assert!(output.contains("TitleUnicode:宙の旋律\n"));
+        assert!(output.contains("Artist:Hatsune Miku\n"));
+        assert!(output.contains("ArtistUnicode:初音ミク\n"));
+
+        let decoded = <OsuDecoder as Decoder>::decode(&encoded).unwrap();
+        assert_eq!(decoded.metadata.title, chart.metadata.title);
+        assert_eq!(
+            decoded.metadata.title_romanized,
+            chart.metadata.title_romanized
+        );
+        assert_eq!(decoded.metadata.artist, chart.metadata.artist);
+        assert_eq!(
+            decoded.metadata.artist_romanized,
+            chart.metadata.artist_romanized
+        );
+    }
+
+    #[test]
+    fn test_ascii_title_has_no_romanized() {
+        let mut chart = RoxChart::new(4);
+        chart.metadata.title = "Test".into();
+        let output = String::from_utf8(OsuEncoder::encode(&chart).unwrap()).unwrap();
+        assert!(output.contains("Title:Test\nTitleUnicode:Test\n"));
+    }
+
+    #[test]
+    fn test_fakes_are_dropped() {
+        use crate::codec::Decoder;
+        use crate::codec::formats::osu::OsuDecoder;
+        let mut chart = RoxChart::new(4);
+        chart.timing_points.push(TimingPoint::bpm(0, 120.0));
+        chart.notes.push(Note::tap(0, 0));
+        chart.notes.push(Note {
+            fake: true,
+            ..Note::tap(500_000, 1)
+        });
+        let encoded = OsuEncoder::encode(&chart).unwrap();
+        let decoded = <OsuDecoder as Decoder>::decode(&encoded).unwrap();
+        assert_eq!(decoded.notes.len(), 1);
+        assert_eq!(decoded.notes[0].column, 0);
+    }
+
     #[test]
     #[cfg(feature = "analysis")]
     fn test_roundtrip() {
@@ -5,10 +5,11 @@ mod sections;
 mod timing;
 
 use super::types::OsuBeatmap;
+use crate::codec::DecodeLimits;
 use crate::error::{RoxError, RoxResult};
 
 pub use objects::parse_hit_object;
-pub use sections::{parse_difficulty, parse_event, parse_general, parse_metadata};
+pub use sections::{parse_difficulty, parse_editor, parse_event, parse_general, parse_metadata};
 pub use timing::parse_timing_point;
 
 /// Current section being parsed.
@@ -43,6 +44,41 @@ const MAX_FILE_SIZE: usize = 100 * 1024 * 1024;
 /// - The beatmap is not in mania mode (mode != 3)
 /// - The file is larger than 100MB
 pub fn parse(data: &[u8]) -> RoxResult<OsuBeatmap> {
+    parse_with_progress(data, |_, _| {})
+}
+
+/// Like [`parse`], but reports `(bytes_processed, total_bytes)` to `progress`
+/// by the byte offset of the current line.
+///
+/// Calls are throttled to roughly one per percent of the input, and the last
+/// one is always `(total_bytes, total_bytes)`.
+///
+/// # Errors
+///
+/// Same as [`parse`].
+pub fn parse_with_progress(
+    data: &[u8],
+    progress: impl FnMut(usize, usize),
+) -> RoxResult<OsuBeatmap> {
+    parse_limited(data, &DecodeLimits::default(), progress)
+}
+
+/// Like [`parse`], but stops with [`RoxError::LimitExceeded`] as soon as a
+/// hit object or timing point past `limits` is reached.
+///
+/// # Errors
+///
+/// Same as [`parse`], plus exceeded limits.
+pub fn parse_with_limits(data: &[u8], limits: &DecodeLimits) -> RoxResult<OsuBeatmap> {
+    limits.check_file_size(data.len())?;
+    parse_limited(data, limits, |_, _| {})
+}
+
+fn parse_limited(
+    data: &[u8],
+    limits: &DecodeLimits,
+    mut progress: impl FnMut(usize, usize),
+) -> RoxResult<OsuBeatmap> {
     if data.len() > MAX_FILE_SIZE {
         return Err(RoxError::InvalidFormat(format!(
             "File too large: {} bytes (max {}MB)",
@@ -59,14 +95,24 @@ pub fn parse(data: &[u8]) -> RoxResult<OsuBeatmap> {
     let mut beatmap = OsuBeatmap::default();
     // Estimate capacity based on file size (approx 40 bytes per HitObject line)
     // This reduces reallocations for large files
-    beatmap.hit_objects.reserve(data.len() / 40);
+    beatmap
+        .hit_objects
+        .reserve((data.len() / 40).min(limits.max_notes));
     let mut section = Section::None;
 
     let mut start = 0;
     let mut line_idx = 0;
+    let total = data.len();
+    let step = (total / 100).max(1);
+    let mut next_report = step;
 
     // Iterate over newlines using SIMD-accelerated memchr
     for end in memchr::memchr_iter(b'\n', data) {
+        if start >= next_report {
+            progress(start, total);
+            next_report = start + step;
+        }
+
         let mut line_bytes = &data[start..end];
 
         // Handle CRLF (trim \r)
@@ -74,7 +120,7 @@ pub fn parse(data: &[u8]) -> RoxResult<OsuBeatmap> {
             line_bytes = &line_bytes[..line_bytes.len() - 1];
         }
 
-        process_line(line_bytes, line_idx, &mut section, &mut beatmap);
+        process_line(line_bytes, line_idx, &mut section, &mut beatmap, limits)?;
 
         start = end + 1;
         line_idx += 1;
@@ -83,9 +129,10 @@ pub fn parse(data: &[u8]) -> RoxResult<OsuBeatmap> {
     // Process the last line if there is no trailing newline
     if start < data.len() {
         let line_bytes = &data[start..];
-        process_line(line_bytes, line_idx, &mut section, &mut beatmap);
+        process_line(line_bytes, line_idx, &mut section, &mut beatmap, limits)?;
     }
 
+    progress(total, total);
     Ok(beatmap)
 }
 
@@ -94,22 +141,34 @@ fn process_line(
     line_idx: usize,
     section: &mut Section,
     beatmap: &mut OsuBeatmap,
-) {
+    limits: &DecodeLimits,
+) -> RoxResult<()> {
     if is_skippable(line_bytes) {
-        return;
+        return Ok(());
     }
 
     if let Some(new_section) = try_parse_section(line_bytes) {
         *section = new_section;
-        return;
+        return Ok(());
     }
 
     if is_format_version(line_bytes) {
         parse_format_version(line_bytes, beatmap);
-        return;
+        return Ok(());
     }
 
-    handle_section_content(section, line_bytes, line_idx, beatmap);
+    handle_section_content(section, line_bytes, line_idx, beatmap, limits)
+}
+
+/// Check whether `data` looks like a .osu file without parsing it.
+///
+/// Accepts the `osu file format v` header (after an optional BOM) or, for
+/// header-less files, a `[HitObjects]` section.
+#[must_use]
+pub fn has_header(data: &[u8]) -> bool {
+    let data = data.strip_prefix(b"\xEF\xBB\xBF").unwrap_or(data);
+    is_format_version(data.trim_ascii_start())
+        || memchr::memmem::find(data, b"[HitObjects]").is_some()
 }
 
 fn is_skippable(line_bytes: &[u8]) -> bool {
@@ -153,19 +212,22 @@ fn handle_section_content(
     line_bytes: &[u8],
     line_idx: usize,
     beatmap: &mut OsuBeatmap,
-) {
+    limits: &DecodeLimits,
+) -> RoxResult<()> {
     match section {
         Section::HitObjects => {
             if let Some(ho) =
                 crate::codec::formats::osu::parser::objects::parse_hit_object_bytes(line_bytes)
             {
+                limits.check_notes(beatmap.hit_objects.len() + 1)?;
                 beatmap.hit_objects.push(ho);
             } else {
                 let line = unsafe { std::str::from_utf8_unchecked(line_bytes) };
                 tracing::warn!(line = line_idx + 1, "Failed to parse hit object: {}", line);
             }
+            Ok(())
         }
-        _ => handle_text_section(section, line_bytes, line_idx, beatmap),
+        _ => handle_text_section(section, line_bytes, line_idx, beatmap, limits),
     }
 }
 
@@ -174,16 +236,19 @@ fn handle_text_section(
     line_bytes: &[u8],
     line_idx: usize,
     beatmap: &mut OsuBeatmap,
-) {
+    limits: &DecodeLimits,
+) -> RoxResult<()> {
     let line = unsafe { std::str::from_utf8_unchecked(line_bytes) };
     let line = line.trim();
     match section {
         Section::General => parse_general(line, &mut beatmap.general),
+        Section::Editor => parse_editor(line, &mut beatmap.editor),
         Section::Metadata => parse_metadata(line, &mut beatmap.metadata),
         Section::Difficulty => parse_difficulty(line, &mut beatmap.difficulty),
         Section::Events => parse_event(line, &mut beatmap.background),
         Section::TimingPoints => {
             if let Some(tp) = parse_timing_point(line) {
+                limits.check_timing_points(beatmap.timing_points.len() + 1)?;
                 beatmap.timing_points.push(tp);
             } else {
                 tracing::warn!(
@@ -194,8 +259,9 @@ fn handle_text_section(
             }
         }
         Section::HitObjects => unreachable!(),
-        Section::None | Section::Editor => {}
+        Section::None => {}
     }
+    Ok(())
 }
 
 #[cfg(test)]
@@ -272,6 +338,14 @@ mod tests {
         assert_eq!(ho3.column(7), 3); // center
     }
 
+    #[test]
+    fn test_parse_editor_bookmarks() {
+        let data = b"osu file format v14\n\n[Editor]\nBookmarks: 1000,2500,4000\nBeatDivisor: 4\n";
+        let beatmap = parse(data).unwrap();
+
+        assert_eq!(beatmap.editor.bookmarks, vec![1000, 2500, 4000]);
+    }
+
     #[test]
     fn test_parse_full_sample() {
         let data = crate::test_utils::get_test_asset("osu/mania_7k.osu");
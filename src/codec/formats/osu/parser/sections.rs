@@ -1,4 +1,4 @@
-use super::super::types::{OsuDifficulty, OsuGeneral, OsuMetadata};
+use super::super::types::{OsuDifficulty, OsuEditor, OsuGeneral, OsuMetadata};
 
 pub fn parse_general(line: &str, general: &mut OsuGeneral) {
     if let Some((key, value)) = line.split_once(':') {
@@ -7,12 +7,39 @@ pub fn parse_general(line: &str, general: &mut OsuGeneral) {
             "AudioFilename" => general.audio_filename = value.to_string(),
             "AudioLeadIn" => general.audio_lead_in = parse_field(value, "AudioLeadIn", 0),
             "PreviewTime" => general.preview_time = parse_field(value, "PreviewTime", -1),
+            "Countdown" => general.countdown = Some(parse_field(value, "Countdown", 1)),
+            "SampleSet" => general.sample_set = Some(value.to_string()),
+            "StackLeniency" => {
+                general.stack_leniency = Some(parse_field(value, "StackLeniency", 0.7));
+            }
             "Mode" => general.mode = parse_field(value, "Mode", 0),
+            "SpecialStyle" => {
+                general.special_style = parse_field::<u8>(value, "SpecialStyle", 0) == 1;
+            }
             _ => {}
         }
     }
 }
 
+pub fn parse_editor(line: &str, editor: &mut OsuEditor) {
+    if let Some((key, value)) = line.split_once(':')
+        && key.trim() == "Bookmarks"
+    {
+        // Format: Bookmarks: 1000,2000,3000
+        editor.bookmarks = value
+            .split(',')
+            .map(str::trim)
+            .filter(|s| !s.is_empty())
+            .filter_map(|s| {
+                s.parse().ok().or_else(|| {
+                    tracing::warn!("Failed to parse bookmark: '{}', skipping", s);
+                    None
+                })
+            })
+            .collect();
+    }
+}
+
 pub fn parse_metadata(line: &str, metadata: &mut OsuMetadata) {
     if let Some((key, value)) = line.split_once(':') {
         let value = value.trim();
@@ -50,6 +77,9 @@ pub fn parse_difficulty(line: &str, difficulty: &mut OsuDifficulty) {
                 difficulty.overall_difficulty = parse_field(value, "OverallDifficulty", 5.0);
             }
             "HPDrainRate" => difficulty.hp_drain_rate = parse_field(value, "HPDrainRate", 5.0),
+            "ApproachRate" => {
+                difficulty.approach_rate = Some(parse_field(value, "ApproachRate", 5.0));
+            }
             _ => {}
         }
     }
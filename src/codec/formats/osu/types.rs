@@ -5,6 +5,7 @@
 pub struct OsuBeatmap {
     pub format_version: u8,
     pub general: OsuGeneral,
+    pub editor: OsuEditor,
     pub metadata: OsuMetadata,
     pub difficulty: OsuDifficulty,
     pub background: Option<String>,
@@ -18,7 +19,19 @@ pub struct OsuGeneral {
     pub audio_filename: String,
     pub audio_lead_in: i32,
     pub preview_time: i32,
+    pub countdown: Option<u8>,
+    pub sample_set: Option<String>,
+    pub stack_leniency: Option<f32>,
     pub mode: u8,
+    /// `SpecialStyle: 1`: the first column is a special (scratch) lane.
+    pub special_style: bool,
+}
+
+/// `[Editor]` section.
+#[derive(Debug, Clone, Default)]
+pub struct OsuEditor {
+    /// Editor bookmarks in milliseconds.
+    pub bookmarks: Vec<i32>,
 }
 
 /// `[Metadata]` section.
@@ -36,6 +49,26 @@ pub struct OsuMetadata {
     pub beatmap_set_id: Option<i32>,
 }
 
+impl OsuMetadata {
+    /// ASCII `Title`, if it differs from `TitleUnicode`.
+    #[must_use]
+    pub fn romanized_title(&self) -> Option<&str> {
+        romanized(&self.title, self.title_unicode.as_deref())
+    }
+
+    /// ASCII `Artist`, if it differs from `ArtistUnicode`.
+    #[must_use]
+    pub fn romanized_artist(&self) -> Option<&str> {
+        romanized(&self.artist, self.artist_unicode.as_deref())
+    }
+}
+
+fn romanized<'a>(ascii: &'a str, unicode: Option<&str>) -> Option<&'a str> {
+    unicode
+        .filter(|unicode| !ascii.is_empty() && *unicode != ascii)
+        .map(|_| ascii)
+}
+
 /// `[Difficulty]` section.
 #[derive(Debug, Clone, Default)]
 pub struct OsuDifficulty {
@@ -43,6 +76,8 @@ pub struct OsuDifficulty {
     pub circle_size: f32,
     pub overall_difficulty: f32,
     pub hp_drain_rate: f32,
+    /// Absent in old beatmaps, where osu! uses the OD.
+    pub approach_rate: Option<f32>,
 }
 
 /// A timing point (BPM or SV change).
@@ -68,6 +103,9 @@ pub struct OsuTimingPoint {
     pub effects: u8,
 }
 
+/// Bit 0 of `OsuTimingPoint::effects`: kiai time.
+pub const EFFECT_KIAI: u8 = 1;
+
 impl OsuTimingPoint {
     /// Get BPM if this is an uninherited point.
     #[must_use]
@@ -94,6 +132,18 @@ impl OsuTimingPoint {
     }
 }
 
+/// Column of an osu!mania X position: `floor(x * key_count / 512)`.
+///
+/// The encoder's `column_to_x` is the inverse of this mapping.
+#[must_use]
+pub fn x_to_column(x: i32, key_count: u8) -> u8 {
+    let column = (x * i32::from(key_count)) / 512;
+    // Safe: column is always 0..key_count which fits in u8
+    #[allow(clippy::cast_sign_loss, clippy::cast_possible_truncation)]
+    let result = column as u8;
+    result
+}
+
 /// A hit object (note).
 #[derive(Debug, Clone)]
 pub struct OsuHitObject {
@@ -106,6 +156,8 @@ pub struct OsuHitObject {
     /// Object type bitfield.
     /// Bit 0: Circle (tap)
     /// Bit 1: Slider (not used in mania)
+    /// Bit 2: New combo (meaningless in mania, ignored)
+    /// Bits 4-6: Combo color skip (ignored)
     /// Bit 7: Hold note
     pub object_type: u8,
     /// Hit sound.
@@ -124,6 +176,9 @@ impl OsuHitObject {
     }
 
     /// Check if this is a tap note.
+    ///
+    /// Any object with the circle bit (1) that isn't a hold is a tap; new-combo
+    /// and combo-color bits are ignored.
     #[must_use]
     pub fn is_tap(&self) -> bool {
         (self.object_type & 1) != 0 && !self.is_hold()
@@ -132,16 +187,17 @@ impl OsuHitObject {
     /// Calculate column index from X position.
     #[must_use]
     pub fn column(&self, key_count: u8) -> u8 {
-        let column = (self.x * i32::from(key_count)) / 512;
-        // Safe: column is always 0..key_count which fits in u8
-        #[allow(clippy::cast_sign_loss, clippy::cast_possible_truncation)]
-        let result = column as u8;
-        result
+        x_to_column(self.x, key_count)
     }
 
     /// Get duration in milliseconds for hold notes.
+    ///
+    /// Returns 0 if the end time is missing or not after the start time
+    /// (malformed hold).
     #[must_use]
     pub fn duration_ms(&self) -> i32 {
-        self.end_time.map_or(0, |e| e - self.time)
+        self.end_time
+            .filter(|&end| end > self.time)
+            .map_or(0, |end| end - self.time)
     }
 }
@@ -2,6 +2,7 @@
 
 use crate::codec::Decoder;
 use crate::error::RoxResult;
+use crate::model::time::ms_f64_to_us;
 use crate::model::{Metadata, Note, RoxChart, TimingPoint};
 
 use super::parser;
@@ -52,44 +53,16 @@ impl QuaDecoder {
             ..Default::default()
         };
 
-        // Convert timing points (BPM)
-        for tp in &qua.timing_points {
-            // Safe: time in ms fits in i64 after multiplying by 1000
-            #[allow(clippy::cast_possible_truncation)]
-            let time_us = (tp.start_time * 1000.0) as i64;
-            let mut timing = TimingPoint::bpm(time_us, tp.bpm);
-            timing.signature = tp
-                .signature
-                .as_ref()
-                .map_or(4, super::types::TimeSignature::beats);
-            chart.timing_points.push(timing);
-        }
-
-        // Convert slider velocities to SV timing points
-        for sv in &qua.slider_velocities {
-            #[allow(clippy::cast_possible_truncation)]
-            let time_us = (sv.start_time * 1000.0) as i64;
-            #[allow(clippy::cast_possible_truncation)]
-            let multiplier = sv.multiplier as f32;
-            chart
-                .timing_points
-                .push(TimingPoint::sv(time_us, multiplier));
-        }
-
-        // Sort timing points by time
-        chart.timing_points.sort_by_key(|tp| tp.time_us);
+        chart.timing_points = timing_points(qua);
 
         // Convert hit objects
         for ho in &qua.hit_objects {
-            #[allow(clippy::cast_possible_truncation)]
-            let time_us = (ho.start_time * 1000.0) as i64;
+            let time_us = ms_f64_to_us(ho.start_time);
             // Quaver lanes are 1-indexed
             let column = ho.lane.saturating_sub(1);
 
             let note = if let Some(end_time) = ho.end_time {
-                #[allow(clippy::cast_possible_truncation)]
-                let end_us = (end_time * 1000.0) as i64;
-                let duration_us = end_us - time_us;
+                let duration_us = ms_f64_to_us(end_time) - time_us;
                 Note::hold(time_us, duration_us, column)
             } else {
                 Note::tap(time_us, column)
@@ -105,7 +78,63 @@ impl QuaDecoder {
     }
 }
 
+/// BPM and SV points of a `.qua`, sorted by time.
+///
+/// In ROX a BPM point resets the scroll speed to 1.0 (as in osu!), while a
+/// Quaver SV lasts until the next SV. So an SV point repeating the speed in
+/// effect is added after each BPM point that doesn't already have one, which
+/// also carries `InitialScrollVelocity` up to the first SV.
+// Exact float comparison on purpose: only a speed of exactly 1.0 needs nothing
+#[allow(clippy::float_cmp)]
+fn timing_points(qua: &QuaChart) -> Vec<TimingPoint> {
+    let mut points: Vec<TimingPoint> = qua
+        .timing_points
+        .iter()
+        .map(|tp| {
+            let mut timing = TimingPoint::bpm(ms_f64_to_us(tp.start_time), tp.bpm);
+            timing.signature = tp
+                .signature
+                .as_ref()
+                .map_or(4, super::types::TimeSignature::beats);
+            timing
+        })
+        .collect();
+    #[allow(clippy::cast_possible_truncation)]
+    points.extend(
+        qua.slider_velocities
+            .iter()
+            .map(|sv| TimingPoint::sv(ms_f64_to_us(sv.start_time), sv.multiplier as f32)),
+    );
+    // Stable: at the same time, BPM points stay before SV points
+    points.sort_by_key(|tp| tp.time_us);
+
+    let mut result = Vec::with_capacity(points.len());
+    let mut scroll_speed = qua.initial_scroll_velocity;
+    let mut points = points.into_iter().peekable();
+    while let Some(tp) = points.next() {
+        if tp.is_inherited {
+            scroll_speed = tp.scroll_speed;
+            result.push(tp);
+            continue;
+        }
+
+        let time_us = tp.time_us;
+        result.push(tp);
+        let has_sv = points
+            .peek()
+            .is_some_and(|next| next.is_inherited && next.time_us == time_us);
+        if scroll_speed != 1.0 && !has_sv {
+            result.push(TimingPoint::sv(time_us, scroll_speed));
+        }
+    }
+    result
+}
+
 impl Decoder for QuaDecoder {
+    fn probe(data: &[u8]) -> bool {
+        memchr::memmem::find(data, b"AudioFile:").is_some()
+    }
+
     fn decode(data: &[u8]) -> RoxResult<RoxChart> {
         let qua = parser::parse(data)?;
         Ok(Self::from_qua(&qua))
@@ -127,4 +156,69 @@ mod tests {
         assert!(!chart.notes.is_empty());
         assert!(!chart.timing_points.is_empty());
     }
+
+    #[test]
+    fn test_decode_asset_4k_svs() {
+        let data = crate::test_utils::get_test_asset("quaver/4K.qua");
+        let qua = parser::parse(&data).unwrap();
+        let chart = QuaDecoder::from_qua(&qua);
+
+        let svs: Vec<&TimingPoint> = chart
+            .timing_points
+            .iter()
+            .filter(|tp| tp.is_inherited)
+            .collect();
+        assert_eq!(svs.len(), qua.slider_velocities.len());
+        assert_eq!(svs.len(), 95);
+        #[allow(clippy::cast_possible_truncation)]
+        let first = qua.slider_velocities[0].multiplier as f32;
+        assert_eq!(svs[0].scroll_speed, first);
+        assert_eq!(svs[0].time_us, 33_520_000);
+        // 33520.015625ms rounds up rather than truncating
+        assert_eq!(svs[1].time_us, 33_520_016);
+        assert!(chart.timing_points.is_sorted_by_key(|tp| tp.time_us));
+    }
+
+    #[test]
+    fn test_sv_carries_over_bpm_changes() {
+        let data = b"AudioFile: audio.mp3
+Mode: Keys4
+InitialScrollVelocity: 0.5
+TimingPoints:
+- Bpm: 120
+- StartTime: 4000
+  Bpm: 180
+- StartTime: 8000
+  Bpm: 240
+SliderVelocities:
+- StartTime: 2000
+  Multiplier: 2
+- StartTime: 8000
+  Multiplier: 1.5
+HitObjects:
+- StartTime: 500
+  Lane: 1
+";
+        let chart = <QuaDecoder as Decoder>::decode(data).unwrap();
+
+        let points: Vec<(i64, bool, f32)> = chart
+            .timing_points
+            .iter()
+            .map(|tp| (tp.time_us, tp.is_inherited, tp.scroll_speed))
+            .collect();
+        assert_eq!(
+            points,
+            vec![
+                (0, false, 1.0),
+                // InitialScrollVelocity before the first SV
+                (0, true, 0.5),
+                (2_000_000, true, 2.0),
+                (4_000_000, false, 1.0),
+                // The 2x SV is still in effect after the BPM change
+                (4_000_000, true, 2.0),
+                (8_000_000, false, 1.0),
+                (8_000_000, true, 1.5),
+            ]
+        );
+    }
 }
@@ -1,16 +1,53 @@
 //! Encoder for converting `RoxChart` to .qua format.
 
 use crate::codec::Encoder;
-use crate::error::RoxResult;
-use crate::model::RoxChart;
+use crate::error::{RoxError, RoxResult};
+use crate::model::{NoteType, RoxChart};
 
-use super::types::{QuaChart, QuaHitObject, QuaSliderVelocity, QuaTimingPoint};
+use super::types::{QuaChart, QuaHitObject, QuaMode, QuaSliderVelocity, QuaTimingPoint};
 
 /// Encoder for Quaver beatmaps.
 pub struct QuaEncoder;
 
+/// What to write a burst (roll) note as. Quaver has no rolls.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum BurstPolicy {
+    /// A long note covering the whole roll. Players hold instead of tapping
+    /// repeatedly, but the note keeps its length.
+    #[default]
+    Hold,
+    /// Fail with [`RoxError::InvalidFormat`] at the first burst.
+    Reject,
+}
+
 impl Encoder for QuaEncoder {
+    /// Validates the chart first (see [`RoxChart::validate`]), so an invalid
+    /// chart is reported instead of written as a broken file. Bursts are
+    /// written as long notes ([`BurstPolicy::Hold`]).
     fn encode(chart: &RoxChart) -> RoxResult<Vec<u8>> {
+        chart.validate()?;
+        Self::encode_unchecked(chart)
+    }
+
+    /// Bursts are written as long notes ([`BurstPolicy::Hold`]).
+    fn encode_unchecked(chart: &RoxChart) -> RoxResult<Vec<u8>> {
+        Self::encode_impl(chart, BurstPolicy::default())
+    }
+}
+
+impl QuaEncoder {
+    /// Validate and encode, writing bursts according to `bursts`.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the chart is invalid, or if it contains a burst
+    /// and `bursts` is [`BurstPolicy::Reject`].
+    pub fn encode_with_burst_policy(chart: &RoxChart, bursts: BurstPolicy) -> RoxResult<Vec<u8>> {
+        chart.validate()?;
+        Self::encode_impl(chart, bursts)
+    }
+
+    fn encode_impl(chart: &RoxChart, bursts: BurstPolicy) -> RoxResult<Vec<u8>> {
         use compact_str::CompactString;
 
         let mut qua = QuaChart {
@@ -53,6 +90,12 @@ impl Encoder for QuaEncoder {
                     .join(" "),
             ),
             description: None,
+            // Quaver only has 4K and 7K
+            mode: if chart.key_count() == 7 {
+                QuaMode::Keys7
+            } else {
+                QuaMode::Keys4
+            },
             initial_scroll_velocity: 1.0,
             bpm_does_not_affect_sv: true,
             ..Default::default()
@@ -80,15 +123,21 @@ impl Encoder for QuaEncoder {
             }
         }
 
-        // Convert notes
-        for note in &chart.notes {
+        // Convert notes (Quaver has no fakes)
+        for note in chart.notes.iter().filter(|n| !n.fake) {
             #[allow(clippy::cast_precision_loss)]
             let start_time = note.time_us as f64 / 1000.0;
             // Quaver lanes are 1-indexed
             let lane = note.column + 1;
 
             let end_time = match &note.note_type {
-                crate::model::NoteType::Hold { duration_us } => {
+                NoteType::Burst { .. } if bursts == BurstPolicy::Reject => {
+                    return Err(RoxError::InvalidFormat(format!(
+                        "Quaver has no roll notes (burst at {}µs in column {})",
+                        note.time_us, note.column
+                    )));
+                }
+                NoteType::Hold { duration_us } | NoteType::Burst { duration_us } => {
                     #[allow(clippy::cast_precision_loss)]
                     let end = (note.time_us + duration_us) as f64 / 1000.0;
                     Some(end)
@@ -105,9 +154,8 @@ impl Encoder for QuaEncoder {
         }
 
         // Serialize to YAML
-        let yaml = serde_yaml::to_string(&qua).map_err(|e| {
-            crate::error::RoxError::InvalidFormat(format!("YAML encoding error: {e}"))
-        })?;
+        let yaml = serde_yaml::to_string(&qua)
+            .map_err(|e| RoxError::InvalidFormat(format!("YAML encoding error: {e}")))?;
 
         Ok(yaml.into_bytes())
     }
@@ -149,4 +197,26 @@ mod tests {
             }
         }
     }
+
+    #[test]
+    fn test_burst_as_long_note() {
+        use super::*;
+        use crate::codec::Decoder;
+        use crate::codec::formats::qua::QuaDecoder;
+        use crate::model::{Note, TimingPoint};
+
+        let mut chart = RoxChart::new(4);
+        chart.timing_points.push(TimingPoint::bpm(0, 120.0));
+        chart.notes.push(Note::burst(1_000_000, 500_000, 2));
+
+        let encoded = QuaEncoder::encode(&chart).unwrap();
+        let decoded = QuaDecoder::decode(&encoded).unwrap();
+        assert_eq!(decoded.notes.len(), 1);
+        assert!(decoded.notes[0].is_hold());
+        assert_eq!(decoded.notes[0].column, 2);
+        assert_eq!(decoded.notes[0].end_time_us(), 1_500_000);
+
+        let err = QuaEncoder::encode_with_burst_policy(&chart, BurstPolicy::Reject).unwrap_err();
+        assert!(matches!(err, RoxError::InvalidFormat(_)));
+    }
 }
@@ -12,4 +12,4 @@ pub mod parser;
 pub mod types;
 
 pub use decoder::QuaDecoder;
-pub use encoder::QuaEncoder;
+pub use encoder::{BurstPolicy, QuaEncoder};
@@ -1,11 +1,13 @@
 use std::io::Read;
 
 use rkyv::rancor::Error as RkyvError;
+use rkyv::util::AlignedVec;
 
-use crate::codec::Decoder;
+use crate::codec::{DecodeLimits, Decoder};
 use crate::error::{RoxError, RoxResult};
-use crate::model::{ROX_MAGIC, RoxChart};
+use crate::model::{ArchivedRoxChart, ROX_MAGIC, ROX_VERSION, RoxChart};
 
+use super::legacy::{self, ArchivedRoxChartV2, RoxChartV2};
 use super::{MAX_FILE_SIZE, RoxCodec};
 
 /// Decompress data (zstd on native, passthrough on WASM).
@@ -23,6 +25,35 @@ fn decompress(data: &[u8]) -> RoxResult<Vec<u8>> {
     Ok(data.to_vec())
 }
 
+/// Decompress at most `max` bytes, failing instead of reading past it.
+#[cfg(not(target_arch = "wasm32"))]
+fn decompress_limited(data: &[u8], max: usize) -> RoxResult<Vec<u8>> {
+    let decoder = zstd::stream::Decoder::new(data)?;
+    let mut decompressed = Vec::new();
+    // One byte past the cap tells "exactly max" apart from "more than max"
+    let cap = u64::try_from(max).unwrap_or(u64::MAX).saturating_add(1);
+    decoder.take(cap).read_to_end(&mut decompressed)?;
+    check_decompressed(decompressed.len(), max)?;
+    Ok(decompressed)
+}
+
+#[cfg(target_arch = "wasm32")]
+fn decompress_limited(data: &[u8], max: usize) -> RoxResult<Vec<u8>> {
+    check_decompressed(data.len(), max)?;
+    Ok(data.to_vec())
+}
+
+fn check_decompressed(len: usize, max: usize) -> RoxResult<()> {
+    if len > max {
+        return Err(RoxError::LimitExceeded {
+            what: "decompressed bytes",
+            found: len,
+            max,
+        });
+    }
+    Ok(())
+}
+
 /// Decode delta-encoded note timestamps back to absolute timestamps.
 fn delta_decode_notes(chart: &mut RoxChart) {
     let mut accumulated_time: i64 = 0;
@@ -33,33 +64,136 @@ fn delta_decode_notes(chart: &mut RoxChart) {
     }
 }
 
-impl Decoder for RoxCodec {
-    fn decode(data: &[u8]) -> RoxResult<RoxChart> {
-        // Check magic bytes
-        if data.len() < 4 || data[..4] != ROX_MAGIC {
-            return Err(RoxError::InvalidFormat(
-                "Invalid ROX file: missing magic bytes".into(),
-            ));
+/// Fail unless `data` starts with the ROX magic bytes.
+fn check_magic(data: &[u8]) -> RoxResult<()> {
+    if data.len() < 4 || data[..4] != ROX_MAGIC {
+        return Err(RoxError::InvalidFormat(
+            "Invalid ROX file: missing magic bytes".into(),
+        ));
+    }
+    Ok(())
+}
+
+/// A validated archive in one of the layouts `RoxCodec` can read.
+pub(super) enum Archived<'a> {
+    Current(&'a ArchivedRoxChart),
+    V2(&'a ArchivedRoxChartV2),
+}
+
+impl Archived<'_> {
+    fn counts(&self) -> (usize, usize) {
+        match self {
+            Self::Current(chart) => (chart.notes.len(), chart.timing_points.len()),
+            Self::V2(chart) => (chart.notes.len(), chart.timing_points.len()),
         }
+    }
 
-        if data.len() > MAX_FILE_SIZE {
-            return Err(RoxError::InvalidFormat(format!(
-                "File too large: {} bytes (max {}MB)",
-                data.len(),
-                MAX_FILE_SIZE / 1024 / 1024
-            )));
+    fn deserialize(self) -> RoxResult<RoxChart> {
+        match self {
+            Self::Current(chart) => rkyv::deserialize::<RoxChart, RkyvError>(chart),
+            Self::V2(chart) => rkyv::deserialize::<RoxChartV2, RkyvError>(chart).map(Into::into),
         }
+        .map_err(|e| RoxError::Deserialize(e.to_string()))
+    }
+}
 
-        // Decompress the data after magic bytes
-        let decompressed = decompress(&data[4..])?;
+/// Validate an aligned rkyv payload and work out which layout it uses.
+///
+/// The version byte is only meaningful once the layout is known, so the
+/// current layout is tried first and trusted if it claims [`ROX_VERSION`].
+/// Otherwise the v2 layout is tried; anything else that validates as the
+/// current layout is read as such, like before versions were checked.
+pub(super) fn archived(bytes: &[u8]) -> RoxResult<Archived<'_>> {
+    let current = rkyv::access::<ArchivedRoxChart, RkyvError>(bytes);
+    if let Ok(chart) = current
+        && chart.version == ROX_VERSION
+    {
+        return Ok(Archived::Current(chart));
+    }
+    if let Ok(chart) = rkyv::access::<ArchivedRoxChartV2, RkyvError>(bytes)
+        && chart.version == legacy::V2
+    {
+        return Ok(Archived::V2(chart));
+    }
+    current
+        .map(Archived::Current)
+        .map_err(|e| RoxError::Deserialize(e.to_string()))
+}
+
+/// Copy a payload into a buffer rkyv can read in place.
+pub(super) fn aligned(payload: &[u8]) -> AlignedVec {
+    let mut bytes = AlignedVec::with_capacity(payload.len());
+    bytes.extend_from_slice(payload);
+    bytes
+}
+
+/// Check the header and return the decompressed rkyv payload.
+pub(super) fn payload(data: &[u8]) -> RoxResult<Vec<u8>> {
+    check_magic(data)?;
+
+    if data.len() > MAX_FILE_SIZE {
+        return Err(RoxError::InvalidFormat(format!(
+            "File too large: {} bytes (max {}MB)",
+            data.len(),
+            MAX_FILE_SIZE / 1024 / 1024
+        )));
+    }
+
+    // Decompress the data after magic bytes
+    decompress(&data[4..])
+}
+
+impl RoxCodec {
+    /// Decode untrusted data within caller-controlled [`DecodeLimits`].
+    ///
+    /// Decompression stops at `max_decompressed_bytes`, and note and timing
+    /// point counts are read from the archive before anything is
+    /// deserialized.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`RoxError::LimitExceeded`] if a limit is exceeded, or the same
+    /// errors as [`decode`](Decoder::decode).
+    pub fn decode_with_limits(data: &[u8], limits: &DecodeLimits) -> RoxResult<RoxChart> {
+        limits.check_file_size(data.len())?;
+        check_magic(data)?;
+        let decompressed = decompress_limited(&data[4..], limits.max_decompressed_bytes)?;
+
+        let bytes = aligned(&decompressed);
+        let archived = archived(&bytes)?;
+        let (notes, timing_points) = archived.counts();
+        limits.check_counts(notes, timing_points)?;
 
-        // Deserialize the chart with rkyv
-        let mut chart: RoxChart = rkyv::from_bytes::<RoxChart, RkyvError>(&decompressed)
-            .map_err(|e| RoxError::Deserialize(e.to_string()))?;
+        Ok(finish(archived.deserialize()?))
+    }
+}
+
+impl Decoder for RoxCodec {
+    fn probe(data: &[u8]) -> bool {
+        data.starts_with(&ROX_MAGIC)
+    }
+
+    fn decode(data: &[u8]) -> RoxResult<RoxChart> {
+        let bytes = aligned(&payload(data)?);
+
+        // Deserialize the chart with rkyv, migrating older layouts
+        let chart = archived(&bytes)?.deserialize()?;
+
+        Ok(finish(chart))
+    }
+}
 
-        // Restore absolute timestamps from deltas
-        delta_decode_notes(&mut chart);
+/// Post-process a freshly deserialized chart.
+fn finish(mut chart: RoxChart) -> RoxChart {
+    // Restore absolute timestamps from deltas
+    delta_decode_notes(&mut chart);
 
-        Ok(chart)
+    // Files written by `RoxCodec` are always sorted, but hand-crafted ones
+    // may not be, and every query assumes sorted notes
+    if !chart.is_sorted() {
+        tracing::warn!("ROX file has unsorted notes or timing points, sorting");
+        chart.timing_points.sort_by_key(|tp| tp.time_us);
+        chart.notes.sort_by_key(|n| n.time_us);
     }
+    chart
 }
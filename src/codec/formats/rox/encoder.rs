@@ -1,10 +1,16 @@
 use std::io::Write;
 
-use rkyv::rancor::Error as RkyvError;
+use rkyv::rancor::{Error as RkyvError, Fallible};
+use rkyv::ser::writer::IoWriter;
+use rkyv::ser::{Allocator, Writer};
+use rkyv::vec::{ArchivedVec, VecResolver};
+use rkyv::{Archive, Place, Serialize, munge::munge};
 
 use crate::codec::Encoder;
 use crate::error::{RoxError, RoxResult};
-use crate::model::{ROX_MAGIC, RoxChart};
+use crate::model::{
+    ArchivedRoxChart, Hitsound, Metadata, Note, ROX_MAGIC, ROX_VERSION, RoxChart, TimingPoint,
+};
 
 use super::RoxCodec;
 
@@ -13,54 +19,132 @@ use super::RoxCodec;
 #[cfg(not(target_arch = "wasm32"))]
 const COMPRESSION_LEVEL: i32 = 3;
 
-/// Compress data (zstd on native, passthrough on WASM).
+/// Serialize `chart` and compress it straight into `writer` (zstd on native,
+/// passthrough on WASM).
 #[cfg(not(target_arch = "wasm32"))]
-fn compress(data: &[u8]) -> RoxResult<Vec<u8>> {
-    let mut encoder = zstd::stream::Encoder::new(Vec::new(), COMPRESSION_LEVEL)?;
-    encoder.write_all(data)?;
-    Ok(encoder.finish()?)
+fn compress_into<W: Write>(chart: &DeltaChart<'_>, writer: W) -> RoxResult<()> {
+    let encoder = zstd::stream::Encoder::new(writer, COMPRESSION_LEVEL)?;
+    serialize_into(chart, encoder)?.finish()?;
+    Ok(())
 }
 
 #[cfg(target_arch = "wasm32")]
-fn compress(data: &[u8]) -> RoxResult<Vec<u8>> {
-    // No compression on WASM - just return data as-is
-    Ok(data.to_vec())
+fn compress_into<W: Write>(chart: &DeltaChart<'_>, writer: W) -> RoxResult<()> {
+    // No compression on WASM - write data as-is
+    serialize_into(chart, writer)?;
+    Ok(())
 }
 
-/// Apply delta encoding to note timestamps for better compression.
-/// Returns a chart with delta-encoded timestamps.
-fn delta_encode_notes(chart: &RoxChart) -> RoxChart {
-    let mut result = chart.clone();
-    let mut last_time: i64 = 0;
+/// Serialize `chart` with rkyv as it goes, without building the archive in
+/// memory first. rkyv only ever appends, so any [`Write`] will do.
+fn serialize_into<W: Write>(chart: &DeltaChart<'_>, writer: W) -> RoxResult<W> {
+    rkyv::api::high::to_bytes_in::<_, RkyvError>(chart, IoWriter::new(writer))
+        .map(IoWriter::into_inner)
+        .map_err(|e| RoxError::Serialize(e.to_string()))
+}
+
+/// Borrowed view of a chart that archives exactly like [`RoxChart`], with
+/// note timestamps delta-encoded for better compression.
+///
+/// The deltas are computed while serializing, so encoding never copies the
+/// chart.
+struct DeltaChart<'a>(&'a RoxChart);
+
+struct DeltaChartResolver {
+    metadata: <Metadata as Archive>::Resolver,
+    timing_points: VecResolver,
+    notes: VecResolver,
+    hitsounds: VecResolver,
+}
+
+impl Archive for DeltaChart<'_> {
+    type Archived = ArchivedRoxChart;
+    type Resolver = DeltaChartResolver;
 
-    for note in &mut result.notes {
-        let original_time = note.time_us;
-        note.time_us = original_time - last_time; // Store delta
-        last_time = original_time;
+    fn resolve(&self, resolver: Self::Resolver, out: Place<Self::Archived>) {
+        munge!(let ArchivedRoxChart { version, metadata, timing_points, notes, hitsounds } = out);
+        let chart = self.0;
+        // The bytes are always in the current layout, whatever was decoded
+        ROX_VERSION.resolve((), version);
+        chart.metadata.resolve(resolver.metadata, metadata);
+        ArchivedVec::resolve_from_slice(
+            &chart.timing_points,
+            resolver.timing_points,
+            timing_points,
+        );
+        ArchivedVec::resolve_from_len(chart.notes.len(), resolver.notes, notes);
+        ArchivedVec::resolve_from_slice(&chart.hitsounds, resolver.hitsounds, hitsounds);
     }
+}
 
-    result
+impl<S> Serialize<S> for DeltaChart<'_>
+where
+    S: Fallible + Allocator + Writer + ?Sized,
+    Metadata: Serialize<S>,
+    TimingPoint: Serialize<S>,
+    Note: Serialize<S>,
+    Hitsound: Serialize<S>,
+{
+    fn serialize(&self, serializer: &mut S) -> Result<Self::Resolver, S::Error> {
+        let chart = self.0;
+        let deltas = chart.notes.iter().enumerate().map(|(i, note)| {
+            let last_time = i.checked_sub(1).map_or(0, |prev| chart.notes[prev].time_us);
+            Note {
+                time_us: note.time_us - last_time, // Store delta
+                ..note.clone()
+            }
+        });
+        Ok(DeltaChartResolver {
+            metadata: chart.metadata.serialize(serializer)?,
+            timing_points: ArchivedVec::serialize_from_slice(&chart.timing_points, serializer)?,
+            notes: ArchivedVec::serialize_from_iter::<Note, _, _>(deltas, serializer)?,
+            hitsounds: ArchivedVec::serialize_from_slice(&chart.hitsounds, serializer)?,
+        })
+    }
 }
 
-impl Encoder for RoxCodec {
-    fn encode(chart: &RoxChart) -> RoxResult<Vec<u8>> {
+impl RoxCodec {
+    /// Encode a chart and stream the compressed output into `writer`.
+    ///
+    /// Neither the chart nor its archived bytes are copied: the archive is
+    /// streamed through the compressor into `writer` as it is serialized,
+    /// which keeps peak memory down when writing very large charts to files
+    /// or sockets.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the chart fails validation, serialization fails,
+    /// or the writer returns an I/O error.
+    pub fn encode_to_writer<W: Write>(chart: &RoxChart, writer: W) -> RoxResult<()> {
         // Validate before encoding
         chart.validate()?;
+        Self::write_unchecked(chart, writer)
+    }
 
-        // Apply delta encoding for better compression
-        let delta_chart = delta_encode_notes(chart);
-
-        // Serialize the chart with rkyv
-        let encoded = rkyv::to_bytes::<RkyvError>(&delta_chart)
-            .map_err(|e| RoxError::Serialize(e.to_string()))?;
-
-        // Compress the encoded data
-        let compressed = compress(&encoded)?;
+    fn write_unchecked<W: Write>(chart: &RoxChart, mut writer: W) -> RoxResult<()> {
+        // Magic bytes first, then the compressed payload
+        writer.write_all(&ROX_MAGIC)?;
+        compress_into(&DeltaChart(chart), writer)
+    }
+}
 
-        // Start with magic bytes, then compressed data
-        let mut data = ROX_MAGIC.to_vec();
-        data.extend(compressed);
+impl Encoder for RoxCodec {
+    fn encode(chart: &RoxChart) -> RoxResult<Vec<u8>> {
+        let mut data = Vec::new();
+        Self::encode_to_writer(chart, &mut data)?;
+        Ok(data)
+    }
 
+    /// [`encode`](Encoder::encode) re-runs [`RoxChart::validate`], a full
+    /// pass over the notes, on every call. Editors that keep the chart valid
+    /// as it is edited can skip that on each save. The output is the same as
+    /// `encode` for a valid chart, but an invalid one is written as-is:
+    /// out-of-range columns, overlapping notes or a missing BPM point make
+    /// a file other tools may reject, and unsorted notes are re-sorted when
+    /// it is decoded.
+    fn encode_unchecked(chart: &RoxChart) -> RoxResult<Vec<u8>> {
+        let mut data = Vec::new();
+        Self::write_unchecked(chart, &mut data)?;
         Ok(data)
     }
 }
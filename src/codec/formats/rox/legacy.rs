@@ -0,0 +1,115 @@
+//! Archived layout of version 2 ROX files.
+//!
+//! rkyv archives are not self-describing, so every field added to the model
+//! changes the layout. The v2 structs below are frozen copies of the model as
+//! it was when `ROX_VERSION` was 2; files written back then are read through
+//! them and migrated to the current model.
+
+use compact_str::CompactString;
+use rkyv::{Archive, Deserialize};
+
+use crate::model::{Hitsound, Metadata, Note, NoteType, ROX_VERSION, RoxChart, TimingPoint};
+
+/// Version number stored in files using this layout.
+pub(super) const V2: u8 = 2;
+
+#[derive(Archive, Deserialize)]
+pub(super) struct RoxChartV2 {
+    pub(super) version: u8,
+    pub(super) metadata: MetadataV2,
+    pub(super) timing_points: Vec<TimingPointV2>,
+    pub(super) notes: Vec<NoteV2>,
+    pub(super) hitsounds: Vec<Hitsound>,
+}
+
+#[derive(Archive, Deserialize)]
+pub(super) struct MetadataV2 {
+    chart_id: Option<u64>,
+    chartset_id: Option<u64>,
+    pub(super) key_count: u8,
+    pub(super) title: CompactString,
+    pub(super) artist: CompactString,
+    pub(super) creator: CompactString,
+    pub(super) difficulty_name: CompactString,
+    difficulty_value: Option<f32>,
+    pub(super) audio_file: CompactString,
+    background_file: Option<CompactString>,
+    audio_offset_us: i64,
+    preview_time_us: i64,
+    preview_duration_us: i64,
+    source: Option<CompactString>,
+    genre: Option<CompactString>,
+    language: Option<CompactString>,
+    tags: Vec<CompactString>,
+    pub(super) is_coop: bool,
+}
+
+#[derive(Archive, Deserialize)]
+pub(super) struct TimingPointV2 {
+    time_us: i64,
+    bpm: f32,
+    signature: u8,
+    is_inherited: bool,
+    scroll_speed: f32,
+}
+
+#[derive(Archive, Deserialize)]
+pub(super) struct NoteV2 {
+    time_us: i64,
+    note_type: NoteType,
+    hitsound_index: Option<u16>,
+    column: u8,
+}
+
+impl From<RoxChartV2> for RoxChart {
+    /// Fields added after v2 take their defaults.
+    fn from(chart: RoxChartV2) -> Self {
+        let meta = chart.metadata;
+        Self {
+            version: ROX_VERSION,
+            metadata: Metadata {
+                chart_id: meta.chart_id,
+                chartset_id: meta.chartset_id,
+                key_count: meta.key_count,
+                title: meta.title,
+                artist: meta.artist,
+                creator: meta.creator,
+                difficulty_name: meta.difficulty_name,
+                difficulty_value: meta.difficulty_value,
+                audio_file: meta.audio_file,
+                background_file: meta.background_file,
+                audio_offset_us: meta.audio_offset_us,
+                preview_time_us: meta.preview_time_us,
+                preview_duration_us: meta.preview_duration_us,
+                source: meta.source,
+                genre: meta.genre,
+                language: meta.language,
+                tags: meta.tags,
+                is_coop: meta.is_coop,
+                ..Metadata::default()
+            },
+            timing_points: chart
+                .timing_points
+                .into_iter()
+                .map(|tp| TimingPoint {
+                    time_us: tp.time_us,
+                    bpm: tp.bpm,
+                    signature: tp.signature,
+                    is_inherited: tp.is_inherited,
+                    scroll_speed: tp.scroll_speed,
+                    kiai: false,
+                })
+                .collect(),
+            notes: chart
+                .notes
+                .into_iter()
+                .map(|note| Note {
+                    note_type: note.note_type,
+                    hitsound_index: note.hitsound_index,
+                    ..Note::tap(note.time_us, note.column)
+                })
+                .collect(),
+            hitsounds: chart.hitsounds,
+        }
+    }
+}
@@ -11,6 +11,10 @@ pub struct RoxCodec;
 
 mod decoder;
 mod encoder;
+mod legacy;
+mod view;
+
+pub use view::ArchivedChartView;
 
 #[cfg(test)]
 mod tests;
@@ -1,8 +1,11 @@
 use super::MAX_FILE_SIZE;
 use super::RoxCodec;
-use crate::codec::{Decoder, Encoder};
+use crate::codec::{DecodeLimits, Decoder, Encoder};
 use crate::error::RoxError;
-use crate::model::{Hitsound, Metadata, Note, ROX_MAGIC, RoxChart, TimingPoint};
+use crate::model::{
+    Hitsound, Metadata, Note, NoteType, ROX_MAGIC, ROX_VERSION, RoxChart, ScrollDirection,
+    TimingPoint,
+};
 
 #[test]
 fn test_roundtrip() {
@@ -85,6 +88,41 @@ fn test_delta_encoding() {
     assert_eq!(decoded.notes[3].time_us, 1_300_000);
 }
 
+/// Build a ROX file by hand, bypassing the encoder's validation.
+/// Note times are written as-is, so they must already be deltas.
+#[cfg(not(target_arch = "wasm32"))]
+fn raw_rox(chart: &RoxChart) -> Vec<u8> {
+    let encoded = rkyv::to_bytes::<rkyv::rancor::Error>(chart).unwrap();
+    let mut data = ROX_MAGIC.to_vec();
+    data.extend(zstd::encode_all(encoded.as_slice(), 3).unwrap());
+    data
+}
+
+#[test]
+#[cfg(not(target_arch = "wasm32"))]
+fn test_decode_sorts_unsorted_notes() {
+    let mut chart = RoxChart::new(4);
+    chart.timing_points.push(TimingPoint::bpm(1_000_000, 150.0));
+    chart.timing_points.push(TimingPoint::bpm(0, 120.0));
+    // Deltas for absolute times 1.0s, 0.5s, 1.5s
+    chart.notes.push(Note::tap(1_000_000, 0));
+    chart.notes.push(Note::tap(-500_000, 1));
+    chart.notes.push(Note::tap(1_000_000, 2));
+    assert!(!chart.is_sorted());
+
+    let decoded = RoxCodec::decode(&raw_rox(&chart)).expect("Failed to decode");
+
+    assert!(decoded.is_sorted());
+    let notes: Vec<(i64, u8)> = decoded
+        .notes
+        .iter()
+        .map(|n| (n.time_us, n.column))
+        .collect();
+    assert_eq!(notes, vec![(500_000, 1), (1_000_000, 0), (1_500_000, 2)]);
+    assert_eq!(decoded.timing_points[0].time_us, 0);
+    assert!(decoded.is_valid());
+}
+
 #[test]
 fn test_roundtrip_all_note_types() {
     let mut chart = RoxChart::new(4);
@@ -122,6 +160,66 @@ fn test_roundtrip_with_hitsounds() {
     assert_eq!(chart, decoded);
 }
 
+#[test]
+fn test_roundtrip_hold_tail_hitsound() {
+    let mut chart = RoxChart::new(4);
+    chart.timing_points.push(TimingPoint::bpm(0, 140.0));
+    chart.hitsounds.push(Hitsound::new("head.wav"));
+    chart.hitsounds.push(Hitsound::new("tail.wav"));
+
+    let mut hold = Note::hold(0, 500_000, 0);
+    hold.hitsound_index = Some(0);
+    hold.tail_hitsound_index = Some(1);
+    chart.notes.push(hold);
+
+    let encoded = RoxCodec::encode(&chart).expect("Failed to encode");
+    let decoded = RoxCodec::decode(&encoded).expect("Failed to decode");
+
+    assert_eq!(decoded.notes[0].hitsound_index, Some(0));
+    assert_eq!(decoded.notes[0].tail_hitsound_index, Some(1));
+    assert_eq!(chart, decoded);
+}
+
+#[test]
+fn test_decode_with_limits() {
+    let mut chart = RoxChart::new(4);
+    chart.timing_points.push(TimingPoint::bpm(0, 120.0));
+    for i in 0..1000 {
+        chart.notes.push(Note::tap(i * 10_000, (i % 4) as u8));
+    }
+    let encoded = RoxCodec::encode(&chart).unwrap();
+
+    let decoded = RoxCodec::decode_with_limits(&encoded, &DecodeLimits::default()).unwrap();
+    assert_eq!(decoded, chart);
+
+    // Small on disk, but over the note cap once decompressed
+    let limits = DecodeLimits {
+        max_notes: 999,
+        ..DecodeLimits::default()
+    };
+    assert!(matches!(
+        RoxCodec::decode_with_limits(&encoded, &limits),
+        Err(RoxError::LimitExceeded {
+            found: 1000,
+            max: 999,
+            ..
+        })
+    ));
+
+    let limits = DecodeLimits {
+        max_decompressed_bytes: 1024,
+        ..DecodeLimits::default()
+    };
+    assert!(encoded.len() < 1024);
+    assert!(matches!(
+        RoxCodec::decode_with_limits(&encoded, &limits),
+        Err(RoxError::LimitExceeded {
+            what: "decompressed bytes",
+            ..
+        })
+    ));
+}
+
 #[test]
 fn test_magic_bytes() {
     let chart = RoxChart::new(4);
@@ -170,6 +268,25 @@ fn test_encode_invalid_column() {
     assert!(result.is_err());
 }
 
+#[test]
+fn test_encode_unchecked() {
+    let mut chart = RoxChart::new(4);
+    chart.timing_points.push(TimingPoint::bpm(0, 150.0));
+    chart.notes.push(Note::tap(0, 0));
+    chart.notes.push(Note::hold(400_000, 200_000, 3));
+
+    assert_eq!(
+        RoxCodec::encode_unchecked(&chart).unwrap(),
+        RoxCodec::encode(&chart).unwrap()
+    );
+
+    // Overlaps the hold on column 3, so `encode` refuses it
+    chart.notes.push(Note::tap(500_000, 3));
+    assert!(RoxCodec::encode(&chart).is_err());
+    let data = RoxCodec::encode_unchecked(&chart).unwrap();
+    assert_eq!(RoxCodec::decode(&data).unwrap().notes, chart.notes);
+}
+
 #[test]
 fn test_roundtrip_full_metadata() {
     let mut chart = RoxChart::new(7);
@@ -199,6 +316,22 @@ fn test_roundtrip_full_metadata() {
     assert_eq!(chart.metadata, decoded.metadata);
 }
 
+#[test]
+fn test_roundtrip_scroll_direction() {
+    let mut chart = RoxChart::new(4);
+    chart.metadata.scroll_direction = ScrollDirection::Up;
+
+    let encoded = RoxCodec::encode(&chart).unwrap();
+    let decoded = RoxCodec::decode(&encoded).unwrap();
+    assert_eq!(decoded.metadata.scroll_direction, ScrollDirection::Up);
+
+    // Files without the flag scroll down
+    assert_eq!(
+        RoxChart::new(4).metadata.scroll_direction,
+        ScrollDirection::Down
+    );
+}
+
 #[test]
 fn test_roundtrip_timing_points() {
     let mut chart = RoxChart::new(4);
@@ -429,3 +562,164 @@ fn test_file_too_large() {
     let result = RoxCodec::decode(&big_data);
     assert!(matches!(result, Err(RoxError::InvalidFormat(msg)) if msg.contains("File too large")));
 }
+
+#[test]
+fn test_encode_to_writer_roundtrip() {
+    let mut chart = RoxChart::new(4);
+    chart.metadata.title = "Streamed".into();
+    chart.timing_points.push(TimingPoint::bpm(0, 150.0));
+    for i in 0i64..500 {
+        #[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+        let column = (i % 4) as u8;
+        chart.notes.push(Note::tap(i * 100_000, column));
+    }
+    chart.notes.push(Note::hold(50_000_000, 250_000, 0));
+
+    let mut buffer: Vec<u8> = Vec::new();
+    RoxCodec::encode_to_writer(&chart, &mut buffer).expect("Failed to encode");
+
+    assert_eq!(&buffer[..4], &ROX_MAGIC);
+    assert_eq!(buffer, RoxCodec::encode(&chart).expect("Failed to encode"));
+
+    let decoded = RoxCodec::decode(&buffer).expect("Failed to decode");
+    assert_eq!(chart, decoded);
+}
+
+/// The streamed archive must be byte-for-byte what rkyv writes for the
+/// delta-encoded model itself.
+#[test]
+fn test_encoded_layout_matches_model() {
+    let mut chart = RoxChart::new(4);
+    chart.metadata.title = "Layout".into();
+    chart.timing_points.push(TimingPoint::bpm(0, 150.0));
+    chart.hitsounds.push(Hitsound::new("clap.wav"));
+    chart.notes.push(Note::tap(1_000_000, 0));
+    chart.notes.push(Note::hold(1_500_000, 500_000, 3));
+    chart.notes.push(Note::tap(2_250_000, 1));
+
+    let mut expected = chart.clone();
+    let mut last_time = 0;
+    for note in &mut expected.notes {
+        (note.time_us, last_time) = (note.time_us - last_time, note.time_us);
+    }
+    let expected = rkyv::to_bytes::<rkyv::rancor::Error>(&expected).expect("Failed to serialize");
+
+    let encoded = RoxCodec::encode(&chart).expect("Failed to encode");
+    let payload = zstd::decode_all(&encoded[ROX_MAGIC.len()..]).expect("Failed to decompress");
+    assert_eq!(payload, expected.as_slice());
+}
+
+#[test]
+fn test_access_matches_decode() {
+    let mut chart = RoxChart::new(7);
+    chart.metadata.title = "Viewed".into();
+    chart.metadata.artist = "Someone".into();
+    chart.metadata.difficulty_name = "Insane".into();
+    chart.timing_points.push(TimingPoint::bpm(0, 180.0));
+    chart.hitsounds.push(Hitsound::new("kick.wav"));
+    for i in 0i64..200 {
+        #[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+        let column = (i % 7) as u8;
+        chart.notes.push(Note::tap(i * 10_000, column));
+    }
+    let encoded = RoxCodec::encode(&chart).expect("Failed to encode");
+
+    let view = RoxCodec::access(&encoded).expect("Failed to access");
+    assert_eq!(view.version(), chart.version);
+    assert_eq!(view.key_count(), 7);
+    assert_eq!(view.title(), "Viewed");
+    assert_eq!(view.artist(), "Someone");
+    assert_eq!(view.difficulty_name(), "Insane");
+    assert_eq!(view.note_count(), 200);
+    assert_eq!(view.timing_point_count(), 1);
+    assert_eq!(view.hitsound_count(), 1);
+
+    let decoded = RoxCodec::decode(&encoded).expect("Failed to decode");
+    assert_eq!(view.title(), decoded.metadata.title);
+    assert_eq!(view.note_count(), decoded.notes.len());
+    assert_eq!(view.timing_point_count(), decoded.timing_points.len());
+}
+
+#[test]
+fn test_access_invalid_data() {
+    assert!(RoxCodec::access(b"NOPE").is_err());
+
+    let mut garbage = ROX_MAGIC.to_vec();
+    garbage.extend_from_slice(&[0xFF; 32]);
+    assert!(RoxCodec::access(&garbage).is_err());
+}
+
+/// `assets/rox/legacy_v2.rox` was written by the last release using format
+/// version 2, before the archived model gained new fields.
+#[test]
+fn test_decode_v2_file() {
+    let data = crate::test_utils::get_test_asset("rox/legacy_v2.rox");
+    let chart = RoxCodec::decode(&data).expect("Failed to decode v2 file");
+
+    assert_eq!(chart.version, ROX_VERSION);
+    assert_eq!(chart.key_count(), 7);
+    assert_eq!(chart.metadata.title, "Legacy");
+    assert_eq!(chart.metadata.artist, "Archive");
+    assert_eq!(chart.metadata.difficulty_name, "Hard");
+    assert_eq!(chart.metadata.tags, vec!["old", "format"]);
+    assert!(chart.metadata.bookmarks_us.is_empty());
+    assert_eq!(chart.metadata.coop_split, None);
+
+    assert_eq!(chart.timing_points.len(), 2);
+    assert!(chart.timing_points.iter().all(|tp| !tp.kiai));
+    assert!(chart.timing_points[1].is_inherited);
+
+    let notes: Vec<(i64, u8, NoteType)> = chart
+        .notes
+        .iter()
+        .map(|n| (n.time_us, n.column, n.note_type))
+        .collect();
+    assert_eq!(
+        notes,
+        vec![
+            (100_000, 0, NoteType::Tap),
+            (500_000, 3, NoteType::Tap),
+            (
+                1_000_000,
+                6,
+                NoteType::Hold {
+                    duration_us: 400_000
+                }
+            ),
+            (
+                1_500_000,
+                2,
+                NoteType::Burst {
+                    duration_us: 250_000
+                }
+            ),
+            (2_500_000, 5, NoteType::Mine),
+        ]
+    );
+    assert_eq!(chart.notes[1].hitsound_index, Some(0));
+    assert_eq!(chart.hitsounds, vec![Hitsound::new("kick.wav")]);
+    assert!(chart.validate().is_ok());
+
+    // Limits and re-encoding go through the same migration
+    let limited = RoxCodec::decode_with_limits(&data, &DecodeLimits::default()).unwrap();
+    assert_eq!(limited, chart);
+    let reencoded = RoxCodec::encode(&chart).expect("Failed to encode");
+    assert_eq!(RoxCodec::decode(&reencoded).unwrap(), chart);
+}
+
+#[test]
+fn test_access_v2_file() {
+    let data = crate::test_utils::get_test_asset("rox/legacy_v2.rox");
+    let view = RoxCodec::access(&data).expect("Failed to access v2 file");
+
+    assert_eq!(view.version(), 2);
+    assert_eq!(view.key_count(), 7);
+    assert_eq!(view.title(), "Legacy");
+    assert_eq!(view.audio_file(), "audio.ogg");
+    assert_eq!(view.note_count(), 5);
+    assert_eq!(view.timing_point_count(), 2);
+    assert_eq!(view.hitsound_count(), 1);
+
+    let current = RoxCodec::encode(&RoxCodec::decode(&data).unwrap()).unwrap();
+    assert_eq!(RoxCodec::access(&current).unwrap().version(), ROX_VERSION);
+}
@@ -0,0 +1,136 @@
+use rkyv::util::AlignedVec;
+
+use crate::error::RoxResult;
+
+use super::RoxCodec;
+use super::decoder::{Archived, aligned, archived, payload};
+
+/// Read the same field from whichever layout the archive uses.
+macro_rules! read {
+    ($view:expr, |$chart:ident| $field:expr) => {
+        match $view.chart() {
+            Archived::Current($chart) => $field,
+            Archived::V2($chart) => $field,
+        }
+    };
+}
+
+/// Read-only view of an encoded ROX chart, backed by the archived bytes.
+///
+/// Created by [`RoxCodec::access`]. Metadata and counts are read straight
+/// from the rkyv archive without building a `RoxChart`, which makes scanning
+/// many files for titles or note counts much cheaper than a full decode.
+///
+/// Note times are stored delta-encoded, so they are intentionally not exposed
+/// here: use [`RoxCodec::decode`](crate::codec::Decoder::decode) when you need
+/// the notes themselves.
+///
+/// Files written with an older [`ROX_VERSION`](crate::model::ROX_VERSION)
+/// are read in their own layout; [`version`](Self::version) reports it.
+pub struct ArchivedChartView {
+    bytes: AlignedVec,
+    legacy: bool,
+}
+
+impl ArchivedChartView {
+    fn chart(&self) -> Archived<'_> {
+        // SAFETY: the bytes were validated as this layout in `RoxCodec::access`
+        // and are never mutated afterwards.
+        unsafe {
+            if self.legacy {
+                Archived::V2(rkyv::access_unchecked(&self.bytes))
+            } else {
+                Archived::Current(rkyv::access_unchecked(&self.bytes))
+            }
+        }
+    }
+
+    /// Format version of the archived chart.
+    #[must_use]
+    pub fn version(&self) -> u8 {
+        read!(self, |chart| chart.version)
+    }
+
+    /// Number of columns.
+    #[must_use]
+    pub fn key_count(&self) -> u8 {
+        read!(self, |chart| chart.metadata.key_count)
+    }
+
+    /// Song title.
+    #[must_use]
+    pub fn title(&self) -> &str {
+        read!(self, |chart| chart.metadata.title.as_str())
+    }
+
+    /// Song artist.
+    #[must_use]
+    pub fn artist(&self) -> &str {
+        read!(self, |chart| chart.metadata.artist.as_str())
+    }
+
+    /// Chart creator.
+    #[must_use]
+    pub fn creator(&self) -> &str {
+        read!(self, |chart| chart.metadata.creator.as_str())
+    }
+
+    /// Difficulty name.
+    #[must_use]
+    pub fn difficulty_name(&self) -> &str {
+        read!(self, |chart| chart.metadata.difficulty_name.as_str())
+    }
+
+    /// Audio file path.
+    #[must_use]
+    pub fn audio_file(&self) -> &str {
+        read!(self, |chart| chart.metadata.audio_file.as_str())
+    }
+
+    /// Whether the chart is a coop chart.
+    #[must_use]
+    pub fn is_coop(&self) -> bool {
+        read!(self, |chart| chart.metadata.is_coop)
+    }
+
+    /// Number of judged notes, fakes excluded, like
+    /// [`RoxChart::note_count`](crate::model::RoxChart::note_count).
+    #[must_use]
+    pub fn note_count(&self) -> usize {
+        match self.chart() {
+            Archived::Current(chart) => chart.notes.iter().filter(|n| !n.fake).count(),
+            // Fakes were added in version 3
+            Archived::V2(chart) => chart.notes.len(),
+        }
+    }
+
+    /// Number of timing points.
+    #[must_use]
+    pub fn timing_point_count(&self) -> usize {
+        read!(self, |chart| chart.timing_points.len())
+    }
+
+    /// Number of hitsound samples.
+    #[must_use]
+    pub fn hitsound_count(&self) -> usize {
+        read!(self, |chart| chart.hitsounds.len())
+    }
+}
+
+impl RoxCodec {
+    /// Open an encoded ROX chart for read-only inspection without decoding it.
+    ///
+    /// The payload is still decompressed, but nothing is deserialized: see
+    /// [`ArchivedChartView`] for what can be read.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the header is invalid, decompression fails, or the
+    /// archive does not validate.
+    pub fn access(data: &[u8]) -> RoxResult<ArchivedChartView> {
+        let bytes = aligned(&payload(data)?);
+        let legacy = matches!(archived(&bytes)?, Archived::V2(_));
+
+        Ok(ArchivedChartView { bytes, legacy })
+    }
+}
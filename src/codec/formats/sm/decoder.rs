@@ -1,7 +1,8 @@
 #![allow(clippy::doc_markdown)]
 //! Decoder for converting StepMania (`.sm`) files to `RoxChart`.
 
-use crate::codec::Decoder;
+use crate::codec::formats::normalize_asset_path;
+use crate::codec::{ChartSelector, DecodeLimits, DecodeOptions, Decoder, NotePolicy};
 use crate::error::RoxResult;
 use crate::model::{Metadata, Note, RoxChart, TimingPoint};
 
@@ -25,6 +26,18 @@ impl SmDecoder {
     #[must_use]
     pub fn from_chart(sm: &SmFile, chart: &SmChart) -> RoxChart {
         let mut rox = RoxChart::new(chart.column_count);
+        Self::from_chart_into(sm, chart, &mut rox);
+        rox
+    }
+
+    /// Convert a specific chart from an `SmFile` into `rox`, replacing its
+    /// contents but reusing its vectors.
+    pub fn from_chart_into(sm: &SmFile, chart: &SmChart, rox: &mut RoxChart) {
+        Self::convert(sm, chart, DecodeOptions::default(), rox);
+    }
+
+    fn convert(sm: &SmFile, chart: &SmChart, options: DecodeOptions, rox: &mut RoxChart) {
+        rox.reset(chart.column_count);
 
         // Map metadata
         rox.metadata = Metadata {
@@ -35,17 +48,16 @@ impl SmDecoder {
             difficulty_name: chart.difficulty.clone().into(),
             #[allow(clippy::cast_precision_loss)]
             difficulty_value: Some(chart.meter as f32),
-            audio_file: sm.metadata.music.clone().into(),
-            background_file: if sm.metadata.background.is_empty() {
-                None
-            } else {
-                Some(sm.metadata.background.clone().into())
-            },
+            audio_file: normalize_asset_path(&sm.metadata.music).into(),
+            background_file: Some(normalize_asset_path(&sm.metadata.background))
+                .filter(|path| !path.is_empty())
+                .map(Into::into),
             audio_offset_us: -sm.offset_us,
             #[allow(clippy::cast_possible_truncation)]
             preview_time_us: (sm.metadata.sample_start * 1_000_000.0) as i64,
             #[allow(clippy::cast_possible_truncation)]
             preview_duration_us: (sm.metadata.sample_length * 1_000_000.0) as i64,
+            display_bpm: sm.metadata.display_bpm,
             source: Some(sm.metadata.banner.clone().into()),
             genre: None,
             language: None,
@@ -103,11 +115,21 @@ impl SmDecoder {
                 SmNoteType::Mine => {
                     rox.notes.push(Note::mine(note.time_us, note.column));
                 }
-                SmNoteType::Lift => {
-                    // Convert lift to tap (no direct ROX equivalent)
-                    rox.notes.push(Note::tap(note.time_us, note.column));
+                SmNoteType::Lift => match options.lift_as {
+                    // No direct ROX equivalent
+                    NotePolicy::Tap => rox.notes.push(Note::tap(note.time_us, note.column)),
+                    NotePolicy::Mine => rox.notes.push(Note::mine(note.time_us, note.column)),
+                    NotePolicy::Skip => {}
+                },
+                SmNoteType::Fake => {
+                    if options.keep_fakes {
+                        rox.notes.push(Note {
+                            fake: true,
+                            ..Note::tap(note.time_us, note.column)
+                        });
+                    }
                 }
-                SmNoteType::Empty | SmNoteType::Fake => {
+                SmNoteType::Empty => {
                     // Ignored
                 }
             }
@@ -115,8 +137,6 @@ impl SmDecoder {
 
         // Sort notes by time
         rox.notes.sort_by_key(|n| n.time_us);
-
-        rox
     }
 
     /// Decode all charts from an SM file.
@@ -127,24 +147,141 @@ impl SmDecoder {
             .map(|chart| Self::from_chart(sm, chart))
             .collect()
     }
-}
 
-impl Decoder for SmDecoder {
-    fn decode(data: &[u8]) -> RoxResult<RoxChart> {
+    /// Decode the chart picked by `selector` from raw SM data.
+    ///
+    /// `Hardest`/`Easiest` compare the chart meter, `ByName` matches the
+    /// difficulty name (e.g. `"Challenge"`).
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if parsing fails or no chart matches the selector.
+    pub fn decode_select(data: &[u8], selector: &ChartSelector) -> RoxResult<RoxChart> {
         let sm = parser::parse(data)?;
-        sm.charts
-            .first()
-            .map(|chart| Self::from_chart(&sm, chart))
+        selector
+            .position(
+                &sm.charts,
+                |chart| f64::from(chart.meter),
+                |chart| chart.difficulty.as_str(),
+            )
+            .map(|i| Self::from_chart(&sm, &sm.charts[i]))
             .ok_or_else(|| {
-                crate::error::RoxError::InvalidFormat("No charts found in SM file".into())
+                crate::error::RoxError::InvalidFormat(format!(
+                    "No chart matching {selector:?} found in SM file"
+                ))
             })
     }
 }
 
+impl SmDecoder {
+    /// Decode the first chart with explicit [`DecodeOptions`] (e.g. legacy
+    /// encoding detection, or how lifts and fakes are handled).
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if parsing fails or the file contains no charts.
+    pub fn decode_with_options(data: &[u8], options: &DecodeOptions) -> RoxResult<RoxChart> {
+        let sm = parser::parse_limited(
+            &options.text(data),
+            &DecodeLimits::default(),
+            options.default_bpm(),
+            &mut |_, _| {},
+        )?;
+        let chart = sm.charts.first().ok_or_else(|| {
+            crate::error::RoxError::InvalidFormat("No charts found in SM file".into())
+        })?;
+        let mut rox = RoxChart::new(chart.column_count);
+        Self::convert(&sm, chart, *options, &mut rox);
+        options.post_process(&mut rox);
+        Ok(rox)
+    }
+
+    /// Decode the first chart with explicit [`DecodeOptions`] while reporting
+    /// `(bytes_processed, total_bytes)` to `progress`.
+    ///
+    /// Progress follows the note lines of every chart in the file; the last
+    /// call is always `(total_bytes, total_bytes)`. Bytes count the text after
+    /// any encoding detection.
+    ///
+    /// # Errors
+    ///
+    /// Same as [`decode_with_options`](Self::decode_with_options).
+    pub fn decode_with_progress(
+        data: &[u8],
+        options: &DecodeOptions,
+        mut progress: impl FnMut(usize, usize),
+    ) -> RoxResult<RoxChart> {
+        let sm = parser::parse_limited(
+            &options.text(data),
+            &DecodeLimits::default(),
+            options.default_bpm(),
+            &mut progress,
+        )?;
+        let chart = sm.charts.first().ok_or_else(|| {
+            crate::error::RoxError::InvalidFormat("No charts found in SM file".into())
+        })?;
+        let mut rox = RoxChart::new(chart.column_count);
+        Self::convert(&sm, chart, *options, &mut rox);
+        options.post_process(&mut rox);
+        Ok(rox)
+    }
+}
+
+impl SmDecoder {
+    /// Decode the first chart of untrusted data within caller-controlled
+    /// [`DecodeLimits`].
+    ///
+    /// Parsing stops at the first BPM change, or note of any chart, past the
+    /// limits, before the chart is built. Decodes with the default
+    /// [`DecodeOptions`]; apply [`DecodeOptions::post_process`] to the result
+    /// for option-driven cleanup.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`RoxError::LimitExceeded`](crate::error::RoxError::LimitExceeded)
+    /// if a limit is exceeded, or an error if parsing fails or the file
+    /// contains no charts.
+    pub fn decode_with_limits(data: &[u8], limits: &DecodeLimits) -> RoxResult<RoxChart> {
+        let sm = parser::parse_with_limits(data, limits)?;
+        let chart = sm.charts.first().ok_or_else(|| {
+            crate::error::RoxError::InvalidFormat("No charts found in SM file".into())
+        })?;
+        Ok(Self::from_chart(&sm, chart))
+    }
+
+    /// Decode the first chart into an existing chart, reusing its note and
+    /// timing point allocations.
+    ///
+    /// The previous contents are fully replaced; on error `rox` is left
+    /// unchanged.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if parsing fails or the file contains no charts.
+    pub fn decode_into(data: &[u8], rox: &mut RoxChart) -> RoxResult<()> {
+        let sm = parser::parse(&DecodeOptions::default().text(data))?;
+        let chart = sm.charts.first().ok_or_else(|| {
+            crate::error::RoxError::InvalidFormat("No charts found in SM file".into())
+        })?;
+        Self::from_chart_into(&sm, chart, rox);
+        Ok(())
+    }
+}
+
+impl Decoder for SmDecoder {
+    fn probe(data: &[u8]) -> bool {
+        memchr::memmem::find(data, b"#NOTES:").is_some()
+    }
+
+    fn decode(data: &[u8]) -> RoxResult<RoxChart> {
+        Self::decode_with_options(data, &DecodeOptions::default())
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::codec::Decoder;
+    use crate::codec::{Decoder, Encoder};
 
     /// Basic SM file content for testing.
     const BASIC_SM: &str = r#"
@@ -174,6 +311,21 @@ mod tests {
 ;
 "#;
 
+    #[test]
+    fn test_decode_normalizes_asset_paths() {
+        let data = BASIC_SM.replace(
+            "#MUSIC:song.ogg;",
+            "#MUSIC:\"audio\\song.ogg\";\n#BACKGROUND: gfx\\bg.png ;",
+        );
+        let chart = <SmDecoder as Decoder>::decode(data.as_bytes()).unwrap();
+
+        assert_eq!(chart.metadata.audio_file, "audio/song.ogg");
+        assert_eq!(
+            chart.metadata.background_file.as_deref(),
+            Some("gfx/bg.png")
+        );
+    }
+
     #[test]
     fn test_decode_basic_sm() {
         let chart = <SmDecoder as Decoder>::decode(BASIC_SM.as_bytes()).expect("Failed to decode");
@@ -203,6 +355,248 @@ mod tests {
         assert_eq!(chart.timing_points[0].bpm, 120.0);
     }
 
+    #[test]
+    fn test_stray_wide_line_keeps_column_count() {
+        let data = BASIC_SM.replace("0100\n", "010000001\n");
+        let sm = parser::parse(data.as_bytes()).unwrap();
+        assert_eq!(sm.charts[0].column_count, 4);
+        assert!(!sm.charts[0].is_routine);
+
+        let chart = <SmDecoder as Decoder>::decode(data.as_bytes()).unwrap();
+        assert_eq!(chart.key_count(), 4);
+        assert!(chart.notes.iter().all(|n| n.column < 4));
+        assert!(chart.validate().is_ok());
+    }
+
+    #[test]
+    fn test_consistently_wide_lines_widen_column_count() {
+        // Mislabeled stepstype: every line has 7 columns
+        let data = BASIC_SM
+            .replace("0000\n", "0000000\n")
+            .replace("1000\n", "1000000\n")
+            .replace("0100\n", "0100000\n")
+            .replace("0001\n", "0001000\n")
+            .replace("0010\n", "0010001\n");
+        let chart = <SmDecoder as Decoder>::decode(data.as_bytes()).unwrap();
+        assert_eq!(chart.key_count(), 7);
+        assert_eq!(chart.notes.len(), 5);
+    }
+
+    #[test]
+    fn test_overly_wide_lines_keep_column_count() {
+        let wide = |row: &str| format!("{row}{}\n", "0".repeat(16));
+        let data = BASIC_SM
+            .replace("0000\n", &wide("0000"))
+            .replace("1000\n", &wide("1000"))
+            .replace("0100\n", &wide("0100"))
+            .replace("0001\n", &wide("0001"))
+            .replace("0010\n", &wide("0010"));
+        let chart = <SmDecoder as Decoder>::decode(data.as_bytes()).unwrap();
+        assert_eq!(chart.key_count(), 4);
+        assert!(chart.validate().is_ok());
+    }
+
+    #[test]
+    fn test_rows_with_extra_note_characters() {
+        // Keysound-only `K`, an unknown letter and a keysound annotation:
+        // every row still counts, so the notes keep their beat
+        let data = BASIC_SM
+            .replace("dance-single", "pump-single")
+            .replacen("0000\n", "K0X0\n", 1)
+            .replace("0100\n", "0000\n")
+            .replace("0010\n", "001[2]0\n");
+        let chart = <SmDecoder as Decoder>::decode(data.as_bytes()).unwrap();
+
+        assert_eq!(chart.key_count(), 4);
+        let notes: Vec<(i64, u8)> = chart.notes.iter().map(|n| (n.time_us, n.column)).collect();
+        assert_eq!(notes, vec![(500_000, 0), (1_500_000, 2), (2_000_000, 3)]);
+    }
+
+    #[test]
+    fn test_display_bpm_roundtrip() {
+        use crate::codec::Encoder;
+        use crate::codec::formats::sm::SmEncoder;
+        use crate::model::DisplayBpm;
+
+        let data = BASIC_SM.replace("#BPMS:", "#DISPLAYBPM:200;\n#BPMS:");
+        let chart = <SmDecoder as Decoder>::decode(data.as_bytes()).unwrap();
+        assert_eq!(chart.metadata.display_bpm, Some(DisplayBpm::Single(200.0)));
+
+        let encoded = SmEncoder::encode(&chart).unwrap();
+        assert!(String::from_utf8_lossy(&encoded).contains("#DISPLAYBPM:200.000;"));
+        let chart = <SmDecoder as Decoder>::decode(&encoded).unwrap();
+        assert_eq!(chart.metadata.display_bpm, Some(DisplayBpm::Single(200.0)));
+
+        for (tag, expected) in [
+            ("*", Some(DisplayBpm::Random)),
+            ("300:150", Some(DisplayBpm::Range(150.0, 300.0))),
+            ("fast", None),
+        ] {
+            let data = BASIC_SM.replace("#BPMS:", &format!("#DISPLAYBPM:{tag};\n#BPMS:"));
+            let chart = <SmDecoder as Decoder>::decode(data.as_bytes()).unwrap();
+            assert_eq!(chart.metadata.display_bpm, expected, "{tag}");
+        }
+    }
+
+    #[test]
+    fn test_decode_long_chart() {
+        // 2000 measures of a jump and two 16ths per beat: 32k notes
+        let mut data = BASIC_SM[..BASIC_SM.find("#NOTES:").unwrap()].to_string();
+        data.push_str(
+            "#NOTES:\n     dance-single:\n     :\n     Hard:\n     10:\n     0,0,0,0,0:\n",
+        );
+        for measure in 0..2000 {
+            if measure > 0 {
+                data.push_str(",\n");
+            }
+            for row in 0..16 {
+                data.push_str(match row % 4 {
+                    0 => "1001\n",
+                    1 => "0100\n",
+                    2 => "0010\n",
+                    _ => "0000\n",
+                });
+            }
+        }
+        data.push_str(";\n");
+
+        let chart = <SmDecoder as Decoder>::decode(data.as_bytes()).unwrap();
+        assert_eq!(chart.notes.len(), 2000 * 4 * 4);
+        assert!(chart.is_valid());
+        // 120 BPM: a 16th is 125ms, so the last jump is at beat 7999
+        let last = &chart.notes[chart.notes.len() - 4..];
+        assert_eq!(last[0].time_us, 7_999 * 500_000);
+        assert_eq!(last[3].time_us, 7_999 * 500_000 + 250_000);
+    }
+
+    #[test]
+    fn test_routine_flag() {
+        let data = BASIC_SM.replace("dance-single", "dance-routine");
+        let sm = parser::parse(data.as_bytes()).unwrap();
+        assert!(sm.charts[0].is_routine);
+        assert_eq!(sm.charts[0].column_count, 8);
+    }
+
+    #[test]
+    fn test_decode_with_progress() {
+        let data = crate::test_utils::get_test_asset("stepmania/4k.sm");
+        let mut reports = Vec::new();
+        let chart =
+            SmDecoder::decode_with_progress(&data, &DecodeOptions::default(), |done, total| {
+                reports.push((done, total));
+            })
+            .unwrap();
+
+        assert_eq!(chart, <SmDecoder as Decoder>::decode(&data).unwrap());
+        assert!(reports.len() > 1);
+        assert!(reports.windows(2).all(|w| w[0].0 < w[1].0));
+        assert!(reports.iter().all(|&(_, total)| total == data.len()));
+        assert_eq!(reports.last(), Some(&(data.len(), data.len())));
+    }
+
+    #[test]
+    fn test_decode_select() {
+        let two_charts = format!(
+            "{BASIC_SM}\n#NOTES:\n     dance-single:\n     :\n     Hard:\n     9:\n     0,0,0,0,0:\n1111\n0000\n0000\n0000\n;\n"
+        );
+        let data = two_charts.as_bytes();
+
+        let hardest = SmDecoder::decode_select(data, &ChartSelector::Hardest).unwrap();
+        assert_eq!(hardest.metadata.difficulty_name, "Hard");
+        assert_eq!(hardest.notes.len(), 4);
+
+        let easiest = SmDecoder::decode_select(data, &ChartSelector::Easiest).unwrap();
+        assert_eq!(easiest.metadata.difficulty_name, "Beginner");
+
+        let first = SmDecoder::decode_select(data, &ChartSelector::First).unwrap();
+        assert_eq!(first.metadata.difficulty_name, "Beginner");
+
+        let named = SmDecoder::decode_select(data, &ChartSelector::ByName("hard".into())).unwrap();
+        assert_eq!(named.metadata.difficulty_value, Some(9.0));
+
+        assert!(SmDecoder::decode_select(data, &ChartSelector::ByName("Edit".into())).is_err());
+    }
+
+    #[test]
+    #[cfg(feature = "encoding")]
+    fn test_decode_shift_jis_title() {
+        // `BASIC_SM` with a Shift-JIS encoded title
+        let (title, _, _) = encoding_rs::SHIFT_JIS.encode("夜に駆ける");
+        let (head, tail) = BASIC_SM.split_once("Test Song").unwrap();
+        let data = [head.as_bytes(), &title, tail.as_bytes()].concat();
+        assert!(<SmDecoder as Decoder>::decode(&data).is_err());
+
+        let chart = SmDecoder::decode_with_options(&data, &DecodeOptions::detect_encoding())
+            .expect("Failed to decode Shift-JIS SM");
+        assert_eq!(chart.metadata.title, "夜に駆ける");
+        assert_eq!(chart.notes.len(), 4);
+    }
+
+    #[test]
+    fn test_decode_into_reuses_chart() {
+        let mut chart = RoxChart::new(7);
+        let data = crate::test_utils::get_test_asset("stepmania/4k.sm");
+        SmDecoder::decode_into(&data, &mut chart).unwrap();
+        assert_eq!(chart, <SmDecoder as Decoder>::decode(&data).unwrap());
+
+        SmDecoder::decode_into(BASIC_SM.as_bytes(), &mut chart).unwrap();
+        assert_eq!(
+            chart,
+            <SmDecoder as Decoder>::decode(BASIC_SM.as_bytes()).unwrap()
+        );
+        assert_eq!(chart.notes.len(), 4);
+    }
+
+    #[test]
+    fn test_decode_with_limits() {
+        let data = BASIC_SM.as_bytes();
+        let chart = SmDecoder::decode_with_limits(data, &DecodeLimits::default()).unwrap();
+        assert_eq!(chart.notes.len(), 4);
+
+        let limits = DecodeLimits {
+            max_notes: 3,
+            ..DecodeLimits::default()
+        };
+        assert!(matches!(
+            SmDecoder::decode_with_limits(data, &limits),
+            Err(crate::error::RoxError::LimitExceeded { found: 4, .. })
+        ));
+    }
+
+    #[test]
+    fn test_lifts_and_fakes() {
+        let data = BASIC_SM.replace("0100\n0010", "0F00\n00L0").into_bytes();
+
+        // Default: lift as tap, fake dropped
+        let chart = SmDecoder::decode(&data).unwrap();
+        assert_eq!(chart.notes.len(), 3);
+        assert!(chart.notes.iter().all(|n| !n.fake && !n.is_mine()));
+
+        let options = DecodeOptions {
+            lift_as: NotePolicy::Mine,
+            keep_fakes: true,
+            ..DecodeOptions::default()
+        };
+        let chart = SmDecoder::decode_with_options(&data, &options).unwrap();
+        assert_eq!(chart.notes.len(), 4);
+        assert!(chart.notes[1].fake);
+        assert_eq!(chart.notes[1].column, 1);
+        assert!(chart.notes[2].is_mine());
+        assert_eq!(chart.notes[2].column, 2);
+
+        // Fakes survive an encode
+        let encoded = super::super::SmEncoder::encode(&chart).unwrap();
+        let again = SmDecoder::decode_with_options(&encoded, &options).unwrap();
+        assert!(again.notes.iter().any(|n| n.fake && n.column == 1));
+
+        let options = DecodeOptions {
+            lift_as: NotePolicy::Skip,
+            ..DecodeOptions::default()
+        };
+        let chart = SmDecoder::decode_with_options(&data, &options).unwrap();
+        assert_eq!(chart.notes.len(), 2);
+    }
+
     #[test]
     fn test_decode_asset_4k() {
         // assets/stepmania/4k.sm
@@ -214,4 +608,20 @@ mod tests {
         // Note: I don't know the exact metadata of 4k.sm, so I'll just check it decoded successfully and has notes
         // Ideally I'd inspect the actual file content, but for now validating decode success is good.
     }
+
+    #[test]
+    fn test_decode_default_bpm_override() {
+        let data = b"#TITLE:No BPM;\n#NOTES:\n     dance-single:\n     :\n     Beginner:\n     1:\n     0,0,0,0,0:\n1000\n0000\n0100\n0000\n;\n";
+        let chart = <SmDecoder as Decoder>::decode(data).unwrap();
+        assert_eq!(chart.notes[1].time_us, 1_000_000);
+
+        let options = DecodeOptions {
+            default_bpm: Some(180.0),
+            ..DecodeOptions::default()
+        };
+        let chart = SmDecoder::decode_with_options(data, &options).unwrap();
+        assert!((chart.timing_points[0].bpm - 180.0).abs() < f32::EPSILON);
+        // Two beats at 180 BPM
+        assert_eq!(chart.notes[1].time_us, 666_666);
+    }
 }
@@ -11,36 +11,46 @@
 )]
 //! Encoder for converting `RoxChart` to StepMania (`.sm`) format.
 
+use std::borrow::Cow;
 use std::fmt::Write;
 
 use crate::codec::Encoder;
 use crate::error::RoxResult;
-use crate::model::{NoteType, RoxChart};
+use crate::model::{DEFAULT_BPM, DisplayBpm, NoteType, RoxChart};
 
 /// Encoder for StepMania (`.sm`) beatmaps.
 pub struct SmEncoder;
 
 impl Encoder for SmEncoder {
+    /// Validates the chart first (see [`RoxChart::validate`]), so an invalid
+    /// chart is reported instead of written as a broken file.
     fn encode(chart: &RoxChart) -> RoxResult<Vec<u8>> {
+        chart.validate()?;
+        Self::encode_unchecked(chart)
+    }
+
+    fn encode_unchecked(chart: &RoxChart) -> RoxResult<Vec<u8>> {
         let mut output = String::new();
+        // Strings from the source file must not break the tag structure
+        let meta = chart.metadata.sanitized();
 
         // Metadata
-        let _ = writeln!(output, "#TITLE:{};", chart.metadata.title);
+        let _ = writeln!(output, "#TITLE:{};", escape(&meta.title));
         let _ = writeln!(output, "#SUBTITLE:;");
-        let _ = writeln!(output, "#ARTIST:{};", chart.metadata.artist);
+        let _ = writeln!(output, "#ARTIST:{};", escape(&meta.artist));
         let _ = writeln!(output, "#TITLETRANSLIT:;");
         let _ = writeln!(output, "#ARTISTTRANSLIT:;");
         let _ = writeln!(output, "#GENRE:;");
-        let _ = writeln!(output, "#CREDIT:{};", chart.metadata.creator);
+        let _ = writeln!(output, "#CREDIT:{};", escape(&meta.creator));
         let _ = writeln!(output, "#BANNER:;");
-        if let Some(bg) = &chart.metadata.background_file {
-            let _ = writeln!(output, "#BACKGROUND:{bg};");
+        if let Some(bg) = &meta.background_file {
+            let _ = writeln!(output, "#BACKGROUND:{};", escape(bg));
         } else {
             let _ = writeln!(output, "#BACKGROUND:;");
         }
         let _ = writeln!(output, "#LYRICSPATH:;");
         let _ = writeln!(output, "#CDTITLE:;");
-        let _ = writeln!(output, "#MUSIC:{};", chart.metadata.audio_file);
+        let _ = writeln!(output, "#MUSIC:{};", escape(&meta.audio_file));
 
         // Determine Sync Point (Beat 0 location)
         // SM expects Offset to be the time of the first beat.
@@ -58,13 +68,29 @@ impl Encoder for SmEncoder {
 
         // Sample start/length
         #[allow(clippy::cast_precision_loss)]
-        let sample_start = chart.metadata.preview_time_us as f64 / 1_000_000.0;
+        let sample_start = meta.preview_time_us as f64 / 1_000_000.0;
         #[allow(clippy::cast_precision_loss)]
-        let sample_length = chart.metadata.preview_duration_us as f64 / 1_000_000.0;
+        let sample_length = meta.preview_duration_us as f64 / 1_000_000.0;
         let _ = writeln!(output, "#SAMPLESTART:{sample_start:.3};");
         let _ = writeln!(output, "#SAMPLELENGTH:{sample_length:.3};");
 
         let _ = writeln!(output, "#SELECTABLE:YES;");
+        // Declared by the chart, or computed from its timing points
+        let display_bpm = meta
+            .display_bpm
+            .unwrap_or_else(|| chart.computed_display_bpm());
+        match display_bpm {
+            DisplayBpm::Single(bpm) if bpm <= 0.0 => {}
+            DisplayBpm::Single(bpm) => {
+                let _ = writeln!(output, "#DISPLAYBPM:{bpm:.3};");
+            }
+            DisplayBpm::Range(min, max) => {
+                let _ = writeln!(output, "#DISPLAYBPM:{min:.3}:{max:.3};");
+            }
+            DisplayBpm::Random => {
+                let _ = writeln!(output, "#DISPLAYBPM:*;");
+            }
+        }
 
         // BPMs
         output.push_str("#BPMS:");
@@ -107,17 +133,15 @@ impl Encoder for SmEncoder {
         let _ = writeln!(output, "     :");
         // Force Difficulty to "Hard" or "Challenge" to ensure Etterna/SM sees it validly.
         // "1.0x" is not a standard difficulty name.
-        let difficulty_name = match chart.metadata.difficulty_name.as_str() {
-            "Beginner" | "Easy" | "Medium" | "Hard" | "Challenge" | "Edit" => {
-                &chart.metadata.difficulty_name
-            }
+        let difficulty_name = match meta.difficulty_name.as_str() {
+            "Beginner" | "Easy" | "Medium" | "Hard" | "Challenge" | "Edit" => &meta.difficulty_name,
             _ => "Hard", // Fallback for numeric versions like "1.0x"
         };
         let _ = writeln!(output, "     {difficulty_name}:");
         let _ = writeln!(
             output,
             "     {}:",
-            chart.metadata.difficulty_value.unwrap_or(1.0) as u32
+            meta.difficulty_value.unwrap_or(1.0) as u32
         );
         // Correct format for radar values
         // Revert to simple integer format as per working 4k.sm example
@@ -139,6 +163,22 @@ impl Encoder for SmEncoder {
     }
 }
 
+/// Backslash-escape the characters that would end or split a tag value.
+fn escape(value: &str) -> Cow<'_, str> {
+    let special = |c: char| matches!(c, ';' | ':' | '#' | '\\');
+    if !value.contains(special) {
+        return Cow::Borrowed(value);
+    }
+    let mut escaped = String::with_capacity(value.len() + 4);
+    for c in value.chars() {
+        if special(c) {
+            escaped.push('\\');
+        }
+        escaped.push(c);
+    }
+    Cow::Owned(escaped)
+}
+
 /// Convert microseconds to beat position.
 /// `start_time_us` is the time where beat count starts (beat 0).
 fn us_to_beat(time_us: i64, bpm_points: &[&crate::model::TimingPoint], start_time_us: i64) -> f64 {
@@ -191,7 +231,10 @@ fn encode_measures(output: &mut String, chart: &RoxChart, bpms: &[(i64, f32)], s
         .max()
         .unwrap_or(0);
 
-    // Calculate number of measures needed
+    // Calculate number of measures needed.
+    // `.sm` note data is always 4 beats per measure (time signatures only exist
+    // as `#TIMESIGNATURES` in `.ssc`), so a 3/4 chart is still written in
+    // 4-beat blocks to keep note timing exact.
     let total_beats = us_to_beat_simple(max_time, bpms, start_time_us);
 
     let total_measures = if total_beats > 0.0 {
@@ -204,6 +247,10 @@ fn encode_measures(output: &mut String, chart: &RoxChart, bpms: &[(i64, f32)], s
     let mut events: Vec<(i64, u8, char)> = Vec::new();
 
     for note in &chart.notes {
+        if note.fake {
+            events.push((note.time_us, note.column, 'F'));
+            continue;
+        }
         match &note.note_type {
             NoteType::Tap => {
                 events.push((note.time_us, note.column, '1'));
@@ -326,34 +373,8 @@ fn encode_measures(output: &mut String, chart: &RoxChart, bpms: &[(i64, f32)], s
             let _ = writeln!(output, ",");
         }
 
-        // Try standard SM divisors
-        let divisors = [4, 8, 12, 16, 24, 32, 48, 64, 96, 192];
-        let mut best_divisor = 192;
-
-        'divisor_loop: for &div in &divisors {
-            // Check if all events align with this divisor
-            for (beat_in_measure, _, _) in events {
-                // Ideal position in lines for this divisor
-                let ideal_line = beat_in_measure * (div as f64) / 4.0;
-                let snapped_line = ideal_line.round();
-
-                // If deviation is too high, this divisor is invalid
-                if (ideal_line - snapped_line).abs() > 0.001 {
-                    continue 'divisor_loop;
-                }
-            }
-
-            // If we get here, all events aligned
-            best_divisor = div;
-            break;
-        }
-
-        // If measure is empty, force 4 lines to save space
-        if events.is_empty() {
-            best_divisor = 4;
-        }
-
-        let lines_per_measure = best_divisor;
+        // Empty measures collapse to 4 lines
+        let lines_per_measure = measure_rows(events);
         for i in 0..lines_per_measure {
             // Collect events on this line
             let mut line_chars: Vec<char> = vec!['0'; chart.key_count() as usize];
@@ -375,9 +396,42 @@ fn encode_measures(output: &mut String, chart: &RoxChart, bpms: &[(i64, f32)], s
     }
 }
 
+/// Maximum rows per measure (the 1/48-beat grid events are snapped to).
+const MAX_ROWS_PER_MEASURE: usize = 192;
+
+/// Rows needed for every event of a measure to land exactly on a row.
+///
+/// Each event's subdivision is derived from its position on the 192-row grid,
+/// and the measure uses their least common multiple (at least 4, like
+/// StepMania editors). A measure mixing 1/12 and 1/16 gets 48 rows instead of
+/// snapping the triplets onto a 16th grid. Because every subdivision divides
+/// 192, the result never exceeds it.
+fn measure_rows(events: &[(f64, u8, char)]) -> usize {
+    events
+        .iter()
+        .fold(4, |rows, (beat_in_measure, _, _)| {
+            let row = (beat_in_measure * MAX_ROWS_PER_MEASURE as f64 / 4.0).round() as usize
+                % MAX_ROWS_PER_MEASURE;
+            let subdivision = MAX_ROWS_PER_MEASURE / gcd(row, MAX_ROWS_PER_MEASURE);
+            lcm(rows, subdivision)
+        })
+        .min(MAX_ROWS_PER_MEASURE)
+}
+
+fn gcd(mut a: usize, mut b: usize) -> usize {
+    while b != 0 {
+        (a, b) = (b, a % b);
+    }
+    a
+}
+
+fn lcm(a: usize, b: usize) -> usize {
+    a / gcd(a, b) * b
+}
+
 fn us_to_beat_simple(time_us: i64, bpms: &[(i64, f32)], start_time_us: i64) -> f64 {
     if bpms.is_empty() {
-        return (time_us - start_time_us) as f64 / 1_000_000.0 * 120.0 / 60.0;
+        return (time_us - start_time_us) as f64 / 1_000_000.0 * f64::from(DEFAULT_BPM) / 60.0;
     }
 
     let mut current_time_us = start_time_us;
@@ -401,6 +455,89 @@ fn us_to_beat_simple(time_us: i64, bpms: &[(i64, f32)], start_time_us: i64) -> f
 
 #[cfg(test)]
 mod tests {
+    use super::*;
+    use crate::codec::Decoder;
+    use crate::codec::formats::sm::SmDecoder;
+    use crate::model::{Note, TimingPoint};
+
+    #[test]
+    fn test_measure_rows_lcm() {
+        assert_eq!(measure_rows(&[]), 4);
+        // Quarter notes only
+        assert_eq!(measure_rows(&[(0.0, 0, '1'), (1.0, 0, '1')]), 4);
+        // 16ths
+        assert_eq!(measure_rows(&[(0.0, 0, '1'), (0.25, 0, '1')]), 16);
+        // 1/12 + 1/16 mixes to 48
+        assert_eq!(measure_rows(&[(1.0 / 3.0, 0, '1'), (0.25, 1, '1')]), 48);
+    }
+
+    #[test]
+    fn test_triplets_keep_timing() {
+        // 120 BPM: one beat = 500ms, triplet 8ths every 500/3 ms
+        let mut chart = RoxChart::new(4);
+        chart.timing_points.push(TimingPoint::bpm(0, 120.0));
+        for i in 0..6i64 {
+            #[allow(clippy::cast_possible_truncation)]
+            let time_us = (i as f64 * 500_000.0 / 3.0).round() as i64;
+            chart.notes.push(Note::tap(time_us, (i % 4) as u8));
+        }
+
+        let encoded = SmEncoder::encode(&chart).unwrap();
+        let output = String::from_utf8(encoded.clone()).unwrap();
+
+        // First measure: 12 rows, not 16
+        let notes_start = output.find("0,0,0,0,0:").unwrap() + "0,0,0,0,0:".len();
+        let first_measure: Vec<&str> = output[notes_start..]
+            .split(',')
+            .next()
+            .unwrap()
+            .split_whitespace()
+            .collect();
+        assert_eq!(first_measure.len(), 12);
+
+        let decoded = <SmDecoder as Decoder>::decode(&encoded).unwrap();
+        assert_eq!(decoded.notes.len(), chart.notes.len());
+        for (original, roundtrip) in chart.notes.iter().zip(&decoded.notes) {
+            assert!(
+                (original.time_us - roundtrip.time_us).abs() <= 1,
+                "{} vs {}",
+                original.time_us,
+                roundtrip.time_us
+            );
+            assert_eq!(original.column, roundtrip.column);
+        }
+    }
+
+    #[test]
+    fn test_tag_separators_are_escaped() {
+        let mut chart = RoxChart::new(4);
+        chart.metadata.title = "Intro; Outro: #1".into();
+        chart.metadata.artist = "A\\B".into();
+        chart.timing_points.push(TimingPoint::bpm(0, 120.0));
+        chart.notes.push(Note::tap(500_000, 0));
+
+        let encoded = SmEncoder::encode(&chart).unwrap();
+        let output = String::from_utf8(encoded.clone()).unwrap();
+        assert!(output.contains("#TITLE:Intro\\; Outro\\: \\#1;"));
+
+        let decoded = <SmDecoder as Decoder>::decode(&encoded).unwrap();
+        assert_eq!(decoded.metadata.title, "Intro; Outro: #1");
+        assert_eq!(decoded.metadata.artist, "A\\B");
+        assert_eq!(decoded.notes.len(), 1);
+    }
+
+    #[test]
+    fn test_display_bpm_tag() {
+        let mut chart = RoxChart::new(4);
+        chart.timing_points.push(TimingPoint::bpm(0, 150.0));
+        chart.notes.push(Note::tap(4_000_000, 0));
+        let output = String::from_utf8(SmEncoder::encode(&chart).unwrap()).unwrap();
+        assert!(output.contains("#DISPLAYBPM:150.000;"));
+
+        chart.timing_points.push(TimingPoint::bpm(2_000_000, 300.0));
+        let output = String::from_utf8(SmEncoder::encode(&chart).unwrap()).unwrap();
+        assert!(output.contains("#DISPLAYBPM:150.000:300.000;"));
+    }
 
     #[test]
     #[cfg(feature = "analysis")]
@@ -6,7 +6,11 @@
 )]
 //! Parser for StepMania (.sm) file format.
 
+use std::collections::HashMap;
+
+use crate::codec::DecodeLimits;
 use crate::error::{RoxError, RoxResult};
+use crate::model::{DEFAULT_BPM, DisplayBpm, MAX_KEY_COUNT};
 
 use super::types::{SmChart, SmFile, SmMetadata, SmNote, SmNoteType, timing};
 
@@ -26,6 +30,43 @@ const MAX_FILE_SIZE: usize = 100 * 1024 * 1024;
 /// - The data is not valid UTF-8
 /// - The file is larger than 100MB (Safety)
 pub fn parse(data: &[u8]) -> RoxResult<SmFile> {
+    parse_with_limits(data, &DecodeLimits::default())
+}
+
+/// Like [`parse`], but stops with [`RoxError::LimitExceeded`] as soon as a BPM
+/// change, or a note of any one chart, past `limits` is reached.
+///
+/// # Errors
+///
+/// Same as [`parse`], plus exceeded limits.
+pub fn parse_with_limits(data: &[u8], limits: &DecodeLimits) -> RoxResult<SmFile> {
+    parse_limited(data, limits, DEFAULT_BPM, &mut |_, _| {})
+}
+
+/// Like [`parse`], but reports `(bytes_processed, total_bytes)` to `progress`
+/// by the byte offset of the current note line.
+///
+/// Calls are throttled to roughly one per percent of the input, and the last
+/// one is always `(total_bytes, total_bytes)`.
+///
+/// # Errors
+///
+/// Same as [`parse`].
+pub fn parse_with_progress(
+    data: &[u8],
+    mut progress: impl FnMut(usize, usize),
+) -> RoxResult<SmFile> {
+    parse_limited(data, &DecodeLimits::default(), DEFAULT_BPM, &mut progress)
+}
+
+/// Shared body of the `parse*` functions. `default_bpm` times files without
+/// a BPM at beat 0.
+pub(super) fn parse_limited(
+    data: &[u8],
+    limits: &DecodeLimits,
+    default_bpm: f32,
+    progress: &mut dyn FnMut(usize, usize),
+) -> RoxResult<SmFile> {
     if data.len() > MAX_FILE_SIZE {
         return Err(RoxError::InvalidFormat(format!(
             "File too large: {} bytes (max {}MB)",
@@ -33,6 +74,7 @@ pub fn parse(data: &[u8]) -> RoxResult<SmFile> {
             MAX_FILE_SIZE / 1024 / 1024
         )));
     }
+    limits.check_file_size(data.len())?;
 
     let content = std::str::from_utf8(data)
         .map_err(|e| RoxError::InvalidFormat(format!("Invalid UTF-8: {e}")))?;
@@ -53,17 +95,62 @@ pub fn parse(data: &[u8]) -> RoxResult<SmFile> {
     }
 
     // Parse BPMs
-    sm.bpms = parse_bpms(content);
+    sm.bpms = parse_bpms(content, limits, default_bpm)?;
 
     // Parse stops
     sm.stops = parse_stops(content, &sm.bpms);
 
     // Parse charts
-    parse_charts(content, &mut sm.charts, &sm.bpms, &sm.stops);
+    let mut progress = Progress::new(content, progress);
+    parse_charts(
+        content,
+        &mut sm.charts,
+        &sm.bpms,
+        &sm.stops,
+        limits,
+        &mut progress,
+    )?;
+    progress.finish();
 
     Ok(sm)
 }
 
+/// Throttled `(bytes_processed, total_bytes)` reporting for [`parse_with_progress`].
+struct Progress<'a> {
+    report: &'a mut dyn FnMut(usize, usize),
+    start: usize,
+    total: usize,
+    step: usize,
+    next: usize,
+}
+
+impl<'a> Progress<'a> {
+    fn new(content: &str, report: &'a mut dyn FnMut(usize, usize)) -> Self {
+        let total = content.len();
+        let step = (total / 100).max(1);
+        Self {
+            report,
+            start: content.as_ptr().addr(),
+            total,
+            step,
+            next: step,
+        }
+    }
+
+    /// Report the offset of `line`, which must be a slice of the content.
+    fn line(&mut self, line: &str) {
+        let done = line.as_ptr().addr() - self.start;
+        if done >= self.next && done < self.total {
+            (self.report)(done, self.total);
+            self.next = done + self.step;
+        }
+    }
+
+    fn finish(&mut self) {
+        (self.report)(self.total, self.total);
+    }
+}
+
 /// Parse metadata fields from content.
 fn parse_metadata(content: &str, metadata: &mut SmMetadata) {
     if let Some(v) = parse_string_field(content, "#TITLE:") {
@@ -99,14 +186,56 @@ fn parse_metadata(content: &str, metadata: &mut SmMetadata) {
     if let Some(v) = parse_float_field(content, "#SAMPLELENGTH:") {
         metadata.sample_length = v;
     }
+    if let Some(v) = parse_string_field(content, "#DISPLAYBPM:") {
+        metadata.display_bpm = parse_display_bpm(&v);
+    }
+}
+
+/// Parse a `#DISPLAYBPM` value: `bpm`, `min:max` or `*` (random).
+fn parse_display_bpm(value: &str) -> Option<DisplayBpm> {
+    if value.is_empty() {
+        return None;
+    }
+    if value == "*" {
+        return Some(DisplayBpm::Random);
+    }
+
+    let parse = |s: &str| s.trim().parse::<f64>().ok().filter(|v| v.is_finite());
+    let display = match value.split_once(':') {
+        None => parse(value).map(DisplayBpm::Single),
+        Some((min, max)) => match (parse(min), parse(max)) {
+            (Some(min), Some(max)) => Some(DisplayBpm::Range(min.min(max), min.max(max))),
+            _ => None,
+        },
+    };
+    if display.is_none() {
+        tracing::warn!("Failed to parse #DISPLAYBPM: '{}'", value);
+    }
+    display
 }
 
 /// Parse a string field like `#TITLE:value;`
+///
+/// `\;`, `\:`, `\#` and `\\` are unescaped. Other backslashes are kept, as
+/// many files use them in Windows paths.
 fn parse_string_field(content: &str, tag: &str) -> Option<String> {
     let start = content.find(tag)?;
-    let after_tag = &content[start + tag.len()..];
-    let end = after_tag.find(';')?;
-    Some(after_tag[..end].trim().to_string())
+    let mut chars = content[start + tag.len()..].chars();
+    let mut value = String::new();
+    loop {
+        match chars.next()? {
+            ';' => break,
+            '\\' => match chars.clone().next() {
+                Some(c @ (';' | ':' | '#' | '\\')) => {
+                    chars.next();
+                    value.push(c);
+                }
+                _ => value.push('\\'),
+            },
+            c => value.push(c),
+        }
+    }
+    Some(value.trim().to_string())
 }
 
 /// Parse a float field like `#OFFSET:-0.123;`
@@ -122,7 +251,13 @@ fn parse_float_field(content: &str, tag: &str) -> Option<f64> {
 
 /// Parse BPM changes from `#BPMS:beat=bpm,beat=bpm,...;`
 /// Returns Vec of (time_us, bpm).
-fn parse_bpms(content: &str) -> Vec<(i64, f32)> {
+///
+/// Anything before the first BPM is timed with `default_bpm`.
+fn parse_bpms(
+    content: &str,
+    limits: &DecodeLimits,
+    default_bpm: f32,
+) -> RoxResult<Vec<(i64, f32)>> {
     let pairs = parse_pairs(content, "#BPMS:");
 
     // Convert beat positions to microseconds
@@ -130,7 +265,7 @@ fn parse_bpms(content: &str) -> Vec<(i64, f32)> {
     let mut result = Vec::new();
     let mut current_time_us: i64 = 0;
     let mut current_beat: f64 = 0.0;
-    let mut current_bpm: f32 = 120.0;
+    let mut current_bpm = default_bpm;
 
     for (beat, bpm) in pairs {
         if beat > current_beat {
@@ -143,16 +278,17 @@ fn parse_bpms(content: &str) -> Vec<(i64, f32)> {
 
         #[allow(clippy::cast_possible_truncation)]
         let bpm_f32 = bpm as f32;
+        limits.check_timing_points(result.len() + 1)?;
         result.push((current_time_us, bpm_f32));
         current_bpm = bpm_f32;
     }
 
     // Ensure we have at least one BPM at time 0
     if result.is_empty() || result[0].0 > 0 {
-        result.insert(0, (0, 120.0));
+        result.insert(0, (0, default_bpm));
     }
 
-    result
+    Ok(result)
 }
 
 /// Parse STOPS from `#STOPS:beat=duration,beat=duration,...;`
@@ -205,9 +341,8 @@ fn parse_pairs(content: &str, tag: &str) -> Vec<(f64, f64)> {
 /// Convert beat position to microseconds using BPM list.
 fn beat_to_us(target_beat: f64, bpms: &[(i64, f32)]) -> i64 {
     if bpms.is_empty() {
-        // Default 120 BPM
         let rows = target_beat * timing::ROWS_PER_BEAT;
-        return timing::rows_to_us(rows, 120.0);
+        return timing::rows_to_us(rows, DEFAULT_BPM);
     }
 
     let mut current_time_us: i64 = 0;
@@ -257,7 +392,9 @@ fn parse_charts(
     charts: &mut Vec<SmChart>,
     bpms: &[(i64, f32)],
     stops: &[(i64, i64)],
-) {
+    limits: &DecodeLimits,
+    progress: &mut Progress<'_>,
+) -> RoxResult<()> {
     // Split by #NOTES: to find each chart
     let sections: Vec<&str> = content.split("#NOTES:").skip(1).collect();
 
@@ -266,13 +403,20 @@ fn parse_charts(
         let end = section.find('#').unwrap_or(section.len());
         let chart_content = &section[..end];
 
-        if let Some(chart) = parse_chart(chart_content, bpms, stops) {
+        if let Some(chart) = parse_chart(chart_content, bpms, stops, limits, progress)? {
             charts.push(chart);
         }
     }
+    Ok(())
 }
 
-fn parse_chart(content: &str, bpms: &[(i64, f32)], _stops: &[(i64, i64)]) -> Option<SmChart> {
+fn parse_chart(
+    content: &str,
+    bpms: &[(i64, f32)],
+    _stops: &[(i64, i64)],
+    limits: &DecodeLimits,
+    progress: &mut Progress<'_>,
+) -> RoxResult<Option<SmChart>> {
     let lines: Vec<&str> = content.lines().map(str::trim).collect();
     let mut chart = SmChart::default();
 
@@ -306,7 +450,7 @@ fn parse_chart(content: &str, bpms: &[(i64, f32)], _stops: &[(i64, i64)]) -> Opt
 
     if header_fields.len() < 5 {
         tracing::warn!("Invalid chart header: missing fields");
-        return None;
+        return Ok(None);
     }
 
     chart.stepstype.clone_from(&header_fields[0]);
@@ -329,8 +473,21 @@ fn parse_chart(content: &str, bpms: &[(i64, f32)], _stops: &[(i64, i64)]) -> Opt
         }
     }
 
-    // Determine column count
+    // Determine column count (may be widened after reading the note data)
     chart.column_count = SmChart::column_count_from_stepstype(&chart.stepstype);
+    chart.is_routine = SmChart::is_routine_stepstype(&chart.stepstype);
+
+    // Estimate capacity from the rows holding a tap, hold or roll head
+    // (one note each, ignoring chords). Bounded by the line count and the
+    // note limit, so a huge file can't make this over-allocate
+    let note_rows = lines[idx..]
+        .iter()
+        .filter(|line| line.bytes().any(|b| matches!(b, b'1' | b'2' | b'4')))
+        .count();
+    chart.notes.reserve(note_rows.min(limits.max_notes));
+
+    // Number of note lines seen per line width
+    let mut line_widths: HashMap<usize, usize> = HashMap::new();
 
     // Parse measures
     let mut measure_num = 0;
@@ -366,7 +523,8 @@ fn parse_chart(content: &str, bpms: &[(i64, f32)], _stops: &[(i64, i64)]) -> Opt
                     bpms,
                     chart.column_count,
                     &mut chart.notes,
-                );
+                    limits,
+                )?;
             }
             break;
         }
@@ -381,7 +539,8 @@ fn parse_chart(content: &str, bpms: &[(i64, f32)], _stops: &[(i64, i64)]) -> Opt
                 bpms,
                 chart.column_count,
                 &mut chart.notes,
-            );
+                limits,
+            )?;
             current_measure_lines.clear();
             measure_num += 1;
             current_row = (measure_num as f64) * timing::ROWS_PER_MEASURE;
@@ -391,31 +550,79 @@ fn parse_chart(content: &str, bpms: &[(i64, f32)], _stops: &[(i64, i64)]) -> Opt
 
         // Note line
         if is_note_line(line) {
-            // Update column count if we see more columns
-            #[allow(clippy::cast_possible_truncation)]
-            {
-                if line.len() as u8 > chart.column_count {
-                    chart.column_count = line.len() as u8;
-                }
-            }
+            progress.line(line);
+            *line_widths.entry(note_chars(line).count()).or_insert(0) += 1;
             current_measure_lines.push(line);
         }
 
         idx += 1;
     }
 
-    Some(chart)
+    resolve_column_count(&mut chart, &line_widths);
+
+    Ok(Some(chart))
+}
+
+/// Settle the column count once all note lines are known.
+///
+/// The stepstype mapping is trusted first. It is only widened when the most
+/// common note line width is larger, i.e. the data is consistently wider (an
+/// unknown or mislabeled stepstype), and never past [`MAX_KEY_COUNT`]. Notes
+/// outside the final column count, such as those from a stray over-long line,
+/// are dropped.
+fn resolve_column_count(chart: &mut SmChart, line_widths: &HashMap<usize, usize>) {
+    // Most common width; ties go to the narrower one to stay deterministic
+    let dominant = line_widths
+        .iter()
+        .max_by(|(w1, c1), (w2, c2)| c1.cmp(c2).then(w2.cmp(w1)))
+        .map(|(&width, _)| width);
+
+    if let Some(width) = dominant
+        && let Ok(width) = u8::try_from(width)
+        && width > chart.column_count
+        && width <= MAX_KEY_COUNT
+    {
+        chart.column_count = width;
+    }
+
+    let column_count = chart.column_count;
+    let before = chart.notes.len();
+    chart.notes.retain(|n| n.column < column_count);
+    let dropped = before - chart.notes.len();
+    if dropped > 0 {
+        tracing::warn!(
+            "Dropped {} notes beyond column {} in '{}' chart",
+            dropped,
+            chart.column_count,
+            chart.stepstype
+        );
+    }
 }
 
-/// Check if a line contains only valid note characters.
+/// Check if a line is a row of notes.
+///
+/// `StepMania` reads any letter or digit as a note (unknown ones as empty),
+/// so those are accepted here too: dropping a row would shift every later
+/// row of its measure. Keysound (`1[3]`) and attack (`{...}`) annotations
+/// are allowed after a note.
 fn is_note_line(line: &str) -> bool {
-    !line.is_empty()
-        && line.chars().all(|c| {
-            matches!(
-                c,
-                '0' | '1' | '2' | '3' | '4' | 'M' | 'm' | 'L' | 'l' | 'F' | 'f'
-            )
-        })
+    note_chars(line).next().is_some() && note_chars(line).all(|c| c.is_ascii_alphanumeric())
+}
+
+/// The note characters of a row, one per column, without annotations.
+fn note_chars(line: &str) -> impl Iterator<Item = char> + '_ {
+    let mut depth = 0u32;
+    line.chars().filter(move |&c| match c {
+        '[' | '{' => {
+            depth += 1;
+            false
+        }
+        ']' | '}' => {
+            depth = depth.saturating_sub(1);
+            false
+        }
+        _ => depth == 0,
+    })
 }
 
 /// Parse notes from measure lines.
@@ -426,14 +633,16 @@ fn parse_measure_notes(
     bpms: &[(i64, f32)],
     _column_count: u8,
     notes: &mut Vec<SmNote>,
-) {
+    limits: &DecodeLimits,
+) -> RoxResult<()> {
     if lines.is_empty() {
         *current_row += timing::ROWS_PER_MEASURE;
-        return;
+        return Ok(());
     }
 
     let num_lines = lines.len();
     let rows_per_line = timing::ROWS_PER_MEASURE / (num_lines as f64);
+    let mut unknown = 0;
 
     for (line_idx, line) in lines.iter().enumerate() {
         #[allow(clippy::cast_possible_truncation)]
@@ -441,10 +650,15 @@ fn parse_measure_notes(
             (measure_num as f64) * timing::ROWS_PER_MEASURE + (line_idx as f64) * rows_per_line;
         let time_us = row_to_us(row, bpms);
 
-        for (col, ch) in line.chars().enumerate() {
+        for (col, ch) in note_chars(line).enumerate() {
             let note_type = SmNoteType::from_char(ch);
+            if note_type == SmNoteType::Empty && !SmNoteType::is_known_empty(ch) {
+                unknown += 1;
+            }
 
-            if note_type.is_note() {
+            // Fakes are kept so the decoder can decide what to do with them
+            if note_type != SmNoteType::Empty {
+                limits.check_notes(notes.len() + 1)?;
                 #[allow(clippy::cast_possible_truncation)]
                 notes.push(SmNote {
                     time_us,
@@ -455,13 +669,22 @@ fn parse_measure_notes(
         }
     }
 
+    if unknown > 0 {
+        tracing::warn!(
+            "Measure {}: read {} unknown note characters as empty",
+            measure_num,
+            unknown
+        );
+    }
+
     *current_row += timing::ROWS_PER_MEASURE;
+    Ok(())
 }
 
 /// Convert row position to microseconds using BPM list.
 fn row_to_us(row: f64, bpms: &[(i64, f32)]) -> i64 {
     if bpms.is_empty() {
-        return timing::rows_to_us(row, 120.0);
+        return timing::rows_to_us(row, DEFAULT_BPM);
     }
 
     let mut current_time_us: i64 = 0;
@@ -1,6 +1,8 @@
 #![allow(clippy::doc_markdown, clippy::match_same_arms)]
 //! Type definitions for StepMania (`.sm`) file format.
 
+use crate::model::DisplayBpm;
+
 /// Note types in StepMania format.
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum SmNoteType {
@@ -39,6 +41,13 @@ impl SmNoteType {
         }
     }
 
+    /// Whether `c` is a documented character that holds no note: `0`, or
+    /// `K` (a keysound played on its own).
+    #[must_use]
+    pub fn is_known_empty(c: char) -> bool {
+        matches!(c, '0' | 'K' | 'k')
+    }
+
     /// Convert to character for encoding.
     #[must_use]
     pub fn to_char(self) -> char {
@@ -90,6 +99,7 @@ pub struct SmMetadata {
     pub background: String,
     pub sample_start: f64,
     pub sample_length: f64,
+    pub display_bpm: Option<DisplayBpm>,
 }
 
 /// A single chart/difficulty in an SM file.
@@ -107,6 +117,10 @@ pub struct SmChart {
     pub radar_values: Vec<f64>,
     /// Number of columns (4 for dance-single, 8 for dance-double).
     pub column_count: u8,
+    /// Two-player routine/couple chart (`dance-routine`, `dance-couple`, ...).
+    ///
+    /// Both players share the columns, so this is not a 4K+4K coop split.
+    pub is_routine: bool,
     /// Parsed notes with timing.
     pub notes: Vec<SmNote>,
 }
@@ -120,6 +134,7 @@ impl Default for SmChart {
             meter: 0,
             radar_values: Vec::new(),
             column_count: 4,
+            is_routine: false,
             notes: Vec::new(),
         }
     }
@@ -133,12 +148,19 @@ impl SmChart {
         match stepstype.trim().to_lowercase().as_str() {
             "dance-single" | "pump-single" => 4,
             "dance-double" | "pump-double" => 8,
-            "dance-couple" => 8,
+            "dance-couple" | "dance-routine" => 8,
             "dance-solo" | "pump-halfdouble" => 6,
             // For unknown types, try to infer from note data later
             _ => 4,
         }
     }
+
+    /// Check whether a stepstype is a two-player routine/couple chart.
+    #[must_use]
+    pub fn is_routine_stepstype(stepstype: &str) -> bool {
+        let stepstype = stepstype.trim().to_lowercase();
+        stepstype.ends_with("-routine") || stepstype.ends_with("-couple")
+    }
 }
 
 /// A single note with timing information.
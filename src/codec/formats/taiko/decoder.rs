@@ -3,13 +3,16 @@
 //! Converts Taiko drums to a 4K layout:
 //! - Columns 0, 3: Kats (rim hits) - alternating
 //! - Columns 1, 2: Dons (center hits) - alternating
-//! - Big notes (Finish): Hit both columns at once
+//! - Big notes (Finish): Hit both columns at once, marked as `accent`
 
 use crate::codec::Decoder;
 use crate::error::RoxResult;
-use crate::model::{Metadata, Note, RoxChart, TimingPoint};
+use crate::model::{DEFAULT_BPM, Metadata, Note, RoxChart, TimingPoint};
 
 use super::types::{AlternationState, ColumnLayout};
+use crate::codec::formats::normalize_asset_path;
+use crate::codec::formats::osu::EFFECT_KIAI;
+use crate::codec::formats::osu::decoder::ensure_leading_bpm;
 use crate::codec::formats::taiko::parser;
 
 /// Decoder for osu!taiko beatmaps.
@@ -57,11 +60,22 @@ impl TaikoDecoder {
                 .clone()
                 .unwrap_or_else(|| beatmap.metadata.artist.clone())
                 .into(),
+            title_romanized: beatmap.metadata.romanized_title().map(Into::into),
+            artist_romanized: beatmap.metadata.romanized_artist().map(Into::into),
             creator: beatmap.metadata.creator.clone().into(),
             difficulty_name: beatmap.metadata.version.clone().into(),
             difficulty_value: Some(beatmap.difficulty.overall_difficulty),
-            audio_file: beatmap.general.audio_filename.clone().into(),
-            background_file: beatmap.background.clone().map(Into::into),
+            hp_drain_rate: Some(beatmap.difficulty.hp_drain_rate),
+            overall_difficulty: Some(beatmap.difficulty.overall_difficulty),
+            approach_rate: beatmap.difficulty.approach_rate,
+            countdown: beatmap.general.countdown,
+            sample_set: beatmap.general.sample_set.clone().map(Into::into),
+            stack_leniency: beatmap.general.stack_leniency,
+            audio_file: normalize_asset_path(&beatmap.general.audio_filename).into(),
+            background_file: beatmap
+                .background
+                .as_deref()
+                .map(|path| normalize_asset_path(path).into()),
             audio_offset_us: i64::from(beatmap.general.audio_lead_in) * 1000,
             preview_time_us: if beatmap.general.preview_time > 0 {
                 i64::from(beatmap.general.preview_time) * 1000
@@ -78,26 +92,33 @@ impl TaikoDecoder {
             ..Default::default()
         };
 
-        // Convert BPM timing points
+        // Convert timing points
         for tp in &beatmap.timing_points {
             #[allow(clippy::cast_possible_truncation)]
             let time_us = (tp.time * 1000.0) as i64;
 
+            let kiai = tp.effects & EFFECT_KIAI != 0;
+
             if tp.uninherited {
                 if let Some(bpm) = tp.bpm() {
                     let mut timing = TimingPoint::bpm(time_us, bpm);
                     timing.signature = tp.meter;
+                    timing.kiai = kiai;
                     chart.timing_points.push(timing);
                 }
             } else {
-                // SV logic if needed, but Taiko SV is complex.
-                // For now, let's stick to BPM.
+                // Green lines scroll the drum track like osu!mania SV, and
+                // often toggle kiai on their own
+                let mut timing = TimingPoint::sv(time_us, tp.scroll_velocity());
+                timing.kiai = kiai;
+                chart.timing_points.push(timing);
             }
         }
+        ensure_leading_bpm(&mut chart.timing_points, DEFAULT_BPM);
 
         // Ensure at least one BPM point
         if chart.timing_points.is_empty() {
-            chart.timing_points.push(TimingPoint::bpm(0, 120.0));
+            chart.timing_points.push(TimingPoint::bpm(0, DEFAULT_BPM));
         }
 
         // Convert hit objects
@@ -119,9 +140,12 @@ impl TaikoDecoder {
                 state.next_don_columns(is_big)
             };
 
-            // Create notes for each column
+            // Create notes for each column, keeping big notes accented
             for col in columns {
-                chart.notes.push(Note::tap(time_us, col));
+                chart.notes.push(Note {
+                    accent: is_big,
+                    ..Note::tap(time_us, col)
+                });
             }
         }
 
@@ -133,8 +157,68 @@ impl TaikoDecoder {
 }
 
 impl Decoder for TaikoDecoder {
+    fn probe(data: &[u8]) -> bool {
+        crate::codec::formats::osu::parser::has_header(data)
+    }
+
     fn decode(data: &[u8]) -> RoxResult<RoxChart> {
         let mut state = AlternationState::default();
         Self::decode_with_state(data, &mut state)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const BIG_DON: &[u8] = b"osu file format v14
+
+[General]
+Mode: 1
+
+[TimingPoints]
+0,500,4,1,0,100,1,0
+
+[HitObjects]
+256,192,1000,1,0,0:0:0:0:
+256,192,1500,1,4,0:0:0:0:
+";
+
+    #[test]
+    fn test_green_line_keeps_kiai() {
+        let data = b"osu file format v14\n\n[General]\nMode: 1\n\n[TimingPoints]\n0,500,4,1,0,100,1,0\n1000,-50,4,1,0,100,0,1\n2000,-100,4,1,0,100,0,0\n\n[HitObjects]\n256,192,1500,1,0,0:0:0:0:\n";
+        let chart = TaikoDecoder::decode(data).unwrap();
+
+        assert!(chart.validate().is_ok());
+        let sv: Vec<(i64, f32, bool)> = chart
+            .timing_points
+            .iter()
+            .filter(|tp| tp.is_inherited)
+            .map(|tp| (tp.time_us, tp.scroll_speed, tp.kiai))
+            .collect();
+        assert_eq!(sv, vec![(1_000_000, 2.0, true), (2_000_000, 1.0, false)]);
+    }
+
+    #[test]
+    fn test_big_note_is_accented() {
+        let chart = TaikoDecoder::decode(BIG_DON).unwrap();
+
+        // Small don: one note, big don: two notes
+        assert_eq!(chart.notes.len(), 3);
+        assert!(!chart.notes[0].accent);
+        assert!(chart.notes[1..].iter().all(|n| n.accent));
+    }
+
+    #[cfg(feature = "compression")]
+    #[test]
+    fn test_accent_survives_rox_roundtrip() {
+        use crate::codec::{Encoder, RoxCodec};
+
+        let chart = TaikoDecoder::decode(BIG_DON).unwrap();
+        let encoded = RoxCodec::encode(&chart).unwrap();
+        let decoded = RoxCodec::decode(&encoded).unwrap();
+
+        assert_eq!(decoded.notes, chart.notes);
+        assert_eq!(decoded.notes.iter().filter(|n| n.accent).count(), 2);
+    }
+}
@@ -13,6 +13,11 @@ pub struct YroxDecoder;
 pub struct YroxEncoder;
 
 impl Decoder for YroxDecoder {
+    fn probe(data: &[u8]) -> bool {
+        !data.trim_ascii_start().starts_with(b"{")
+            && memchr::memmem::find(data, b"timing_points:").is_some()
+    }
+
     fn decode(data: &[u8]) -> RoxResult<RoxChart> {
         // Safety limit: 100MB
         const MAX_FILE_SIZE: usize = 100 * 1024 * 1024;
@@ -21,12 +21,18 @@
 
 mod auto;
 pub mod formats;
+mod options;
+mod select;
 mod traits;
 
 pub use auto::{
-    InputFormat, OutputFormat, auto_convert, auto_decode, auto_encode, decode_with_format,
-    encode_with_format, from_bytes, from_string,
+    ConversionReport, FormatCapabilities, InputFormat, OutputFormat, auto_convert, auto_decode,
+    auto_decode_select, auto_encode, batch_convert, convert_checked, convert_with_report,
+    decode_with_format, decode_with_limits, decode_with_options, encode_with_format, from_bytes,
+    from_bytes_with_limits, from_string, supported_input_formats, supported_output_formats,
 };
 #[cfg(feature = "compression")]
 pub use formats::RoxCodec;
+pub use options::{DecodeLimits, DecodeOptions, NotePolicy};
+pub use select::ChartSelector;
 pub use traits::{Decoder, Encoder, Format, convert, convert_file};
@@ -0,0 +1,171 @@
+//! Options for decoding text-based chart formats.
+
+use std::borrow::Cow;
+
+use serde::{Deserialize, Serialize};
+
+use crate::error::{RoxError, RoxResult};
+use crate::model::{DEFAULT_BPM, NoteType, RoxChart};
+
+/// What to decode a note type without a ROX equivalent as.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum NotePolicy {
+    /// A regular tap.
+    #[default]
+    Tap,
+    /// A mine.
+    Mine,
+    /// Drop the note.
+    Skip,
+}
+
+/// Options controlling how text-based formats (`.osu`, `.sm`) are decoded.
+///
+/// The default is strict: input must be valid UTF-8. Options that only make
+/// sense for one format are ignored by the others.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct DecodeOptions {
+    /// On invalid UTF-8, detect the legacy encoding (Shift-JIS, EUC-KR,
+    /// Windows-1252, ...) and transcode to UTF-8 instead of failing.
+    ///
+    /// Only takes effect with the `encoding` feature enabled.
+    pub detect_encoding: bool,
+    /// How `StepMania` lifts (`L`) are decoded. Defaults to taps.
+    pub lift_as: NotePolicy,
+    /// Keep `StepMania` fakes (`F`) as taps with
+    /// [`Note::fake`](crate::model::Note::fake) set instead of dropping them.
+    pub keep_fakes: bool,
+    /// Holds and bursts shorter than this many microseconds are decoded as
+    /// taps. 0 (the default) keeps every hold.
+    pub min_hold_us: i64,
+    /// Tempo for `StepMania` files without a BPM at beat 0 and osu! beatmaps
+    /// without a red line, instead of [`DEFAULT_BPM`]. Set it when the real tempo is known (e.g. a
+    /// fixed-BPM pack).
+    pub default_bpm: Option<f32>,
+}
+
+impl DecodeOptions {
+    /// Options with encoding detection enabled.
+    #[must_use]
+    pub fn detect_encoding() -> Self {
+        Self {
+            detect_encoding: true,
+            ..Self::default()
+        }
+    }
+
+    /// Apply these options to raw input, returning UTF-8 bytes when possible.
+    ///
+    /// Valid UTF-8 (and anything when detection is off) is returned as-is, so
+    /// the parser's own UTF-8 check still reports invalid input.
+    pub(crate) fn text(self, data: &[u8]) -> Cow<'_, [u8]> {
+        if !self.detect_encoding || std::str::from_utf8(data).is_ok() {
+            return Cow::Borrowed(data);
+        }
+        transcode(data)
+    }
+
+    /// [`default_bpm`](Self::default_bpm), or [`DEFAULT_BPM`] when unset.
+    pub(crate) fn default_bpm(self) -> f32 {
+        self.default_bpm.unwrap_or(DEFAULT_BPM)
+    }
+
+    /// Clean up a freshly decoded chart, whatever its format.
+    ///
+    /// Currently turns holds and bursts shorter than
+    /// [`min_hold_us`](Self::min_hold_us) into taps. The `decode_with_limits`
+    /// entry points take no options and decode with the defaults; call this
+    /// on their result to get the same cleanup.
+    pub fn post_process(self, chart: &mut RoxChart) {
+        if self.min_hold_us <= 0 {
+            return;
+        }
+        for note in &mut chart.notes {
+            if (note.is_hold() || note.is_burst()) && note.duration_us() < self.min_hold_us {
+                note.note_type = NoteType::Tap;
+                note.tail_hitsound_index = None;
+            }
+        }
+    }
+}
+
+/// Caller-controlled caps for decoding untrusted input.
+///
+/// Pass to a `decode_with_limits` entry point (`RoxCodec`, `OsuDecoder`,
+/// `SmDecoder`).
+/// The text parsers stop at the first note or timing point past a limit, and
+/// the ROX decompressed size is capped while decompressing, so a small file
+/// cannot expand into an arbitrarily large allocation. Exceeding a limit
+/// returns [`RoxError::LimitExceeded`]; when decoding stops early, `found` is
+/// one more than `max`.
+///
+/// The default caps the input at 100MB, like the plain decoders, and a
+/// decompressed ROX payload at 1GB. Counts are unlimited.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct DecodeLimits {
+    /// Maximum size of the input in bytes.
+    pub max_file_bytes: usize,
+    /// Maximum size of a decompressed ROX payload in bytes.
+    pub max_decompressed_bytes: usize,
+    /// Maximum number of notes (hit objects for `.osu`).
+    pub max_notes: usize,
+    /// Maximum number of timing points.
+    pub max_timing_points: usize,
+}
+
+impl Default for DecodeLimits {
+    fn default() -> Self {
+        Self {
+            max_file_bytes: 100 * 1024 * 1024,
+            max_decompressed_bytes: 1024 * 1024 * 1024,
+            max_notes: usize::MAX,
+            max_timing_points: usize::MAX,
+        }
+    }
+}
+
+impl DecodeLimits {
+    pub(crate) fn check_file_size(&self, len: usize) -> RoxResult<()> {
+        check("bytes of input", len, self.max_file_bytes)
+    }
+
+    pub(crate) fn check_counts(&self, notes: usize, timing_points: usize) -> RoxResult<()> {
+        self.check_notes(notes)?;
+        self.check_timing_points(timing_points)
+    }
+
+    /// For parsers: call with the count a push would reach, before pushing.
+    pub(crate) fn check_notes(&self, notes: usize) -> RoxResult<()> {
+        check("notes", notes, self.max_notes)
+    }
+
+    /// For parsers: call with the count a push would reach, before pushing.
+    pub(crate) fn check_timing_points(&self, timing_points: usize) -> RoxResult<()> {
+        check("timing points", timing_points, self.max_timing_points)
+    }
+}
+
+fn check(what: &'static str, found: usize, max: usize) -> RoxResult<()> {
+    if found > max {
+        return Err(RoxError::LimitExceeded { what, found, max });
+    }
+    Ok(())
+}
+
+#[cfg(feature = "encoding")]
+fn transcode(data: &[u8]) -> Cow<'_, [u8]> {
+    let mut detector = chardetng::EncodingDetector::new();
+    detector.feed(data, true);
+    let encoding = detector.guess(None, true);
+    tracing::debug!("Transcoding non-UTF-8 input from {}", encoding.name());
+
+    let (text, _) = encoding.decode_without_bom_handling(data);
+    Cow::Owned(text.into_owned().into_bytes())
+}
+
+#[cfg(not(feature = "encoding"))]
+fn transcode(data: &[u8]) -> Cow<'_, [u8]> {
+    tracing::warn!("Encoding detection requested but the `encoding` feature is disabled");
+    Cow::Borrowed(data)
+}
@@ -0,0 +1,88 @@
+//! Chart selection for formats that bundle several difficulties in one file.
+
+/// Which chart to pick from a multi-chart file (e.g. a `.sm` with several
+/// difficulties).
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub enum ChartSelector {
+    /// The first chart in file order.
+    #[default]
+    First,
+    /// The chart with the highest difficulty rating (first one on ties).
+    Hardest,
+    /// The chart with the lowest difficulty rating (first one on ties).
+    Easiest,
+    /// The first chart whose difficulty name matches, ignoring ASCII case.
+    ByName(String),
+}
+
+impl ChartSelector {
+    /// Find the index of the selected item.
+    ///
+    /// `rating` and `name` extract the difficulty rating and name of an item,
+    /// so the selection can run on a format's own chart type before the
+    /// (more expensive) conversion to `RoxChart`.
+    pub(crate) fn position<T>(
+        &self,
+        items: &[T],
+        rating: impl Fn(&T) -> f64,
+        name: impl Fn(&T) -> &str,
+    ) -> Option<usize> {
+        let best_by = |better: fn(f64, f64) -> bool| {
+            let mut best: Option<(usize, f64)> = None;
+            for (i, item) in items.iter().enumerate() {
+                let value = rating(item);
+                if best.is_none_or(|(_, current)| better(value, current)) {
+                    best = Some((i, value));
+                }
+            }
+            best.map(|(i, _)| i)
+        };
+
+        match self {
+            Self::First => (!items.is_empty()).then_some(0),
+            Self::Hardest => best_by(|a, b| a > b),
+            Self::Easiest => best_by(|a, b| a < b),
+            Self::ByName(wanted) => items
+                .iter()
+                .position(|item| name(item).eq_ignore_ascii_case(wanted)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const CHARTS: [(&str, f64); 4] = [
+        ("Easy", 3.0),
+        ("Hard", 9.0),
+        ("Edit", 9.0),
+        ("Beginner", 1.0),
+    ];
+
+    fn select(selector: &ChartSelector) -> Option<usize> {
+        selector.position(&CHARTS, |c| c.1, |c| c.0)
+    }
+
+    #[test]
+    fn test_selector_position() {
+        assert_eq!(select(&ChartSelector::First), Some(0));
+        assert_eq!(select(&ChartSelector::Hardest), Some(1));
+        assert_eq!(select(&ChartSelector::Easiest), Some(3));
+        assert_eq!(select(&ChartSelector::ByName("edit".into())), Some(2));
+        assert_eq!(select(&ChartSelector::ByName("Challenge".into())), None);
+    }
+
+    #[test]
+    fn test_selector_empty() {
+        let empty: [(&str, f64); 0] = [];
+        assert_eq!(
+            ChartSelector::Hardest.position(&empty, |c| c.1, |c| c.0),
+            None
+        );
+        assert_eq!(
+            ChartSelector::First.position(&empty, |c| c.1, |c| c.0),
+            None
+        );
+    }
+}
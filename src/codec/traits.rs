@@ -14,6 +14,17 @@ pub trait Decoder {
     /// Returns an error if the data is invalid or cannot be parsed.
     fn decode(data: &[u8]) -> RoxResult<RoxChart>;
 
+    /// Cheaply check whether `data` looks like this format.
+    ///
+    /// Used by auto-detection to pick a decoder before parsing. Formats
+    /// override this with a magic-byte or header check; the default falls back
+    /// to a full [`decode`](Self::decode). A `true` result does not guarantee
+    /// that decoding succeeds.
+    #[must_use]
+    fn probe(data: &[u8]) -> bool {
+        Self::decode(data).is_ok()
+    }
+
     /// Decode a chart from a file path.
     ///
     /// # Errors
@@ -34,6 +45,22 @@ pub trait Encoder {
     /// Returns an error if the chart is invalid or encoding fails.
     fn encode(chart: &RoxChart) -> RoxResult<Vec<u8>>;
 
+    /// Encode a chart without validating it first.
+    ///
+    /// For callers that already validated it, or that want a best-effort
+    /// file from a chart [`RoxChart::validate`] rejects (e.g. with
+    /// overlapping notes). An invalid chart is written as-is, so the file
+    /// may not load in the target game. Formats whose
+    /// [`encode`](Self::encode) validates override this; the default just
+    /// calls `encode`.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if encoding fails.
+    fn encode_unchecked(chart: &RoxChart) -> RoxResult<Vec<u8>> {
+        Self::encode(chart)
+    }
+
     /// Encode a chart to a file path.
     ///
     /// # Errors
@@ -49,11 +76,13 @@ pub trait Encoder {
     ///
     /// # Errors
     ///
-    /// Returns an error if encoding fails or the output is not valid UTF-8.
+    /// Returns an error if encoding fails, or [`RoxError::NotUtf8`] if the
+    /// format is binary (e.g. ROX).
+    ///
+    /// [`RoxError::NotUtf8`]: crate::error::RoxError::NotUtf8
     fn encode_to_string(chart: &RoxChart) -> RoxResult<String> {
         let data = Self::encode(chart)?;
-        String::from_utf8(data)
-            .map_err(|e| crate::error::RoxError::InvalidFormat(format!("Invalid UTF-8: {e}")))
+        Ok(String::from_utf8(data)?)
     }
 }
 
@@ -157,6 +186,22 @@ mod tests {
         let data = crate::test_utils::get_test_asset("osu/mania_7k.osu");
         let chart = OsuDecoder::decode(&data).unwrap();
         let s = OsuEncoder::encode_to_string(&chart).unwrap();
+        assert!(s.starts_with("osu file format"));
         assert!(s.contains("Artist:Iced Blade"));
     }
+
+    #[cfg(all(feature = "compression", not(target_arch = "wasm32")))]
+    #[test]
+    fn test_encoder_to_string_rejects_binary() {
+        use crate::codec::RoxCodec;
+        use crate::error::RoxError;
+
+        let chart = crate::RoxChartBuilder::new(4)
+            .bpm(0, 120.0)
+            .tap(0, 0)
+            .build()
+            .unwrap();
+        let result = RoxCodec::encode_to_string(&chart);
+        assert!(matches!(result, Err(RoxError::NotUtf8(_))));
+    }
 }
@@ -23,6 +23,9 @@ pub enum RoxError {
     #[error("Unsupported version: {0}")]
     UnsupportedVersion(u8),
 
+    #[error("Invalid key count {key_count}K (must be 1-{max}K)")]
+    InvalidKeyCount { key_count: u8, max: u8 },
+
     #[error("Invalid column index {column} for {key_count}K chart")]
     InvalidColumn { column: u8, key_count: u8 },
 
@@ -49,4 +52,161 @@ pub enum RoxError {
 
     #[error("Unsupported format: {0}")]
     UnsupportedFormat(String),
+
+    #[error("Wrong game mode: found mode {found}, expected mode {expected}")]
+    WrongGameMode { found: u8, expected: u8 },
+
+    #[error("Key count mismatch: expected {expected}K, found {found}K")]
+    KeyCountMismatch { expected: u8, found: u8 },
+
+    #[error("Encoded output is not valid UTF-8 (binary format?): {0}")]
+    NotUtf8(#[from] std::string::FromUtf8Error),
+
+    #[error("Decode limit exceeded: {found} {what} (max {max})")]
+    LimitExceeded {
+        what: &'static str,
+        found: usize,
+        max: usize,
+    },
+
+    #[error("Re-decoding the {format} output gave {found} notes, expected {expected}")]
+    NoteCountMismatch {
+        format: &'static str,
+        expected: usize,
+        found: usize,
+    },
+
+    #[error(
+        "Hold on column {column} at {start_us}µs runs past the next note at {overlaps_at_us}µs"
+    )]
+    HoldTooLong {
+        column: u8,
+        start_us: i64,
+        overlaps_at_us: i64,
+    },
+}
+
+impl RoxError {
+    /// Stable numeric code for this error kind, for callers (e.g. over FFI)
+    /// that need to branch on the kind without parsing messages.
+    ///
+    /// `0` is never returned so it can mean success. Codes are never reused:
+    ///
+    /// | Code | Variant |
+    /// |------|---------|
+    /// | 1 | `Io` |
+    /// | 2 | `Serialize` |
+    /// | 3 | `Deserialize` |
+    /// | 4 | `InvalidFormat` |
+    /// | 5 | `UnsupportedVersion` |
+    /// | 6 | `InvalidColumn` |
+    /// | 7 | `InvalidHoldDuration` |
+    /// | 8 | `TimingPointsNotSorted` |
+    /// | 9 | `OverlappingNotes` |
+    /// | 10 | `NotesNotSorted` |
+    /// | 11 | `NoBpmTimingPoint` |
+    /// | 12 | `BpmAfterFirstNote` |
+    /// | 13 | `ParseError` |
+    /// | 14 | `UnsupportedFormat` |
+    /// | 15 | `WrongGameMode` |
+    /// | 16 | `KeyCountMismatch` |
+    /// | 17 | `InvalidKeyCount` |
+    /// | 18 | `NotUtf8` |
+    /// | 19 | `LimitExceeded` |
+    /// | 20 | `NoteCountMismatch` |
+    /// | 21 | `HoldTooLong` |
+    #[must_use]
+    pub fn error_code(&self) -> u32 {
+        match self {
+            Self::Io(_) => 1,
+            Self::Serialize(_) => 2,
+            Self::Deserialize(_) => 3,
+            Self::InvalidFormat(_) => 4,
+            Self::UnsupportedVersion(_) => 5,
+            Self::InvalidColumn { .. } => 6,
+            Self::InvalidHoldDuration { .. } => 7,
+            Self::TimingPointsNotSorted { .. } => 8,
+            Self::OverlappingNotes { .. } => 9,
+            Self::NotesNotSorted { .. } => 10,
+            Self::NoBpmTimingPoint => 11,
+            Self::BpmAfterFirstNote { .. } => 12,
+            Self::ParseError { .. } => 13,
+            Self::UnsupportedFormat(_) => 14,
+            Self::WrongGameMode { .. } => 15,
+            Self::KeyCountMismatch { .. } => 16,
+            Self::InvalidKeyCount { .. } => 17,
+            Self::NotUtf8(_) => 18,
+            Self::LimitExceeded { .. } => 19,
+            Self::NoteCountMismatch { .. } => 20,
+            Self::HoldTooLong { .. } => 21,
+        }
+    }
+
+    /// Stable `snake_case` name for this error kind (`"invalid_format"`,
+    /// `"limit_exceeded"`, ...), the string counterpart of
+    /// [`error_code`](Self::error_code) for bindings such as WASM.
+    #[must_use]
+    pub fn kind(&self) -> &'static str {
+        match self {
+            Self::Io(_) => "io",
+            Self::Serialize(_) => "serialize",
+            Self::Deserialize(_) => "deserialize",
+            Self::InvalidFormat(_) => "invalid_format",
+            Self::UnsupportedVersion(_) => "unsupported_version",
+            Self::InvalidColumn { .. } => "invalid_column",
+            Self::InvalidHoldDuration { .. } => "invalid_hold_duration",
+            Self::TimingPointsNotSorted { .. } => "timing_points_not_sorted",
+            Self::OverlappingNotes { .. } => "overlapping_notes",
+            Self::NotesNotSorted { .. } => "notes_not_sorted",
+            Self::NoBpmTimingPoint => "no_bpm_timing_point",
+            Self::BpmAfterFirstNote { .. } => "bpm_after_first_note",
+            Self::ParseError { .. } => "parse_error",
+            Self::UnsupportedFormat(_) => "unsupported_format",
+            Self::WrongGameMode { .. } => "wrong_game_mode",
+            Self::KeyCountMismatch { .. } => "key_count_mismatch",
+            Self::InvalidKeyCount { .. } => "invalid_key_count",
+            Self::NotUtf8(_) => "not_utf8",
+            Self::LimitExceeded { .. } => "limit_exceeded",
+            Self::NoteCountMismatch { .. } => "note_count_mismatch",
+            Self::HoldTooLong { .. } => "hold_too_long",
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_error_codes() {
+        assert_eq!(RoxError::UnsupportedFormat("mp3".into()).error_code(), 14);
+        assert_eq!(RoxError::InvalidFormat(String::new()).error_code(), 4);
+        assert_eq!(RoxError::NoBpmTimingPoint.error_code(), 11);
+        assert_eq!(
+            RoxError::InvalidKeyCount {
+                key_count: 0,
+                max: 18
+            }
+            .error_code(),
+            17
+        );
+    }
+
+    #[test]
+    fn test_error_kinds() {
+        assert_eq!(
+            RoxError::InvalidFormat(String::new()).kind(),
+            "invalid_format"
+        );
+        assert_eq!(RoxError::NoBpmTimingPoint.kind(), "no_bpm_timing_point");
+        assert_eq!(
+            RoxError::LimitExceeded {
+                what: "notes",
+                found: 2,
+                max: 1
+            }
+            .kind(),
+            "limit_exceeded"
+        );
+    }
 }
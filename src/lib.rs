@@ -20,7 +20,7 @@ pub mod test_utils;
 pub use codec::RoxCodec;
 pub use codec::{
     Decoder, Encoder, InputFormat, OutputFormat, auto_convert, auto_decode, auto_encode,
-    encode_with_format, from_bytes, from_string,
+    batch_convert, encode_with_format, from_bytes, from_string,
 };
 pub use error::{RoxError, RoxResult};
-pub use model::{Hitsound, Metadata, Note, NoteType, RoxChart, TimingPoint};
+pub use model::{Hitsound, Metadata, Note, NoteType, RoxChart, RoxChartBuilder, TimingPoint};
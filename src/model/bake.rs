@@ -0,0 +1,107 @@
+//! Baking scroll velocity into BPM for engines without SV.
+
+use super::{RoxChart, TimingPoint};
+
+/// Lowest scroll speed baked into a tempo, osu!mania's minimum SV.
+const MIN_BAKED_SCROLL_SPEED: f32 = 0.01;
+
+impl RoxChart {
+    /// Replace SV points with BPM points whose tempo is the BPM in effect
+    /// times the active scroll speed.
+    ///
+    /// For engines that derive scroll speed from BPM and have no SV of their
+    /// own, the baked chart scrolls like the original. Note times don't
+    /// change, but the nominal BPM values do, and every former SV point now
+    /// re-anchors beats and measures, so beat snapping and bar lines after it
+    /// no longer match the song. Points at the same time are merged into one.
+    /// Repeated identical tempos are kept; see
+    /// [`simplify_timing`](Self::simplify_timing). Timing points must be
+    /// sorted by time, and SV points before the first BPM point are dropped.
+    /// A scroll speed of zero or less (or NaN) would give a tempo no engine
+    /// can play, so it is clamped to 0.01x first: stops and reverse scroll
+    /// become a near-stop.
+    pub fn bake_sv_into_bpm(&mut self) {
+        let mut baked: Vec<TimingPoint> = Vec::with_capacity(self.timing_points.len());
+        // (bpm, signature) of the last BPM point
+        let mut base: Option<(f32, u8)> = None;
+
+        for tp in &self.timing_points {
+            if !tp.is_inherited {
+                base = Some((tp.bpm, tp.signature));
+            }
+            let Some((bpm, signature)) = base else {
+                continue;
+            };
+
+            let mut point = TimingPoint::bpm(
+                tp.time_us,
+                bpm * tp.scroll_speed.max(MIN_BAKED_SCROLL_SPEED),
+            );
+            point.signature = signature;
+            point.kiai = tp.kiai;
+            match baked.last_mut() {
+                Some(last) if last.time_us == tp.time_us => *last = point,
+                _ => baked.push(point),
+            }
+        }
+
+        self.timing_points = baked;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::model::{Note, RoxChart, TimingPoint};
+
+    #[test]
+    fn test_bake_sv_into_bpm() {
+        let mut chart = RoxChart::new(4);
+        chart.timing_points.push(TimingPoint::sv(0, 3.0));
+        chart.timing_points.push(TimingPoint::bpm(0, 120.0));
+        chart.timing_points.push(TimingPoint::sv(1_000_000, 1.5));
+        chart.timing_points.push(TimingPoint::sv(2_000_000, 1.0));
+        let mut three_four = TimingPoint::bpm(3_000_000, 200.0);
+        three_four.signature = 3;
+        chart.timing_points.push(three_four);
+        chart.timing_points.push(TimingPoint::sv(3_000_000, 0.5));
+        chart.notes.push(Note::tap(500_000, 0));
+        chart.notes.push(Note::hold(1_500_000, 250_000, 1));
+        chart.notes.push(Note::tap(3_500_000, 2));
+        let notes = chart.notes.clone();
+
+        chart.bake_sv_into_bpm();
+
+        assert!(chart.timing_points.iter().all(|tp| !tp.is_inherited));
+        let tempos: Vec<(i64, f32, u8)> = chart
+            .timing_points
+            .iter()
+            .map(|tp| (tp.time_us, tp.bpm, tp.signature))
+            .collect();
+        assert_eq!(
+            tempos,
+            vec![
+                (0, 120.0, 4),
+                (1_000_000, 180.0, 4),
+                (2_000_000, 120.0, 4),
+                (3_000_000, 100.0, 3),
+            ]
+        );
+        assert_eq!(chart.notes, notes);
+    }
+
+    #[test]
+    fn test_bake_sv_into_bpm_clamps_non_positive_speeds() {
+        let mut chart = RoxChart::new(4);
+        chart.timing_points.push(TimingPoint::bpm(0, 120.0));
+        chart.timing_points.push(TimingPoint::sv(1_000_000, 0.0));
+        chart.timing_points.push(TimingPoint::sv(2_000_000, -2.0));
+
+        chart.bake_sv_into_bpm();
+
+        let bpms: Vec<f32> = chart.timing_points.iter().map(|tp| tp.bpm).collect();
+        assert_eq!(bpms.len(), 3);
+        assert!(bpms.iter().all(|&bpm| bpm > 0.0));
+        assert!((bpms[1] - 1.2).abs() < 1e-4);
+        assert!((bpms[2] - 1.2).abs() < 1e-4);
+    }
+}
@@ -0,0 +1,131 @@
+//! Conversion between absolute time and beat position.
+//!
+//! Beat 0 is anchored at the first BPM timing point. Inherited (SV) points
+//! never affect beat positions, only scroll speed.
+
+use super::{DEFAULT_BPM, NoteType, RoxChart, TimingPoint};
+
+#[allow(clippy::cast_precision_loss)]
+fn beats_between(from_us: i64, to_us: i64, bpm: f64) -> f64 {
+    (to_us - from_us) as f64 / 60_000_000.0 * bpm
+}
+
+fn bpm_points(timing_points: &[TimingPoint]) -> impl Iterator<Item = &TimingPoint> {
+    timing_points
+        .iter()
+        .filter(|tp| !tp.is_inherited && tp.bpm > 0.0)
+}
+
+/// Convert an absolute time to a (fractional) beat position.
+///
+/// Timing points must be sorted by time. Times before the first BPM point
+/// extrapolate backwards with the first BPM, yielding negative beats.
+pub(crate) fn us_to_beat(timing_points: &[TimingPoint], time_us: i64) -> f64 {
+    let mut bpms = bpm_points(timing_points);
+    let Some(first) = bpms.next() else {
+        return beats_between(0, time_us, f64::from(DEFAULT_BPM));
+    };
+
+    let mut seg_time = first.time_us;
+    let mut seg_beat = 0.0;
+    let mut bpm = f64::from(first.bpm);
+    for tp in bpms {
+        if tp.time_us > time_us {
+            break;
+        }
+        seg_beat += beats_between(seg_time, tp.time_us, bpm);
+        seg_time = tp.time_us;
+        bpm = f64::from(tp.bpm);
+    }
+
+    seg_beat + beats_between(seg_time, time_us, bpm)
+}
+
+/// Convert a beat position back to an absolute time (inverse of [`us_to_beat`]).
+#[allow(clippy::cast_possible_truncation)]
+pub(crate) fn beat_to_us(timing_points: &[TimingPoint], beat: f64) -> i64 {
+    let mut bpms = bpm_points(timing_points);
+    let Some(first) = bpms.next() else {
+        return (beat * 60_000_000.0 / f64::from(DEFAULT_BPM)).round() as i64;
+    };
+
+    let mut seg_time = first.time_us;
+    let mut seg_beat = 0.0;
+    let mut bpm = f64::from(first.bpm);
+    for tp in bpms {
+        let tp_beat = seg_beat + beats_between(seg_time, tp.time_us, bpm);
+        if tp_beat > beat {
+            break;
+        }
+        seg_time = tp.time_us;
+        seg_beat = tp_beat;
+        bpm = f64::from(tp.bpm);
+    }
+
+    seg_time + ((beat - seg_beat) * 60_000_000.0 / bpm).round() as i64
+}
+
+impl RoxChart {
+    /// Replace the timing points and move every note so it stays on the same beat.
+    ///
+    /// Each note (and each hold/burst end) is converted to a beat position with
+    /// the current timing, then back to a time with `new_timing`. Use this after
+    /// correcting a BPM map: notes keep their rhythm instead of their absolute
+    /// times. Beat 0 is the first BPM point of each map, so both maps should
+    /// start at the same musical position.
+    pub fn retime(&mut self, new_timing: Vec<TimingPoint>) {
+        let old_timing = std::mem::replace(&mut self.timing_points, new_timing);
+
+        for note in &mut self.notes {
+            let start_beat = us_to_beat(&old_timing, note.time_us);
+            let new_start = beat_to_us(&self.timing_points, start_beat);
+
+            if let NoteType::Hold { duration_us } | NoteType::Burst { duration_us } =
+                &mut note.note_type
+            {
+                let end_beat = us_to_beat(&old_timing, note.time_us + *duration_us);
+                *duration_us = beat_to_us(&self.timing_points, end_beat) - new_start;
+            }
+
+            note.time_us = new_start;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model::Note;
+
+    #[test]
+    fn test_beat_conversion_with_bpm_change() {
+        // 120 BPM for 4 beats (2s), then 240 BPM
+        let timing = vec![
+            TimingPoint::bpm(0, 120.0),
+            TimingPoint::sv(500_000, 2.0),
+            TimingPoint::bpm(2_000_000, 240.0),
+        ];
+
+        assert!((us_to_beat(&timing, 1_000_000) - 2.0).abs() < 1e-9);
+        assert!((us_to_beat(&timing, 2_500_000) - 6.0).abs() < 1e-9);
+        assert_eq!(beat_to_us(&timing, 2.0), 1_000_000);
+        assert_eq!(beat_to_us(&timing, 6.0), 2_500_000);
+    }
+
+    #[test]
+    fn test_retime_double_bpm_halves_times() {
+        let mut chart = RoxChart::new(4);
+        chart.timing_points.push(TimingPoint::bpm(0, 120.0));
+        chart.notes.push(Note::tap(500_000, 0));
+        chart.notes.push(Note::tap(1_000_000, 1));
+        chart.notes.push(Note::hold(2_000_000, 1_000_000, 2));
+
+        chart.retime(vec![TimingPoint::bpm(0, 240.0)]);
+
+        assert_eq!(chart.notes[0].time_us, 250_000);
+        assert_eq!(chart.notes[1].time_us, 500_000);
+        assert_eq!(chart.notes[2].time_us, 1_000_000);
+        assert_eq!(chart.notes[2].duration_us(), 500_000);
+        assert_eq!(chart.timing_points[0].bpm, 240.0);
+    }
+}
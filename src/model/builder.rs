@@ -0,0 +1,166 @@
+//! Fluent builder for constructing charts programmatically.
+
+use compact_str::CompactString;
+
+use super::{Note, RoxChart, TimingPoint};
+use crate::error::RoxResult;
+
+/// Builder for [`RoxChart`].
+///
+/// Notes and timing points can be added in any order; [`build`](Self::build)
+/// sorts them and validates the result.
+///
+/// ```
+/// use rhythm_open_exchange::RoxChartBuilder;
+///
+/// let chart = RoxChartBuilder::new(4)
+///     .title("x")
+///     .artist("y")
+///     .bpm(0, 180.0)
+///     .tap(1_000_000, 0)
+///     .build()
+///     .unwrap();
+/// assert_eq!(chart.notes.len(), 1);
+/// ```
+#[derive(Debug, Clone)]
+#[must_use]
+pub struct RoxChartBuilder {
+    chart: RoxChart,
+}
+
+impl RoxChartBuilder {
+    /// Start a chart with the given key count.
+    pub fn new(key_count: u8) -> Self {
+        Self {
+            chart: RoxChart::new(key_count),
+        }
+    }
+
+    /// Set the song title.
+    pub fn title(mut self, title: impl Into<CompactString>) -> Self {
+        self.chart.metadata.title = title.into();
+        self
+    }
+
+    /// Set the song artist.
+    pub fn artist(mut self, artist: impl Into<CompactString>) -> Self {
+        self.chart.metadata.artist = artist.into();
+        self
+    }
+
+    /// Set the chart creator.
+    pub fn creator(mut self, creator: impl Into<CompactString>) -> Self {
+        self.chart.metadata.creator = creator.into();
+        self
+    }
+
+    /// Set the difficulty name.
+    pub fn difficulty_name(mut self, name: impl Into<CompactString>) -> Self {
+        self.chart.metadata.difficulty_name = name.into();
+        self
+    }
+
+    /// Set the audio file path.
+    pub fn audio_file(mut self, path: impl Into<CompactString>) -> Self {
+        self.chart.metadata.audio_file = path.into();
+        self
+    }
+
+    /// Mark the chart as a 2-player coop chart.
+    pub fn coop(mut self, is_coop: bool) -> Self {
+        self.chart.metadata.is_coop = is_coop;
+        self
+    }
+
+    /// Add a BPM timing point.
+    pub fn bpm(self, time_us: i64, bpm: f32) -> Self {
+        self.timing_point(TimingPoint::bpm(time_us, bpm))
+    }
+
+    /// Add a scroll velocity timing point.
+    pub fn sv(self, time_us: i64, scroll_speed: f32) -> Self {
+        self.timing_point(TimingPoint::sv(time_us, scroll_speed))
+    }
+
+    /// Add an arbitrary timing point.
+    pub fn timing_point(mut self, timing_point: TimingPoint) -> Self {
+        self.chart.timing_points.push(timing_point);
+        self
+    }
+
+    /// Add a tap note.
+    pub fn tap(self, time_us: i64, column: u8) -> Self {
+        self.note(Note::tap(time_us, column))
+    }
+
+    /// Add a hold note.
+    pub fn hold(self, time_us: i64, duration_us: i64, column: u8) -> Self {
+        self.note(Note::hold(time_us, duration_us, column))
+    }
+
+    /// Add a burst/roll note.
+    pub fn burst(self, time_us: i64, duration_us: i64, column: u8) -> Self {
+        self.note(Note::burst(time_us, duration_us, column))
+    }
+
+    /// Add a mine.
+    pub fn mine(self, time_us: i64, column: u8) -> Self {
+        self.note(Note::mine(time_us, column))
+    }
+
+    /// Add an arbitrary note.
+    pub fn note(mut self, note: Note) -> Self {
+        self.chart.notes.push(note);
+        self
+    }
+
+    /// Sort notes and timing points by time and validate the chart.
+    ///
+    /// # Errors
+    ///
+    /// Returns the first error reported by [`RoxChart::validate`].
+    pub fn build(mut self) -> RoxResult<RoxChart> {
+        // Stable sorts keep the insertion order of simultaneous events
+        self.chart.timing_points.sort_by_key(|tp| tp.time_us);
+        self.chart.notes.sort_by_key(|n| n.time_us);
+        self.chart.validate()?;
+        Ok(self.chart)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::RoxError;
+
+    #[test]
+    fn test_build_sorts_and_validates() {
+        let chart = RoxChartBuilder::new(4)
+            .title("x")
+            .artist("y")
+            .tap(2_000_000, 1)
+            .hold(1_500_000, 250_000, 3)
+            .sv(1_000_000, 1.5)
+            .bpm(0, 180.0)
+            .tap(1_000_000, 0)
+            .build()
+            .unwrap();
+
+        assert!(chart.is_valid());
+        assert_eq!(chart.metadata.title, "x");
+        assert_eq!(chart.metadata.artist, "y");
+        let times: Vec<i64> = chart.notes.iter().map(|n| n.time_us).collect();
+        assert_eq!(times, vec![1_000_000, 1_500_000, 2_000_000]);
+        assert!(!chart.timing_points[0].is_inherited);
+        assert!(chart.timing_points[1].is_inherited);
+    }
+
+    #[test]
+    fn test_build_rejects_invalid_chart() {
+        let result = RoxChartBuilder::new(4).bpm(0, 120.0).tap(0, 4).build();
+        assert!(matches!(result, Err(RoxError::InvalidColumn { .. })));
+
+        let result = RoxChartBuilder::new(4).tap(0, 0).build();
+        assert!(matches!(result, Err(RoxError::NoBpmTimingPoint)));
+    }
+}
@@ -3,10 +3,18 @@
 use rkyv::{Archive, Deserialize, Serialize};
 use serde::{Deserialize as SerdeDeserialize, Serialize as SerdeSerialize};
 
+use super::hitsound::rebase_hitsound_index;
 use super::{Hitsound, Metadata, Note, TimingPoint};
 
 /// Current ROX format version.
-pub const ROX_VERSION: u8 = 2;
+///
+/// Bump it whenever a field is added to the archived model: rkyv archives are
+/// not self-describing, so `RoxCodec` keeps a frozen copy of each older layout
+/// to read existing files.
+pub const ROX_VERSION: u8 = 3;
+
+/// Largest supported key count.
+pub const MAX_KEY_COUNT: u8 = 18;
 
 /// Magic bytes to identify ROX files: "ROX\0"
 pub const ROX_MAGIC: [u8; 4] = [0x52, 0x4F, 0x58, 0x00];
@@ -44,12 +52,49 @@ impl RoxChart {
         }
     }
 
+    /// Reset to an empty chart with the given key count, keeping the
+    /// allocations of the note, timing point and hitsound vectors.
+    pub(crate) fn reset(&mut self, key_count: u8) {
+        self.version = ROX_VERSION;
+        self.metadata = Metadata {
+            key_count,
+            ..Metadata::default()
+        };
+        self.timing_points.clear();
+        self.notes.clear();
+        self.hitsounds.clear();
+    }
+
+    /// Copy the chart without its notes.
+    ///
+    /// Metadata (and so the key count), timing points and hitsounds are kept,
+    /// as a starting point for generated variants (mirrors, rates, spreads).
+    #[must_use]
+    pub fn clone_empty(&self) -> Self {
+        Self {
+            version: self.version,
+            metadata: self.metadata.clone(),
+            timing_points: self.timing_points.clone(),
+            notes: Vec::new(),
+            hitsounds: self.hitsounds.clone(),
+        }
+    }
+
     /// Get the key count (convenience accessor for `metadata.key_count`).
     #[must_use]
     pub fn key_count(&self) -> u8 {
         self.metadata.key_count
     }
 
+    /// Set the key count.
+    ///
+    /// `metadata.key_count` is the only place the key count is stored; notes
+    /// are left untouched, so shrinking the key count can make the chart
+    /// invalid.
+    pub fn set_key_count(&mut self, key_count: u8) {
+        self.metadata.key_count = key_count;
+    }
+
     /// Get the total duration of the chart in microseconds.
     #[must_use]
     pub fn duration_us(&self) -> i64 {
@@ -60,21 +105,75 @@ impl RoxChart {
             .unwrap_or(0)
     }
 
-    /// Get the number of notes (taps + holds).
+    /// Get the number of judged notes, fakes excluded.
+    ///
+    /// Same as [`NoteCounts::total`](crate::model::NoteCounts::total); use
+    /// `notes.len()` to count every entry.
     #[must_use]
     pub fn note_count(&self) -> usize {
-        self.notes.len()
+        self.notes.iter().filter(|n| !n.fake).count()
+    }
+
+    /// Release times of all holds and bursts as `(release_time_us, column)`,
+    /// sorted by time (then column).
+    #[must_use]
+    pub fn hold_releases(&self) -> Vec<(i64, u8)> {
+        let mut releases: Vec<(i64, u8)> = self
+            .notes
+            .iter()
+            .filter(|n| n.is_hold() || n.is_burst())
+            .map(|n| (n.end_time_us(), n.column))
+            .collect();
+        releases.sort_unstable();
+        releases
+    }
+
+    /// Notes grouped into rows of identical start time, as
+    /// `(time_us, notes)`.
+    ///
+    /// Rows borrow from `notes`, which must be sorted by time. For grouping
+    /// with a tolerance, see `analysis::chords::group_by_time`.
+    pub fn rows(&self) -> impl Iterator<Item = (i64, &[Note])> + '_ {
+        self.notes
+            .chunk_by(|a, b| a.time_us == b.time_us)
+            .map(|row| (row[0].time_us, row))
+    }
+
+    /// Notes grouped by column: entry `c` holds the notes in column `c`,
+    /// sorted by time.
+    ///
+    /// Built in a single pass over `notes`, so column-oriented analysis
+    /// doesn't have to filter the whole chart once per lane. Notes whose
+    /// column is outside the key count are left out; see
+    /// [`validate`](Self::validate).
+    #[must_use]
+    pub fn notes_by_column(&self) -> Vec<Vec<&Note>> {
+        let mut columns = vec![Vec::new(); self.key_count() as usize];
+        for note in &self.notes {
+            if let Some(column) = columns.get_mut(note.column as usize) {
+                column.push(note);
+            }
+        }
+        if !self.notes.is_sorted_by_key(|n| n.time_us) {
+            for column in &mut columns {
+                column.sort_by_key(|n| n.time_us);
+            }
+        }
+        columns
     }
 
     /// Validate the chart for consistency and correctness.
     ///
     /// Checks:
+    /// - Key count is between 1 and [`MAX_KEY_COUNT`]
     /// - All note columns are within bounds
     /// - All hold/burst durations are > 0
     /// - Timing points are sorted by time
     /// - At least one BPM timing point exists
     /// - First BPM timing point is at or before the first note
-    /// - No overlapping notes on the same column
+    /// - No overlapping notes on the same column; a hold or burst that runs
+    ///   past the start of the next note in its column is reported as
+    ///   [`HoldTooLong`](crate::RoxError::HoldTooLong) so it can be shortened
     ///
     /// # Errors
     ///
@@ -82,10 +181,26 @@ impl RoxChart {
     pub fn validate(&self) -> Result<(), crate::RoxError> {
         // 1. Check metadata consistency
         let key_count = self.key_count();
-        if self.metadata.is_coop && !key_count.is_multiple_of(2) {
-            return Err(crate::RoxError::InvalidFormat(format!(
-                "Coop mode requires even key count, got {key_count}"
-            )));
+        if key_count == 0 || key_count > MAX_KEY_COUNT {
+            return Err(crate::RoxError::InvalidKeyCount {
+                key_count,
+                max: MAX_KEY_COUNT,
+            });
+        }
+        if self.metadata.is_coop {
+            match self.metadata.coop_split {
+                None if !key_count.is_multiple_of(2) => {
+                    return Err(crate::RoxError::InvalidFormat(format!(
+                        "Coop mode requires even key count, got {key_count}"
+                    )));
+                }
+                Some(split) if split == 0 || split >= key_count => {
+                    return Err(crate::RoxError::InvalidFormat(format!(
+                        "Coop split column {split} out of range for {key_count}K chart"
+                    )));
+                }
+                _ => {}
+            }
         }
 
         // 2. Check timing points sorted by time
@@ -109,11 +224,11 @@ impl RoxChart {
         }
 
         // 3. Single pass validation for notes O(N)
-        // We track the last end time for each column to detect overlaps.
+        // We track the last note interval `[start, end)` for each column to detect overlaps.
         // This requires notes to be sorted globally by time, or at least per column.
         // The previous implementation sorted per-column. Here we assume global sort or sorted-per-column input.
         // However, to strictly guarantee O(N) overlap checks without allocation, we track per-column state.
-        let mut last_end_times = vec![i64::MIN; key_count as usize];
+        let mut last_intervals = vec![(i64::MIN, i64::MIN); key_count as usize];
 
         // We verify that notes are strictly sorted by time overall.
         // If they are not, `validate` fails. This enforces strict ordering.
@@ -147,30 +262,158 @@ impl RoxChart {
             }
 
             // 3d. Check overlaps on specific column
+            // A note overlaps the previous one if it starts before that note's end,
+            // or at the same time (a zero-length tap never starts "before" an end,
+            // so a tap and a hold starting together must be caught explicitly).
+            // A note may start exactly where a hold ends. Starting inside a
+            // hold is reported as the hold being too long rather than as a
+            // stack. Fakes are never hit, so they may sit on top of real notes.
+            if note.fake {
+                continue;
+            }
             let col_idx = note.column as usize;
-            if note.time_us < last_end_times[col_idx] {
-                // Overlap detected!
+            let (last_start, last_end) = last_intervals[col_idx];
+            if note.time_us > last_start && note.time_us < last_end {
+                // Starts after the previous note, so it's that note's tail
+                return Err(crate::RoxError::HoldTooLong {
+                    column: note.column,
+                    start_us: last_start,
+                    overlaps_at_us: note.time_us,
+                });
+            }
+            if note.time_us == last_start {
                 return Err(crate::RoxError::OverlappingNotes {
                     column: note.column,
                     time_us: note.time_us,
                 });
             }
-            last_end_times[col_idx] = note.end_time_us();
+            last_intervals[col_idx] = (note.time_us, note.end_time_us());
         }
 
         Ok(())
     }
+
+    /// Check whether the chart passes [`validate`](Self::validate).
+    ///
+    /// Convenience for callers that only need a yes/no answer (e.g. graying out
+    /// invalid charts in a list) and don't care about the specific error.
+    #[must_use]
+    pub fn is_valid(&self) -> bool {
+        self.validate().is_ok()
+    }
+
+    /// Check that notes and timing points are both sorted by time.
+    ///
+    /// Beat conversion, analysis windows and the ROX delta encoding all rely
+    /// on this ordering; [`validate`](Self::validate) rejects unsorted charts.
+    #[must_use]
+    pub fn is_sorted(&self) -> bool {
+        self.notes.is_sorted_by_key(|n| n.time_us)
+            && self.timing_points.is_sorted_by_key(|tp| tp.time_us)
+    }
+
+    /// Append `other` to the end of this chart.
+    ///
+    /// All of `other`'s notes and timing points are shifted to start
+    /// `gap_us` after this chart's last note ends. Its hitsound samples are
+    /// appended to [`hitsounds`](Self::hitsounds) and the notes' hitsound
+    /// indices are rebased accordingly. Metadata of `self` is kept as-is.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`RoxError::KeyCountMismatch`](crate::RoxError::KeyCountMismatch)
+    /// if the key counts differ, or an error if the merged sample list would
+    /// no longer be addressable by a `u16` index.
+    pub fn append(&mut self, other: &RoxChart, gap_us: i64) -> Result<(), crate::RoxError> {
+        if other.key_count() != self.key_count() {
+            return Err(crate::RoxError::KeyCountMismatch {
+                expected: self.key_count(),
+                found: other.key_count(),
+            });
+        }
+
+        // Rebased indices must still fit in a `u16`
+        let merged = self.hitsounds.len() + other.hitsounds.len();
+        let base = match u16::try_from(self.hitsounds.len()) {
+            Ok(base) if merged <= usize::from(u16::MAX) + 1 => base,
+            _ => {
+                return Err(crate::RoxError::InvalidFormat(format!(
+                    "Too many hitsound samples to merge ({merged})"
+                )));
+            }
+        };
+
+        let offset = self.duration_us() + gap_us;
+
+        // Built first so `self` is untouched if an index overflows
+        let notes = other
+            .notes
+            .iter()
+            .map(|note| {
+                Ok(Note {
+                    time_us: note.time_us + offset,
+                    hitsound_index: rebase_hitsound_index(note.hitsound_index, base)?,
+                    tail_hitsound_index: rebase_hitsound_index(note.tail_hitsound_index, base)?,
+                    ..note.clone()
+                })
+            })
+            .collect::<Result<Vec<_>, crate::RoxError>>()?;
+
+        self.timing_points
+            .extend(other.timing_points.iter().map(|tp| TimingPoint {
+                time_us: tp.time_us + offset,
+                ..tp.clone()
+            }));
+        self.notes.extend(notes);
+        self.hitsounds.extend(other.hitsounds.iter().cloned());
+
+        // Stable sorts: only needed if `self` had timing points or notes past its end
+        self.timing_points.sort_by_key(|tp| tp.time_us);
+        self.notes.sort_by_key(|n| n.time_us);
+
+        Ok(())
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_notes_by_column() {
+        let mut chart = RoxChart::new(4);
+        chart.notes.push(Note::tap(0, 0));
+        chart.notes.push(Note::hold(0, 500, 2));
+        chart.notes.push(Note::tap(100, 0));
+        chart.notes.push(Note::tap(200, 3));
+        chart.notes.push(Note::tap(700, 2));
+        chart.notes.push(Note::tap(300, 0));
+
+        let columns = chart.notes_by_column();
+
+        assert_eq!(columns.len(), 4);
+        let times: Vec<Vec<i64>> = columns
+            .iter()
+            .map(|col| col.iter().map(|n| n.time_us).collect())
+            .collect();
+        assert_eq!(
+            times,
+            vec![vec![0, 100, 300], vec![], vec![0, 700], vec![200]]
+        );
+        assert!(
+            columns
+                .iter()
+                .enumerate()
+                .all(|(c, notes)| { notes.iter().all(|n| n.column as usize == c) })
+        );
+        assert!(columns[2][0].is_hold());
+    }
+
     #[test]
     fn test_rox_chart_new() {
         let chart = RoxChart::new(4);
 
-        assert_eq!(chart.version, 2);
+        assert_eq!(chart.version, 3);
         assert_eq!(chart.key_count(), 4);
         assert!(chart.timing_points.is_empty());
         assert!(chart.notes.is_empty());
@@ -183,6 +426,43 @@ mod tests {
         assert_eq!(chart.key_count(), 7);
     }
 
+    #[test]
+    fn test_rox_chart_clone_empty() {
+        let mut chart = RoxChart::new(7);
+        chart.metadata.title = "Variant".into();
+        chart.timing_points.push(TimingPoint::bpm(0, 150.0));
+        chart
+            .hitsounds
+            .push(crate::model::Hitsound::new("kick.wav"));
+        chart.notes.push(Note::tap(0, 3));
+
+        let empty = chart.clone_empty();
+        assert!(empty.notes.is_empty());
+        assert_eq!(empty.key_count(), 7);
+        assert_eq!(empty.metadata, chart.metadata);
+        assert_eq!(empty.timing_points, chart.timing_points);
+        assert_eq!(empty.hitsounds, chart.hitsounds);
+    }
+
+    #[test]
+    fn test_rox_chart_set_key_count() {
+        let mut chart = RoxChart::new(7);
+        chart.timing_points.push(TimingPoint::bpm(0, 120.0));
+        chart.notes.push(Note::tap(0, 6));
+        assert!(chart.is_valid());
+
+        chart.set_key_count(4);
+        assert_eq!(chart.key_count(), 4);
+        assert_eq!(chart.metadata.key_count, 4);
+        assert!(matches!(
+            chart.validate(),
+            Err(crate::RoxError::InvalidColumn {
+                column: 6,
+                key_count: 4
+            })
+        ));
+    }
+
     #[test]
     fn test_rox_chart_duration_empty() {
         let chart = RoxChart::new(4);
@@ -209,6 +489,46 @@ mod tests {
         chart.notes.push(Note::mine(2_000_000, 2));
 
         assert_eq!(chart.note_count(), 3);
+
+        let mut fake = Note::tap(3_000_000, 3);
+        fake.fake = true;
+        chart.notes.push(fake);
+        assert_eq!(chart.note_count(), 3);
+        assert_eq!(chart.note_count(), chart.note_counts().total());
+    }
+
+    #[test]
+    fn test_rox_chart_hold_releases() {
+        let mut chart = RoxChart::new(4);
+        chart.notes.push(Note::hold(1_000_000, 2_000_000, 0)); // releases at 3s
+        chart.notes.push(Note::tap(1_500_000, 1));
+        chart.notes.push(Note::hold(2_000_000, 500_000, 3)); // releases at 2.5s
+        chart.notes.push(Note::burst(2_000_000, 1_000_000, 2)); // releases at 3s
+        chart.notes.push(Note::mine(2_200_000, 1));
+
+        assert_eq!(
+            chart.hold_releases(),
+            vec![(2_500_000, 3), (3_000_000, 0), (3_000_000, 2)]
+        );
+    }
+
+    #[test]
+    fn test_rox_chart_rows() {
+        let mut chart = RoxChart::new(4);
+        chart.notes.push(Note::tap(0, 0));
+        chart.notes.push(Note::tap(500_000, 1)); // jump
+        chart.notes.push(Note::hold(500_000, 250_000, 3));
+        chart.notes.push(Note::tap(1_000_000, 2));
+
+        let rows: Vec<(i64, Vec<u8>)> = chart
+            .rows()
+            .map(|(time, row)| (time, row.iter().map(|n| n.column).collect()))
+            .collect();
+        assert_eq!(
+            rows,
+            vec![(0, vec![0]), (500_000, vec![1, 3]), (1_000_000, vec![2])]
+        );
+        assert_eq!(RoxChart::new(4).rows().count(), 0);
     }
 
     #[test]
@@ -231,4 +551,177 @@ mod tests {
 
         assert!(chart.validate().is_err());
     }
+
+    #[test]
+    fn test_rox_chart_validate_overlaps() {
+        let chart_with = |notes: &[Note]| {
+            let mut chart = RoxChart::new(4);
+            chart.timing_points.push(TimingPoint::bpm(0, 120.0));
+            chart.notes.extend_from_slice(notes);
+            chart
+        };
+        let overlaps = |notes: &[Note]| {
+            matches!(
+                chart_with(notes).validate(),
+                Err(crate::RoxError::OverlappingNotes { column: 2, .. })
+            )
+        };
+
+        // A note strictly inside a hold means the hold is too long
+        assert!(matches!(
+            chart_with(&[Note::hold(1_000_000, 1_000_000, 2), Note::tap(1_500_000, 2)]).validate(),
+            Err(crate::RoxError::HoldTooLong {
+                column: 2,
+                start_us: 1_000_000,
+                overlaps_at_us: 1_500_000
+            })
+        ));
+        assert!(matches!(
+            chart_with(&[
+                Note::burst(1_000_000, 1_000_000, 2),
+                Note::hold(1_999_999, 500_000, 2)
+            ])
+            .validate(),
+            Err(crate::RoxError::HoldTooLong { column: 2, .. })
+        ));
+        // Tap and hold starting together, in either order
+        assert!(overlaps(&[
+            Note::hold(1_000_000, 1_000_000, 2),
+            Note::tap(1_000_000, 2)
+        ]));
+        assert!(overlaps(&[
+            Note::tap(1_000_000, 2),
+            Note::hold(1_000_000, 1_000_000, 2)
+        ]));
+        // Two taps at the same time
+        assert!(overlaps(&[
+            Note::tap(1_000_000, 2),
+            Note::tap(1_000_000, 2)
+        ]));
+
+        // A tap right at the hold's end is fine, as is another column
+        assert!(
+            chart_with(&[
+                Note::hold(1_000_000, 1_000_000, 2),
+                Note::tap(1_500_000, 1),
+                Note::tap(2_000_000, 2),
+            ])
+            .validate()
+            .is_ok()
+        );
+
+        // Fakes are never judged, so they may share a slot with a real note
+        let fake = Note {
+            fake: true,
+            ..Note::tap(1_000_000, 2)
+        };
+        assert!(
+            chart_with(&[Note::tap(1_000_000, 2), fake])
+                .validate()
+                .is_ok()
+        );
+    }
+
+    #[test]
+    fn test_rox_chart_validate_key_count() {
+        for key_count in [0, MAX_KEY_COUNT + 1, 200] {
+            let chart = RoxChart::new(key_count);
+            assert!(matches!(
+                chart.validate(),
+                Err(crate::RoxError::InvalidKeyCount { key_count: k, .. }) if k == key_count
+            ));
+        }
+        assert!(RoxChart::new(1).validate().is_ok());
+        assert!(RoxChart::new(MAX_KEY_COUNT).validate().is_ok());
+    }
+
+    #[test]
+    fn test_rox_chart_append() {
+        let mut first = RoxChart::new(4);
+        first.timing_points.push(TimingPoint::bpm(0, 120.0));
+        first.hitsounds.push(Hitsound::new("a.wav"));
+        first.hitsounds.push(Hitsound::new("b.wav"));
+        let mut note = Note::hold(0, 1_000_000, 0);
+        note.hitsound_index = Some(1);
+        first.notes.push(note);
+
+        let mut second = RoxChart::new(4);
+        second.timing_points.push(TimingPoint::bpm(0, 180.0));
+        second.hitsounds.push(Hitsound::new("c.wav"));
+        let mut note = Note::tap(0, 2);
+        note.hitsound_index = Some(0);
+        second.notes.push(note);
+        second.notes.push(Note::tap(500_000, 3));
+
+        first.append(&second, 250_000).unwrap();
+
+        assert_eq!(first.notes.len(), 3);
+        assert_eq!(first.hitsounds.len(), 3);
+        assert_eq!(first.timing_points[1].time_us, 1_250_000);
+
+        let appended = &first.notes[1];
+        assert_eq!(appended.time_us, 1_250_000);
+        assert_eq!(appended.hitsound_index, Some(2));
+        let sample = &first.hitsounds[appended.hitsound_index.unwrap() as usize];
+        assert_eq!(sample.file, "c.wav");
+        assert_eq!(first.notes[0].hitsound_index, Some(1));
+        assert_eq!(first.notes[2].hitsound_index, None);
+        assert!(first.is_valid());
+    }
+
+    #[test]
+    fn test_rox_chart_append_hitsound_index_overflow() {
+        let mut first = RoxChart::new(4);
+        first.hitsounds.push(Hitsound::new("a.wav"));
+        first.notes.push(Note::tap(0, 0));
+        let before = first.clone();
+
+        let mut second = RoxChart::new(4);
+        let mut note = Note::tap(0, 1);
+        note.hitsound_index = Some(u16::MAX);
+        second.notes.push(note);
+
+        assert!(first.append(&second, 0).is_err());
+        assert_eq!(first, before);
+    }
+
+    #[test]
+    fn test_rox_chart_append_key_count_mismatch() {
+        let mut chart = RoxChart::new(4);
+        let err = chart.append(&RoxChart::new(7), 0).unwrap_err();
+        assert!(matches!(
+            err,
+            crate::RoxError::KeyCountMismatch {
+                expected: 4,
+                found: 7
+            }
+        ));
+    }
+
+    #[test]
+    fn test_rox_chart_is_valid() {
+        let mut chart = RoxChart::new(4);
+        chart.timing_points.push(TimingPoint::bpm(0, 120.0));
+        chart.notes.push(Note::tap(0, 3));
+        assert!(chart.is_valid());
+
+        chart.notes.push(Note::tap(500_000, 4)); // Out of range for 4K
+        assert!(!chart.is_valid());
+    }
+
+    #[test]
+    fn test_rox_chart_is_sorted() {
+        let mut chart = RoxChart::new(4);
+        chart.timing_points.push(TimingPoint::bpm(0, 120.0));
+        chart.notes.push(Note::tap(0, 0));
+        chart.notes.push(Note::tap(0, 1));
+        assert!(chart.is_sorted());
+
+        chart.notes.push(Note::tap(-1, 2));
+        assert!(!chart.is_sorted());
+        chart.notes.pop();
+
+        chart.timing_points.push(TimingPoint::sv(-1, 1.0));
+        assert!(!chart.is_sorted());
+    }
 }
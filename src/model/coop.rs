@@ -0,0 +1,203 @@
+//! Splitting and merging 2-player coop charts.
+
+use super::hitsound::rebase_hitsound_index;
+use super::{MAX_KEY_COUNT, RoxChart};
+use crate::error::{RoxError, RoxResult};
+
+impl RoxChart {
+    /// First column of the second player, or `None` if the chart is not coop.
+    ///
+    /// Uses [`Metadata::coop_split`](super::Metadata::coop_split), falling
+    /// back to an even split at `key_count / 2`. A split that would leave a
+    /// player without columns (e.g. from an unvalidated file) also gives
+    /// `None`, so callers can index both sides safely.
+    #[must_use]
+    pub fn coop_split(&self) -> Option<u8> {
+        if !self.metadata.is_coop {
+            return None;
+        }
+        let split = self.metadata.coop_split.unwrap_or(self.key_count() / 2);
+        (split > 0 && split < self.key_count()).then_some(split)
+    }
+
+    /// Split a coop chart into one single-player chart per side.
+    ///
+    /// Both halves keep the metadata, timing points and hitsound table; the
+    /// second player's columns are renumbered from 0. Returns `None` if the
+    /// chart is not coop.
+    #[must_use]
+    pub fn split_coop(&self) -> Option<(RoxChart, RoxChart)> {
+        let split = self.coop_split()?;
+        let side = |columns: std::ops::Range<u8>| {
+            let mut chart = self.clone();
+            chart.metadata.key_count = columns.end - columns.start;
+            chart.metadata.is_coop = false;
+            chart.metadata.coop_split = None;
+            chart.notes.retain(|n| columns.contains(&n.column));
+            for note in &mut chart.notes {
+                note.column -= columns.start;
+            }
+            chart
+        };
+        Some((side(0..split), side(split..self.key_count())))
+    }
+
+    /// Merge two single-player charts into a coop chart, `p1` on the left.
+    ///
+    /// Metadata and timing points come from `p1`. `p2`'s hitsounds are
+    /// appended and its notes re-indexed. Uneven key counts are recorded in
+    /// [`Metadata::coop_split`](super::Metadata::coop_split).
+    ///
+    /// # Errors
+    ///
+    /// Returns [`RoxError::InvalidKeyCount`] if the combined key count is
+    /// above [`MAX_KEY_COUNT`], or [`RoxError::InvalidFormat`] if `p2`'s
+    /// rebased hitsound indices no longer fit in a `u16`.
+    pub fn merge_coop(p1: &RoxChart, p2: &RoxChart) -> RoxResult<RoxChart> {
+        let split = p1.key_count();
+        let total = u16::from(split) + u16::from(p2.key_count());
+        if total > u16::from(MAX_KEY_COUNT) {
+            return Err(RoxError::InvalidKeyCount {
+                key_count: u8::try_from(total).unwrap_or(u8::MAX),
+                max: MAX_KEY_COUNT,
+            });
+        }
+        // Checked against MAX_KEY_COUNT above
+        #[allow(clippy::cast_possible_truncation)]
+        let key_count = total as u8;
+
+        // Same limit as the ROX format's u16 hitsound indices
+        let base = u16::try_from(p1.hitsounds.len()).map_err(|_| {
+            RoxError::InvalidFormat(format!(
+                "Too many hitsound samples to merge ({})",
+                p1.hitsounds.len() + p2.hitsounds.len()
+            ))
+        })?;
+        let p2_notes = p2
+            .notes
+            .iter()
+            .map(|note| {
+                let mut note = note.clone();
+                note.column += split;
+                note.hitsound_index = rebase_hitsound_index(note.hitsound_index, base)?;
+                note.tail_hitsound_index = rebase_hitsound_index(note.tail_hitsound_index, base)?;
+                Ok(note)
+            })
+            .collect::<RoxResult<Vec<_>>>()?;
+
+        let mut chart = p1.clone();
+        chart.metadata.key_count = key_count;
+        chart.metadata.is_coop = true;
+        chart.metadata.coop_split = (split * 2 != key_count).then_some(split);
+        chart.notes.extend(p2_notes);
+        chart.hitsounds.extend(p2.hitsounds.iter().cloned());
+        chart.notes.sort_by_key(|n| n.time_us);
+        Ok(chart)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::RoxError;
+    use crate::model::{Hitsound, Note, RoxChart, TimingPoint};
+
+    fn coop_7k() -> RoxChart {
+        let mut chart = RoxChart::new(7);
+        chart.metadata.is_coop = true;
+        chart.metadata.coop_split = Some(4);
+        chart.timing_points.push(TimingPoint::bpm(0, 120.0));
+        for column in 0..7 {
+            chart
+                .notes
+                .push(Note::tap(i64::from(column) * 100_000, column));
+        }
+        chart
+    }
+
+    #[test]
+    fn test_split_asymmetric_coop() {
+        let chart = coop_7k();
+        assert!(chart.is_valid());
+        assert_eq!(chart.coop_split(), Some(4));
+
+        let (p1, p2) = chart.split_coop().unwrap();
+        assert_eq!(p1.key_count(), 4);
+        assert_eq!(p2.key_count(), 3);
+        assert!(!p1.metadata.is_coop && !p2.metadata.is_coop);
+        assert!(p1.is_valid() && p2.is_valid());
+
+        let columns = |c: &RoxChart| c.notes.iter().map(|n| n.column).collect::<Vec<_>>();
+        assert_eq!(columns(&p1), vec![0, 1, 2, 3]);
+        assert_eq!(columns(&p2), vec![0, 1, 2]);
+        assert_eq!(p2.notes[0].time_us, 400_000);
+
+        assert_eq!(RoxChart::merge_coop(&p1, &p2).unwrap(), chart);
+    }
+
+    #[test]
+    fn test_out_of_range_split_is_ignored() {
+        for split in [0, 7, 9] {
+            let mut chart = coop_7k();
+            chart.metadata.coop_split = Some(split);
+            assert!(!chart.is_valid());
+            assert_eq!(chart.coop_split(), None);
+            assert!(chart.split_coop().is_none());
+
+            // Decoded files are not validated; shuffling must not panic
+            chart.shuffle_columns(1);
+            chart.shuffle_columns_per_measure(1);
+            assert_eq!(chart.notes.len(), 7);
+        }
+    }
+
+    #[test]
+    fn test_split_default_is_even() {
+        let mut chart = RoxChart::new(8);
+        assert!(chart.split_coop().is_none());
+
+        chart.metadata.is_coop = true;
+        assert_eq!(chart.coop_split(), Some(4));
+        let (p1, p2) = chart.split_coop().unwrap();
+        assert_eq!((p1.key_count(), p2.key_count()), (4, 4));
+        assert_eq!(
+            RoxChart::merge_coop(&p1, &p2).unwrap().metadata.coop_split,
+            None
+        );
+    }
+
+    #[test]
+    fn test_validate_coop_split_range() {
+        let mut chart = coop_7k();
+        for split in [0, 7, 9] {
+            chart.metadata.coop_split = Some(split);
+            assert!(matches!(chart.validate(), Err(RoxError::InvalidFormat(_))));
+        }
+
+        chart.metadata.coop_split = None;
+        assert!(chart.validate().is_err(), "7K needs an explicit split");
+    }
+
+    #[test]
+    fn test_merge_hitsound_index_overflow() {
+        let mut p1 = RoxChart::new(4);
+        p1.hitsounds.push(Hitsound::new("a.wav"));
+        let mut p2 = RoxChart::new(4);
+        let mut note = Note::tap(0, 0);
+        note.hitsound_index = Some(u16::MAX);
+        p2.notes.push(note);
+
+        assert!(matches!(
+            RoxChart::merge_coop(&p1, &p2),
+            Err(RoxError::InvalidFormat(_))
+        ));
+    }
+
+    #[test]
+    fn test_merge_too_many_keys() {
+        let half = RoxChart::new(10);
+        assert!(matches!(
+            RoxChart::merge_coop(&half, &half),
+            Err(RoxError::InvalidKeyCount { .. })
+        ));
+    }
+}
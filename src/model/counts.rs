@@ -0,0 +1,99 @@
+//! Per-type note breakdown.
+
+use serde::{Deserialize as SerdeDeserialize, Serialize as SerdeSerialize};
+
+use super::{NoteType, RoxChart};
+
+/// Number of notes of each type in a chart.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, SerdeSerialize, SerdeDeserialize)]
+pub struct NoteCounts {
+    /// Regular tap notes.
+    pub taps: usize,
+    /// Hold (long) notes.
+    pub holds: usize,
+    /// Burst/roll notes.
+    pub bursts: usize,
+    /// Mines.
+    pub mines: usize,
+    /// Fake notes, whatever their type. Not part of [`total`](Self::total).
+    #[serde(default)]
+    pub fakes: usize,
+}
+
+impl NoteCounts {
+    /// Total number of judged notes across all types.
+    #[must_use]
+    pub fn total(&self) -> usize {
+        self.taps + self.holds + self.bursts + self.mines
+    }
+}
+
+impl RoxChart {
+    /// Count notes by type in a single pass.
+    #[must_use]
+    pub fn note_counts(&self) -> NoteCounts {
+        let mut counts = NoteCounts::default();
+        for note in &self.notes {
+            if note.fake {
+                counts.fakes += 1;
+                continue;
+            }
+            match note.note_type {
+                NoteType::Tap => counts.taps += 1,
+                NoteType::Hold { .. } => counts.holds += 1,
+                NoteType::Burst { .. } => counts.bursts += 1,
+                NoteType::Mine => counts.mines += 1,
+            }
+        }
+        counts
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model::Note;
+
+    #[test]
+    fn test_note_counts_one_of_each() {
+        let mut chart = RoxChart::new(4);
+        chart.notes.push(Note::tap(0, 0));
+        chart.notes.push(Note::hold(100_000, 500_000, 1));
+        chart.notes.push(Note::burst(200_000, 300_000, 2));
+        chart.notes.push(Note::mine(300_000, 3));
+
+        let counts = chart.note_counts();
+        assert_eq!(
+            counts,
+            NoteCounts {
+                taps: 1,
+                holds: 1,
+                bursts: 1,
+                mines: 1,
+                fakes: 0,
+            }
+        );
+        assert_eq!(counts.total(), chart.note_count());
+    }
+
+    #[test]
+    fn test_note_counts_fakes() {
+        let mut chart = RoxChart::new(4);
+        chart.notes.push(Note::tap(0, 0));
+        chart.notes.push(Note {
+            fake: true,
+            ..Note::tap(0, 1)
+        });
+
+        let counts = chart.note_counts();
+        assert_eq!(counts.taps, 1);
+        assert_eq!(counts.fakes, 1);
+        assert_eq!(counts.total(), 1);
+    }
+
+    #[test]
+    fn test_note_counts_empty() {
+        let chart = RoxChart::new(4);
+        assert_eq!(chart.note_counts(), NoteCounts::default());
+    }
+}
@@ -0,0 +1,283 @@
+//! In-place note filters (practice / beginner variants).
+
+use super::{Note, NoteType, RoxChart};
+
+impl RoxChart {
+    /// Keep only the notes for which `f` returns `true`.
+    ///
+    /// Order is preserved and the hitsound table is left untouched.
+    pub fn retain_notes<F: Fn(&Note) -> bool>(&mut self, f: F) {
+        self.notes.retain(f);
+    }
+
+    /// Remove all mines.
+    pub fn remove_mines(&mut self) {
+        self.retain_notes(|note| !note.is_mine());
+    }
+
+    /// Remove notes that start before `time_us`, e.g. to delete an intro.
+    ///
+    /// Times are not shifted. A hold or burst still running at `time_us` is
+    /// cut to start there, and loses its head hitsound since that no longer
+    /// lines up with anything.
+    pub fn remove_notes_before(&mut self, time_us: i64) {
+        self.notes.retain_mut(|note| {
+            if note.time_us >= time_us {
+                return true;
+            }
+            let end_us = note.end_time_us();
+            if end_us <= time_us {
+                return false;
+            }
+            set_duration(note, end_us - time_us);
+            note.time_us = time_us;
+            note.hitsound_index = None;
+            true
+        });
+    }
+
+    /// Remove notes that start after `time_us`, e.g. to delete an outro.
+    ///
+    /// A hold or burst still running at `time_us` is cut to end there (or
+    /// becomes a tap if it starts exactly at `time_us`) and loses its tail
+    /// hitsound.
+    pub fn remove_notes_after(&mut self, time_us: i64) {
+        self.notes.retain_mut(|note| {
+            if note.time_us > time_us {
+                return false;
+            }
+            if note.end_time_us() > time_us {
+                if note.time_us == time_us {
+                    note.note_type = NoteType::Tap;
+                } else {
+                    set_duration(note, time_us - note.time_us);
+                }
+                note.tail_hitsound_index = None;
+            }
+            true
+        });
+    }
+
+    /// Turn every hold and burst into a tap at its start time.
+    ///
+    /// Unlike [`retain_notes`](Self::retain_notes), nothing is deleted: the
+    /// note keeps its time, column and hitsound. The tail hitsound goes
+    /// with the tail.
+    pub fn remove_holds(&mut self) {
+        for note in &mut self.notes {
+            if note.is_hold() || note.is_burst() {
+                note.note_type = NoteType::Tap;
+                note.tail_hitsound_index = None;
+            }
+        }
+    }
+
+    /// Remove notes that duplicate an earlier note at the same time,
+    /// returning how many were removed.
+    ///
+    /// With `gameplay_only`, notes are duplicates if they play the same
+    /// ([`Note::same_gameplay`]) even when their hitsounds differ; otherwise
+    /// they must be fully equal, so keysound variants are kept. The first
+    /// note of each group wins. Notes must be sorted by time.
+    pub fn dedup_notes(&mut self, gameplay_only: bool) -> usize {
+        let before = self.notes.len();
+        let mut kept: Vec<Note> = Vec::with_capacity(before);
+        // Index in `kept` of the first note at the current time
+        let mut group_start = 0;
+
+        for note in self.notes.drain(..) {
+            if kept.last().is_some_and(|last| last.time_us != note.time_us) {
+                group_start = kept.len();
+            }
+            let is_duplicate = kept[group_start..].iter().any(|other| {
+                if gameplay_only {
+                    other.same_gameplay(&note)
+                } else {
+                    *other == note
+                }
+            });
+            if !is_duplicate {
+                kept.push(note);
+            }
+        }
+
+        self.notes = kept;
+        before - self.notes.len()
+    }
+
+    /// Merge flams: notes in the same column within `window_us` of the
+    /// previous kept note are removed, so the earlier note stands for the
+    /// whole group. Returns how many notes were removed.
+    ///
+    /// Meant for drum/taiko converts, where two hits a few milliseconds apart
+    /// are one accented hit. Unlike [`dedup_notes`](Self::dedup_notes) times
+    /// don't have to match exactly. Mines are left alone. Notes must be
+    /// sorted by time.
+    pub fn collapse_flams(&mut self, window_us: i64) -> usize {
+        let before = self.notes.len();
+        // Time of the last kept note in each column
+        let mut last_kept = [None::<i64>; 256];
+        self.notes.retain(|note| {
+            if note.is_mine() {
+                return true;
+            }
+            let last = &mut last_kept[usize::from(note.column)];
+            if last.is_some_and(|time| note.time_us - time <= window_us) {
+                return false;
+            }
+            *last = Some(note.time_us);
+            true
+        });
+        before - self.notes.len()
+    }
+
+    /// Reduce the chart to plain taps: mines are removed and holds/bursts
+    /// become taps.
+    pub fn taps_only(&mut self) {
+        self.remove_mines();
+        self.remove_holds();
+    }
+}
+
+/// Change the length of a hold or burst; other notes have none.
+fn set_duration(note: &mut Note, new_duration_us: i64) {
+    if let NoteType::Hold { duration_us } | NoteType::Burst { duration_us } = &mut note.note_type {
+        *duration_us = new_duration_us;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn mixed_chart() -> RoxChart {
+        let mut chart = RoxChart::new(4);
+        chart.notes.push(Note::tap(0, 0));
+        chart.notes.push(Note::hold(100_000, 200_000, 1));
+        chart.notes.push(Note::mine(150_000, 2));
+        chart.notes.push(Note::burst(200_000, 100_000, 3));
+        chart.notes.push(Note::tap(400_000, 2));
+        chart
+    }
+
+    #[test]
+    fn test_retain_notes() {
+        let mut chart = mixed_chart();
+        chart.retain_notes(|note| note.column < 2);
+        let columns: Vec<u8> = chart.notes.iter().map(|n| n.column).collect();
+        assert_eq!(columns, vec![0, 1]);
+    }
+
+    #[test]
+    fn test_remove_notes_before() {
+        let mut chart = mixed_chart();
+        chart.notes[1].hitsound_index = Some(0);
+        chart.notes[1].tail_hitsound_index = Some(1);
+        chart.remove_notes_before(150_000);
+
+        assert_eq!(chart.notes.len(), 4);
+        // The hold straddled the cut: same end, new start
+        assert_eq!(chart.notes[0].time_us, 150_000);
+        assert_eq!(chart.notes[0].end_time_us(), 300_000);
+        assert_eq!(chart.notes[0].hitsound_index, None);
+        assert_eq!(chart.notes[0].tail_hitsound_index, Some(1));
+        assert!(chart.notes[1].is_mine());
+
+        chart.remove_notes_before(300_000);
+        assert_eq!(chart.notes, vec![Note::tap(400_000, 2)]);
+    }
+
+    #[test]
+    fn test_remove_notes_after() {
+        let mut chart = mixed_chart();
+        chart.remove_notes_after(250_000);
+
+        assert_eq!(chart.notes.len(), 4);
+        assert_eq!(chart.notes[1], Note::hold(100_000, 150_000, 1));
+        assert_eq!(chart.notes[3], Note::burst(200_000, 50_000, 3));
+
+        // A hold starting right at the cut is left as a tap
+        chart.remove_notes_after(200_000);
+        assert_eq!(chart.notes[3], Note::tap(200_000, 3));
+        chart.remove_notes_after(-1);
+        assert!(chart.notes.is_empty());
+    }
+
+    #[test]
+    fn test_remove_mines() {
+        let mut chart = mixed_chart();
+        chart.remove_mines();
+        assert_eq!(chart.notes.len(), 4);
+        assert!(!chart.notes.iter().any(Note::is_mine));
+        assert!(chart.notes[1].is_hold());
+    }
+
+    #[test]
+    fn test_remove_holds() {
+        let mut chart = mixed_chart();
+        chart.notes[1].hitsound_index = Some(0);
+        chart.notes[1].tail_hitsound_index = Some(1);
+        chart.remove_holds();
+        assert_eq!(chart.notes.len(), 5);
+        assert_eq!(chart.notes[1].note_type, NoteType::Tap);
+        assert_eq!(chart.notes[1].time_us, 100_000);
+        assert_eq!(chart.notes[1].hitsound_index, Some(0));
+        assert_eq!(chart.notes[1].tail_hitsound_index, None);
+        assert_eq!(chart.notes[3].note_type, NoteType::Tap);
+        assert_eq!(chart.notes[3].time_us, 200_000);
+        assert!(chart.notes[2].is_mine());
+    }
+
+    #[test]
+    fn test_dedup_notes() {
+        let mut chart = mixed_chart();
+        let mut kick = Note::tap(400_000, 2);
+        kick.hitsound_index = Some(0);
+        chart.notes.push(kick.clone());
+        chart.notes.push(kick);
+        chart.notes.push(Note::tap(400_000, 3));
+
+        // Strict: only the exact copy of the keysounded note goes
+        let mut strict = chart.clone();
+        assert_eq!(strict.dedup_notes(false), 1);
+        assert_eq!(strict.notes.len(), 7);
+        assert_eq!(strict.notes[5].hitsound_index, Some(0));
+
+        // Gameplay: both keysound variants merge into the first note
+        assert_eq!(chart.dedup_notes(true), 2);
+        assert_eq!(chart.notes.len(), 6);
+        assert_eq!(chart.notes[4].hitsound_index, None);
+        assert_eq!(chart.notes[5].column, 3);
+    }
+
+    #[test]
+    fn test_collapse_flams() {
+        let mut chart = RoxChart::new(4);
+        chart.notes.push(Note::tap(1_000_000, 0));
+        chart.notes.push(Note::tap(1_002_000, 1));
+        chart.notes.push(Note::tap(1_005_000, 0));
+        chart.notes.push(Note::mine(1_006_000, 0));
+        chart.notes.push(Note::tap(1_020_000, 0));
+
+        assert_eq!(chart.collapse_flams(10_000), 1);
+        let kept: Vec<(i64, u8)> = chart.notes.iter().map(|n| (n.time_us, n.column)).collect();
+        assert_eq!(
+            kept,
+            vec![
+                (1_000_000, 0),
+                (1_002_000, 1),
+                (1_006_000, 0),
+                (1_020_000, 0)
+            ]
+        );
+        assert_eq!(chart.collapse_flams(10_000), 0);
+    }
+
+    #[test]
+    fn test_taps_only() {
+        let mut chart = mixed_chart();
+        chart.taps_only();
+        assert_eq!(chart.notes.len(), 4);
+        assert!(chart.notes.iter().all(|n| n.note_type == NoteType::Tap));
+    }
+}
@@ -4,6 +4,8 @@ use compact_str::CompactString;
 use rkyv::{Archive, Deserialize, Serialize};
 use serde::{Deserialize as SerdeDeserialize, Serialize as SerdeSerialize};
 
+use crate::error::{RoxError, RoxResult};
+
 /// A hitsound sample definition.
 #[derive(
     Debug, Clone, PartialEq, Eq, Archive, Serialize, Deserialize, SerdeSerialize, SerdeDeserialize,
@@ -35,6 +37,22 @@ impl Hitsound {
     }
 }
 
+/// Shift a note's hitsound index by `base`, for appending one chart's
+/// samples after another's.
+///
+/// Fails instead of wrapping when the index no longer fits in a `u16`.
+pub(crate) fn rebase_hitsound_index(index: Option<u16>, base: u16) -> RoxResult<Option<u16>> {
+    index
+        .map(|i| {
+            i.checked_add(base).ok_or_else(|| {
+                RoxError::InvalidFormat(format!(
+                    "Hitsound index {i} + {base} does not fit in a u16"
+                ))
+            })
+        })
+        .transpose()
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
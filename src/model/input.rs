@@ -0,0 +1,92 @@
+//! Key-down/key-up timeline for input simulation (autoplay, replays).
+
+use serde::{Deserialize as SerdeDeserialize, Serialize as SerdeSerialize};
+
+use super::RoxChart;
+
+/// A key changing state in one column.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, SerdeSerialize, SerdeDeserialize)]
+pub struct InputEvent {
+    /// Time of the event in microseconds.
+    pub time_us: i64,
+    /// Column whose key changes state.
+    pub column: u8,
+    /// `true` for a key press, `false` for a release.
+    pub down: bool,
+}
+
+impl RoxChart {
+    /// Key presses and releases that play this chart perfectly, sorted by
+    /// time.
+    ///
+    /// Taps press and release at the same instant; use
+    /// [`input_timeline_with_tap_duration`](Self::input_timeline_with_tap_duration)
+    /// to hold them down briefly.
+    #[must_use]
+    pub fn input_timeline(&self) -> Vec<InputEvent> {
+        self.input_timeline_with_tap_duration(0)
+    }
+
+    /// Like [`input_timeline`](Self::input_timeline), with taps released
+    /// `tap_duration_us` after they are pressed.
+    ///
+    /// Holds and bursts are pressed at their start and released at their end.
+    /// Mines and fakes are never pressed. At equal times events keep note
+    /// order, so a press always comes before its own release.
+    #[must_use]
+    pub fn input_timeline_with_tap_duration(&self, tap_duration_us: i64) -> Vec<InputEvent> {
+        let mut events = Vec::with_capacity(self.notes.len() * 2);
+        for note in self.notes.iter().filter(|n| !n.is_mine() && !n.fake) {
+            let release_us = if note.is_hold() || note.is_burst() {
+                note.end_time_us()
+            } else {
+                note.time_us + tap_duration_us.max(0)
+            };
+            events.push(InputEvent {
+                time_us: note.time_us,
+                column: note.column,
+                down: true,
+            });
+            events.push(InputEvent {
+                time_us: release_us,
+                column: note.column,
+                down: false,
+            });
+        }
+        // Stable, so same-time events stay in note order
+        events.sort_by_key(|e| e.time_us);
+        events
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model::Note;
+
+    #[test]
+    fn test_input_timeline() {
+        let mut chart = RoxChart::new(4);
+        chart.notes.push(Note::hold(1_000, 5_000, 2));
+        chart.notes.push(Note::tap(2_000, 0));
+        chart.notes.push(Note::mine(3_000, 1));
+
+        let event = |time_us, column, down| InputEvent {
+            time_us,
+            column,
+            down,
+        };
+        assert_eq!(
+            chart.input_timeline(),
+            vec![
+                event(1_000, 2, true),
+                event(2_000, 0, true),
+                event(2_000, 0, false),
+                event(6_000, 2, false),
+            ]
+        );
+
+        let timeline = chart.input_timeline_with_tap_duration(500);
+        assert_eq!(timeline[2], event(2_500, 0, false));
+    }
+}
@@ -0,0 +1,130 @@
+//! Seeded timing jitter for generating test charts.
+//!
+//! Like the column shuffles, offsets come from `rand`'s `StdRng`, so a seed
+//! reproduces the same chart for a given version of this crate.
+
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+
+use super::RoxChart;
+
+impl RoxChart {
+    /// Shift every note by a pseudo-random offset in `[-max_us, max_us]`
+    /// derived from `seed`, for exercising judgment windows.
+    ///
+    /// Shifts are clamped so a note never reaches the previous note on its
+    /// column (or the inside of its hold) and a hold never runs into the next
+    /// one, so a valid chart stays valid. Hold durations are kept and notes
+    /// are re-sorted by time afterwards. A `max_us` of 0 or less does nothing.
+    pub fn jitter(&mut self, max_us: i64, seed: u64) {
+        if max_us <= 0 || self.notes.is_empty() {
+            return;
+        }
+
+        let mut rng = StdRng::seed_from_u64(seed);
+        let columns = self
+            .notes
+            .iter()
+            .map(|n| n.column)
+            .max()
+            .map_or(0, |c| c as usize + 1);
+        // Indices of each column's notes, in time order
+        let mut by_column = vec![Vec::new(); columns];
+        let mut order: Vec<usize> = (0..self.notes.len()).collect();
+        order.sort_by_key(|&i| self.notes[i].time_us);
+        for i in order {
+            by_column[self.notes[i].column as usize].push(i);
+        }
+
+        for indices in by_column {
+            // (start, end) of the previous note after it was moved
+            let mut previous = (i64::MIN, i64::MIN);
+            for (k, &i) in indices.iter().enumerate() {
+                let note = &self.notes[i];
+                let time = note.time_us;
+                let duration = note.end_time_us() - time;
+
+                // Stay past the previous note, and end before the next note's
+                // original start so it can always keep its own position
+                let low = previous.1.max(previous.0.saturating_add(1));
+                let high = indices.get(k + 1).map_or(i64::MAX, |&next| {
+                    self.notes[next].time_us.saturating_sub(duration.max(1))
+                });
+                let low = low.max(time.saturating_sub(max_us)).min(time);
+                let high = high.min(time.saturating_add(max_us)).max(time);
+
+                let offset = rng.random_range(-max_us..=max_us);
+                let shifted = time.saturating_add(offset).clamp(low, high);
+
+                let note = &mut self.notes[i];
+                note.time_us = shifted;
+                previous = (shifted, note.end_time_us());
+            }
+        }
+
+        self.notes.sort_by_key(|n| n.time_us);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model::{Note, TimingPoint};
+
+    fn dense_chart() -> RoxChart {
+        let mut chart = RoxChart::new(4);
+        chart.timing_points.push(TimingPoint::bpm(0, 180.0));
+        for i in 0..200 {
+            let time = 1_000_000 + i * 20_000;
+            let column = (i % 4) as u8;
+            if i % 7 == 0 {
+                chart.notes.push(Note::hold(time, 70_000, column));
+            } else {
+                chart.notes.push(Note::tap(time, column));
+            }
+        }
+        chart
+    }
+
+    #[test]
+    fn test_jitter_zero_is_noop() {
+        let original = dense_chart();
+        let mut chart = original.clone();
+        chart.jitter(0, 42);
+        assert_eq!(chart, original);
+    }
+
+    #[test]
+    fn test_jitter_same_seed_same_output() {
+        let mut a = dense_chart();
+        let mut b = dense_chart();
+        a.jitter(15_000, 42);
+        b.jitter(15_000, 42);
+        assert_eq!(a, b);
+        assert_ne!(a, dense_chart());
+    }
+
+    #[test]
+    fn test_jitter_stays_in_range_and_valid() {
+        let original = dense_chart();
+        for seed in 0..16 {
+            let mut chart = original.clone();
+            // Wider than the gap between notes on a column
+            chart.jitter(100_000, seed);
+
+            assert!(chart.is_valid(), "seed {seed} produced an invalid chart");
+            assert!(chart.notes.is_sorted_by_key(|n| n.time_us));
+            let columns = chart.notes_by_column();
+            for (column, notes) in original.notes_by_column().iter().enumerate() {
+                assert_eq!(notes.len(), columns[column].len());
+                for (before, after) in notes.iter().zip(&columns[column]) {
+                    assert!((after.time_us - before.time_us).abs() <= 100_000);
+                    assert_eq!(
+                        after.end_time_us() - after.time_us,
+                        before.end_time_us() - before.time_us
+                    );
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,202 @@
+//! Measure (bar) boundaries derived from BPM timing points and signatures.
+
+use super::RoxChart;
+use super::beat::{beat_to_us, us_to_beat};
+
+/// Beats per measure when a timing point carries no signature.
+const DEFAULT_SIGNATURE: u8 = 4;
+
+/// Tolerance (in beats) for a measure ending exactly on a timing point.
+const BEAT_EPSILON: f64 = 1e-6;
+
+/// One measure (bar) of a chart.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Measure {
+    /// Start of the measure in microseconds.
+    pub start_us: i64,
+    /// End of the measure (start of the next one) in microseconds.
+    pub end_us: i64,
+    /// Length in beats. Equals the signature, except for a measure cut short
+    /// by the next BPM timing point.
+    pub beats: f64,
+}
+
+impl RoxChart {
+    /// Iterate over the measures of the chart, honoring each BPM timing
+    /// point's `signature`.
+    ///
+    /// Measures start at the first BPM point and every BPM point starts a new
+    /// measure (like osu! barlines), so a measure can be cut short by the next
+    /// point. Inherited (SV) points are ignored. Iteration stops once the end
+    /// of the last note is covered; a chart without notes has no measures.
+    pub fn measures(&self) -> impl Iterator<Item = Measure> + '_ {
+        let timing = &self.timing_points;
+        let end_beat = us_to_beat(timing, self.duration_us());
+
+        // (start beat, signature) of each BPM segment
+        let mut segments: Vec<(f64, u8)> = timing
+            .iter()
+            .filter(|tp| !tp.is_inherited && tp.bpm > 0.0)
+            .map(|tp| (us_to_beat(timing, tp.time_us), tp.signature))
+            .collect();
+        if segments.is_empty() {
+            segments.push((0.0, DEFAULT_SIGNATURE));
+        }
+
+        let has_notes = !self.notes.is_empty();
+        let mut segment = 0;
+        let mut beat = segments[0].0;
+
+        std::iter::from_fn(move || {
+            if !has_notes || beat >= end_beat - BEAT_EPSILON {
+                return None;
+            }
+
+            // Move to the segment containing `beat`
+            while segments
+                .get(segment + 1)
+                .is_some_and(|&(start, _)| start <= beat + BEAT_EPSILON)
+            {
+                segment += 1;
+            }
+
+            let (_, signature) = segments[segment];
+            let signature = if signature == 0 {
+                DEFAULT_SIGNATURE
+            } else {
+                signature
+            };
+            let mut end = beat + f64::from(signature);
+            if let Some(&(next_start, _)) = segments.get(segment + 1) {
+                end = end.min(next_start);
+            }
+
+            let measure = Measure {
+                start_us: beat_to_us(timing, beat),
+                end_us: beat_to_us(timing, end),
+                beats: end - beat,
+            };
+            beat = end;
+            Some(measure)
+        })
+    }
+
+    /// Points where the time signature changes, as `(time_us, signature)`.
+    ///
+    /// The first BPM timing point is always included; later ones only when
+    /// their signature differs from the previous one. Inherited (SV) points
+    /// are ignored and a signature of 0 is read as 4, like in
+    /// [`measures`](Self::measures).
+    #[must_use]
+    pub fn signature_changes(&self) -> Vec<(i64, u8)> {
+        let mut changes: Vec<(i64, u8)> = Vec::new();
+        for tp in self.timing_points.iter().filter(|tp| !tp.is_inherited) {
+            let signature = if tp.signature == 0 {
+                DEFAULT_SIGNATURE
+            } else {
+                tp.signature
+            };
+            if changes.last().is_none_or(|&(_, last)| last != signature) {
+                changes.push((tp.time_us, signature));
+            }
+        }
+        changes
+    }
+
+    /// Rewrite every timing point to 4/4, for engines without odd meters.
+    ///
+    /// Only `signature` changes: note and timing point times are untouched,
+    /// and [`measures`](Self::measures) now yields 4-beat measures (cut short
+    /// only where a BPM point lands mid-measure).
+    pub fn rebar_to_4_4(&mut self) {
+        for tp in &mut self.timing_points {
+            tp.signature = DEFAULT_SIGNATURE;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model::{Note, TimingPoint};
+
+    #[test]
+    fn test_measures_three_four() {
+        let mut chart = RoxChart::new(4);
+        // 120 BPM in 3/4: one beat = 500ms, one measure = 1.5s
+        let mut tp = TimingPoint::bpm(0, 120.0);
+        tp.signature = 3;
+        chart.timing_points.push(tp);
+        chart.notes.push(Note::tap(4_000_000, 0));
+
+        let measures: Vec<Measure> = chart.measures().collect();
+        assert_eq!(measures.len(), 3);
+        assert_eq!(measures[0].beats, 3.0);
+        assert_eq!(measures[0].start_us, 0);
+        assert_eq!(measures[0].end_us, 1_500_000);
+        assert_eq!(measures[2].end_us, 4_500_000);
+    }
+
+    #[test]
+    fn test_measures_cut_by_timing_point() {
+        let mut chart = RoxChart::new(4);
+        chart.timing_points.push(TimingPoint::bpm(0, 120.0));
+        // New section in 7/8-style 7 beats, starting mid-measure at beat 6
+        let mut tp = TimingPoint::bpm(3_000_000, 120.0);
+        tp.signature = 7;
+        chart.timing_points.push(tp);
+        chart.notes.push(Note::tap(5_000_000, 0));
+
+        let beats: Vec<f64> = chart.measures().map(|m| m.beats).collect();
+        assert_eq!(beats, vec![4.0, 2.0, 7.0]);
+    }
+
+    #[test]
+    fn test_measures_empty_chart() {
+        let mut chart = RoxChart::new(4);
+        chart.timing_points.push(TimingPoint::bpm(0, 120.0));
+        assert_eq!(chart.measures().count(), 0);
+    }
+
+    #[test]
+    fn test_rebar_to_4_4() {
+        let mut chart = RoxChart::new(4);
+        let mut tp = TimingPoint::bpm(0, 120.0);
+        tp.signature = 3;
+        chart.timing_points.push(tp);
+        chart.timing_points.push(TimingPoint::sv(1_000_000, 0.5));
+        for i in 0..9 {
+            chart.notes.push(Note::tap(i * 500_000, (i % 4) as u8));
+        }
+        let original = chart.clone();
+
+        chart.rebar_to_4_4();
+
+        assert_eq!(chart.notes, original.notes);
+        assert!(chart.timing_points.iter().all(|tp| tp.signature == 4));
+        let times = |c: &RoxChart| {
+            c.timing_points
+                .iter()
+                .map(|tp| tp.time_us)
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(times(&chart), times(&original));
+        // 4s at 120 BPM: two 2s measures instead of 1.5s ones
+        let ends: Vec<i64> = chart.measures().map(|m| m.end_us).collect();
+        assert_eq!(ends, vec![2_000_000, 4_000_000]);
+    }
+
+    #[test]
+    fn test_signature_changes() {
+        let mut chart = RoxChart::new(4);
+        chart.timing_points.push(TimingPoint::bpm(0, 120.0));
+        chart.timing_points.push(TimingPoint::bpm(2_000_000, 140.0));
+        chart.timing_points.push(TimingPoint::sv(3_000_000, 1.5));
+        let mut tp = TimingPoint::bpm(4_000_000, 140.0);
+        tp.signature = 3;
+        chart.timing_points.push(tp);
+
+        assert_eq!(chart.signature_changes(), vec![(0, 4), (4_000_000, 3)]);
+        assert!(RoxChart::new(4).signature_changes().is_empty());
+    }
+}
@@ -1,9 +1,39 @@
 //! Chart metadata (title, artist, etc.)
 
+use std::borrow::Cow;
+
 use compact_str::CompactString;
 use rkyv::{Archive, Deserialize, Serialize};
 use serde::{Deserialize as SerdeDeserialize, Serialize as SerdeSerialize};
 
+use super::DisplayBpm;
+
+/// Direction notes scroll in.
+///
+/// Timing and keysounds are the same either way; only the playfield is
+/// flipped. None of the supported text formats store it (it is a player
+/// setting in those games), so it survives only through ROX, JROX and YROX.
+#[derive(
+    Debug,
+    Clone,
+    Copy,
+    Default,
+    PartialEq,
+    Eq,
+    Archive,
+    Serialize,
+    Deserialize,
+    SerdeSerialize,
+    SerdeDeserialize,
+)]
+pub enum ScrollDirection {
+    /// Notes fall towards a receptor line at the bottom.
+    #[default]
+    Down,
+    /// Notes rise towards a receptor line at the top.
+    Up,
+}
+
 /// Metadata describing the chart and associated media.
 #[derive(
     Debug, Clone, PartialEq, Archive, Serialize, Deserialize, SerdeSerialize, SerdeDeserialize,
@@ -23,12 +53,36 @@ pub struct Metadata {
     pub title: CompactString,
     /// Song artist.
     pub artist: CompactString,
+    /// Romanized (ASCII) title, when `title` is in another script.
+    #[serde(default)]
+    pub title_romanized: Option<CompactString>,
+    /// Romanized (ASCII) artist, when `artist` is in another script.
+    #[serde(default)]
+    pub artist_romanized: Option<CompactString>,
     /// Chart creator/mapper.
     pub creator: CompactString,
     /// Difficulty name (e.g., "Hard", "Expert").
     pub difficulty_name: CompactString,
     /// Optional numeric difficulty value (format-dependent).
     pub difficulty_value: Option<f32>,
+    /// Optional HP drain rate (osu! `HPDrainRate`).
+    #[serde(default)]
+    pub hp_drain_rate: Option<f32>,
+    /// Optional overall difficulty / judgement strictness (osu! `OverallDifficulty`).
+    #[serde(default)]
+    pub overall_difficulty: Option<f32>,
+    /// Optional osu! `ApproachRate`.
+    #[serde(default)]
+    pub approach_rate: Option<f32>,
+    /// Optional osu! `Countdown` (0 = none, 1 = normal, 2 = half, 3 = double).
+    #[serde(default)]
+    pub countdown: Option<u8>,
+    /// Optional osu! default `SampleSet` (`Normal`, `Soft`, `Drum`).
+    #[serde(default)]
+    pub sample_set: Option<CompactString>,
+    /// Optional osu! `StackLeniency`.
+    #[serde(default)]
+    pub stack_leniency: Option<f32>,
 
     // Media files
     /// Relative path to the audio file.
@@ -43,6 +97,10 @@ pub struct Metadata {
     pub preview_time_us: i64,
     /// Preview duration in microseconds.
     pub preview_duration_us: i64,
+    /// BPM the chart declares for song select, when it differs from what
+    /// its timing points would show (`StepMania` `#DISPLAYBPM`).
+    #[serde(default)]
+    pub display_bpm: Option<DisplayBpm>,
 
     // Additional info
     /// Source (anime, game, original, etc.)
@@ -53,12 +111,28 @@ pub struct Metadata {
     pub language: Option<CompactString>,
     /// Tags for search/categorization.
     pub tags: Vec<CompactString>,
+    /// Editor bookmarks in microseconds (e.g. osu! `[Editor] Bookmarks`).
+    #[serde(default)]
+    pub bookmarks_us: Vec<i64>,
 
     // Coop/multiplayer info
     /// Whether this chart is designed for 2-player coop mode.
-    /// When true, columns are split evenly: P1 = `0..key_count/2`, P2 = `key_count/2..key_count`.
+    /// Columns are split at [`coop_split`](Self::coop_split): P1 = `0..split`, P2 = `split..key_count`.
     /// Examples: 8K with `is_coop=true` → 4K+4K, 16K with `is_coop=true` → 8K+8K.
     pub is_coop: bool,
+    /// First column of the second player in coop mode.
+    /// `None` means an even split at `key_count/2`; set it for asymmetric
+    /// layouts such as 7K = 4K+3K (`Some(4)`).
+    #[serde(default)]
+    pub coop_split: Option<u8>,
+    /// The first column is a special (scratch) lane, as in osu!mania
+    /// `SpecialStyle` 7K+1 maps. Only the layout changes: `key_count`
+    /// includes the lane and notes in it use column 0.
+    #[serde(default)]
+    pub special_style: bool,
+    /// Direction notes scroll in.
+    #[serde(default)]
+    pub scroll_direction: ScrollDirection,
 }
 
 impl Default for Metadata {
@@ -69,23 +143,109 @@ impl Default for Metadata {
             key_count: 4,
             title: CompactString::new(""),
             artist: CompactString::new(""),
+            title_romanized: None,
+            artist_romanized: None,
             creator: CompactString::new(""),
             difficulty_name: CompactString::from("Normal"),
             difficulty_value: None,
+            hp_drain_rate: None,
+            overall_difficulty: None,
+            approach_rate: None,
+            countdown: None,
+            sample_set: None,
+            stack_leniency: None,
             audio_file: CompactString::new(""),
             background_file: None,
             audio_offset_us: 0,
             preview_time_us: 0,
             preview_duration_us: 15_000_000, // 15 seconds default
+            display_bpm: None,
             source: None,
             genre: None,
             language: None,
             tags: Vec::new(),
+            bookmarks_us: Vec::new(),
             is_coop: false,
+            coop_split: None,
+            special_style: false,
+            scroll_direction: ScrollDirection::Down,
         }
     }
 }
 
+/// Longest string field kept by [`Metadata::sanitize_strings`], in characters.
+pub const MAX_METADATA_STRING_CHARS: usize = 1024;
+
+impl Metadata {
+    /// Strip control characters (newlines, tabs, NUL, ...) from every string
+    /// field and cut each one to [`MAX_METADATA_STRING_CHARS`] characters.
+    ///
+    /// Decoders copy strings from files as-is; a title with a newline would
+    /// otherwise split a line in line-based output such as `.osu` or `.sm`.
+    pub fn sanitize_strings(&mut self) {
+        for s in [
+            &mut self.title,
+            &mut self.artist,
+            &mut self.creator,
+            &mut self.difficulty_name,
+            &mut self.audio_file,
+        ]
+        .into_iter()
+        .chain(self.title_romanized.as_mut())
+        .chain(self.artist_romanized.as_mut())
+        .chain(self.background_file.as_mut())
+        .chain(self.source.as_mut())
+        .chain(self.genre.as_mut())
+        .chain(self.language.as_mut())
+        .chain(self.sample_set.as_mut())
+        .chain(self.tags.iter_mut())
+        {
+            if !is_clean(s) {
+                *s = s
+                    .chars()
+                    .filter(|c| !c.is_control())
+                    .take(MAX_METADATA_STRING_CHARS)
+                    .collect();
+            }
+        }
+    }
+
+    /// A copy with [`sanitize_strings`](Self::sanitize_strings) applied,
+    /// borrowed when every string is already clean.
+    #[must_use]
+    pub fn sanitized(&self) -> Cow<'_, Self> {
+        let clean = [
+            &self.title,
+            &self.artist,
+            &self.creator,
+            &self.difficulty_name,
+            &self.audio_file,
+        ]
+        .into_iter()
+        .chain(self.title_romanized.as_ref())
+        .chain(self.artist_romanized.as_ref())
+        .chain(self.background_file.as_ref())
+        .chain(self.source.as_ref())
+        .chain(self.genre.as_ref())
+        .chain(self.language.as_ref())
+        .chain(self.sample_set.as_ref())
+        .chain(self.tags.iter())
+        .all(|s| is_clean(s));
+
+        if clean {
+            Cow::Borrowed(self)
+        } else {
+            let mut meta = self.clone();
+            meta.sanitize_strings();
+            Cow::Owned(meta)
+        }
+    }
+}
+
+fn is_clean(s: &str) -> bool {
+    !s.chars().any(char::is_control) && s.chars().nth(MAX_METADATA_STRING_CHARS).is_none()
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -96,17 +256,48 @@ mod tests {
 
         assert!(meta.title.is_empty());
         assert!(meta.artist.is_empty());
+        assert!(meta.title_romanized.is_none());
+        assert!(meta.artist_romanized.is_none());
         assert!(meta.creator.is_empty());
         assert_eq!(meta.difficulty_name, "Normal");
         assert!(meta.difficulty_value.is_none());
+        assert!(meta.hp_drain_rate.is_none());
+        assert!(meta.overall_difficulty.is_none());
+        assert!(meta.approach_rate.is_none());
+        assert!(meta.countdown.is_none());
+        assert!(meta.sample_set.is_none());
+        assert!(meta.stack_leniency.is_none());
         assert!(meta.audio_file.is_empty());
         assert!(meta.background_file.is_none());
         assert_eq!(meta.audio_offset_us, 0);
         assert_eq!(meta.preview_time_us, 0);
         assert_eq!(meta.preview_duration_us, 15_000_000); // 15 seconds
+        assert!(meta.coop_split.is_none());
+        assert!(!meta.special_style);
+        assert_eq!(meta.scroll_direction, ScrollDirection::Down);
         assert!(meta.source.is_none());
         assert!(meta.genre.is_none());
         assert!(meta.language.is_none());
         assert!(meta.tags.is_empty());
+        assert!(meta.bookmarks_us.is_empty());
+    }
+
+    #[test]
+    fn test_sanitize_strings() {
+        let mut meta = Metadata {
+            title: "Line\nBreak\t".into(),
+            artist: "a".repeat(MAX_METADATA_STRING_CHARS + 10).into(),
+            source: Some("\0Source".into()),
+            tags: vec!["ok".into(), "bad\r\n".into()],
+            ..Metadata::default()
+        };
+        assert!(matches!(meta.sanitized(), Cow::Owned(_)));
+
+        meta.sanitize_strings();
+        assert_eq!(meta.title, "LineBreak");
+        assert_eq!(meta.artist.chars().count(), MAX_METADATA_STRING_CHARS);
+        assert_eq!(meta.source.as_deref(), Some("Source"));
+        assert_eq!(meta.tags, vec!["ok", "bad"]);
+        assert!(matches!(meta.sanitized(), Cow::Borrowed(_)));
     }
 }
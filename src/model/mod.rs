@@ -1,13 +1,33 @@
 //! Data model for ROX format.
 
+mod bake;
+mod beat;
+mod builder;
 mod chart;
+mod coop;
+mod counts;
+mod filter;
 mod hitsound;
+mod input;
+mod jitter;
+mod measure;
 mod metadata;
 mod note;
+mod samples;
+mod shuffle;
+mod simplify;
+pub mod time;
 mod timing;
 
-pub use chart::{ROX_MAGIC, ROX_VERSION, RoxChart};
+pub(crate) use beat::{beat_to_us, us_to_beat};
+pub use builder::RoxChartBuilder;
+#[cfg(feature = "compression")]
+pub(crate) use chart::ArchivedRoxChart;
+pub use chart::{MAX_KEY_COUNT, ROX_MAGIC, ROX_VERSION, RoxChart};
+pub use counts::NoteCounts;
 pub use hitsound::Hitsound;
-pub use metadata::Metadata;
+pub use input::InputEvent;
+pub use measure::Measure;
+pub use metadata::{MAX_METADATA_STRING_CHARS, Metadata, ScrollDirection};
 pub use note::{Note, NoteType};
-pub use timing::TimingPoint;
+pub use timing::{DEFAULT_BPM, DISPLAY_BPM_TOLERANCE, DisplayBpm, TimingPoint};
@@ -41,6 +41,22 @@ pub struct Note {
     pub hitsound_index: Option<u16>,
     /// Column index (0-indexed).
     pub column: u8,
+    /// Optional index into `RoxChart.hitsounds` played on release.
+    ///
+    /// Only holds use it; other note types ignore it. osu! stores a single
+    /// sample per hold, so the `.osu` codec neither reads nor writes it.
+    #[serde(default)]
+    pub tail_hitsound_index: Option<u16>,
+    /// Strong/accented hit (e.g. a big taiko note). Formats without the
+    /// concept ignore it.
+    #[serde(default)]
+    pub accent: bool,
+    /// Decorative note that is shown but never judged (`StepMania` `F`).
+    ///
+    /// Analysis and validation skip fakes, and encoders for formats without
+    /// them drop fakes rather than writing playable notes.
+    #[serde(default)]
+    pub fake: bool,
 }
 
 impl Note {
@@ -52,6 +68,9 @@ impl Note {
             column,
             note_type: NoteType::Tap,
             hitsound_index: None,
+            tail_hitsound_index: None,
+            accent: false,
+            fake: false,
         }
     }
 
@@ -63,6 +82,9 @@ impl Note {
             column,
             note_type: NoteType::Hold { duration_us },
             hitsound_index: None,
+            tail_hitsound_index: None,
+            accent: false,
+            fake: false,
         }
     }
 
@@ -74,6 +96,9 @@ impl Note {
             column,
             note_type: NoteType::Burst { duration_us },
             hitsound_index: None,
+            tail_hitsound_index: None,
+            accent: false,
+            fake: false,
         }
     }
 
@@ -85,6 +110,9 @@ impl Note {
             column,
             note_type: NoteType::Mine,
             hitsound_index: None,
+            tail_hitsound_index: None,
+            accent: false,
+            fake: false,
         }
     }
 
@@ -120,6 +148,17 @@ impl Note {
     pub fn end_time_us(&self) -> i64 {
         self.time_us + self.duration_us()
     }
+
+    /// Whether both notes play the same: same time, column, type (including
+    /// duration), accent and fake flag. Hitsounds are ignored.
+    #[must_use]
+    pub fn same_gameplay(&self, other: &Note) -> bool {
+        self.time_us == other.time_us
+            && self.column == other.column
+            && self.note_type == other.note_type
+            && self.accent == other.accent
+            && self.fake == other.fake
+    }
 }
 
 #[cfg(test)]
@@ -134,6 +173,9 @@ mod tests {
         assert_eq!(note.column, 2);
         assert!(matches!(note.note_type, NoteType::Tap));
         assert!(note.hitsound_index.is_none());
+        assert!(note.tail_hitsound_index.is_none());
+        assert!(!note.accent);
+        assert!(!note.fake);
     }
 
     #[test]
@@ -173,6 +215,16 @@ mod tests {
         assert!(matches!(note.note_type, NoteType::Mine));
     }
 
+    #[test]
+    fn test_note_same_gameplay() {
+        let mut keysounded = Note::hold(0, 100, 1);
+        keysounded.hitsound_index = Some(3);
+        assert!(keysounded.same_gameplay(&Note::hold(0, 100, 1)));
+        assert!(!keysounded.same_gameplay(&Note::hold(0, 200, 1)));
+        assert!(!keysounded.same_gameplay(&Note::hold(0, 100, 2)));
+        assert!(!Note::tap(0, 0).same_gameplay(&Note::mine(0, 0)));
+    }
+
     #[test]
     fn test_note_is_hold() {
         assert!(!Note::tap(0, 0).is_hold());
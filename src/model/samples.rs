@@ -0,0 +1,115 @@
+//! Keysound sample bookkeeping (which sample files a chart actually needs).
+
+use super::RoxChart;
+
+impl RoxChart {
+    /// Sample files referenced by at least one note, in hitsound table order.
+    ///
+    /// Paths are deduplicated. Use this to know which files to ship with a
+    /// keysounded chart.
+    #[must_use]
+    pub fn sample_manifest(&self) -> Vec<String> {
+        let used = self.used_hitsounds();
+        self.sample_files(|i| used[i])
+    }
+
+    /// Sample files in the hitsound table that no note references.
+    ///
+    /// A path is only reported if no referenced entry shares it, so the
+    /// result is safe to delete from the song folder.
+    #[must_use]
+    pub fn unused_samples(&self) -> Vec<String> {
+        let used = self.used_hitsounds();
+        let manifest = self.sample_files(|i| used[i]);
+        self.sample_files(|i| !used[i])
+            .into_iter()
+            .filter(|file| !manifest.contains(file))
+            .collect()
+    }
+
+    /// Per hitsound entry, whether any note points at it.
+    fn used_hitsounds(&self) -> Vec<bool> {
+        let mut used = vec![false; self.hitsounds.len()];
+        let indices = self.notes.iter().flat_map(|n| {
+            let tail = n.tail_hitsound_index.filter(|_| n.is_hold());
+            n.hitsound_index.into_iter().chain(tail)
+        });
+        for index in indices {
+            if let Some(flag) = used.get_mut(usize::from(index)) {
+                *flag = true;
+            }
+        }
+        used
+    }
+
+    /// Deduplicated file paths of the hitsound entries selected by `keep`.
+    fn sample_files(&self, keep: impl Fn(usize) -> bool) -> Vec<String> {
+        let mut files: Vec<String> = Vec::new();
+        for (i, hitsound) in self.hitsounds.iter().enumerate() {
+            if keep(i) && !files.iter().any(|f| f == hitsound.file.as_str()) {
+                files.push(hitsound.file.to_string());
+            }
+        }
+        files
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::model::{Hitsound, Note, RoxChart};
+
+    #[test]
+    fn test_sample_manifest_and_unused() {
+        let mut chart = RoxChart::new(4);
+        chart.hitsounds.push(Hitsound::new("kick.wav"));
+        chart.hitsounds.push(Hitsound::new("unused.wav"));
+        // Same file at a different volume, referenced once
+        chart.hitsounds.push(Hitsound::with_volume("kick.wav", 50));
+
+        let mut note = Note::tap(0, 0);
+        note.hitsound_index = Some(0);
+        chart.notes.push(note);
+        let mut note = Note::tap(100_000, 1);
+        note.hitsound_index = Some(2);
+        chart.notes.push(note);
+        chart.notes.push(Note::tap(200_000, 2));
+
+        assert_eq!(chart.sample_manifest(), vec!["kick.wav".to_string()]);
+        assert_eq!(chart.unused_samples(), vec!["unused.wav".to_string()]);
+    }
+
+    #[test]
+    fn test_sample_manifest_includes_hold_tails() {
+        let mut chart = RoxChart::new(4);
+        chart.hitsounds.push(Hitsound::new("head.wav"));
+        chart.hitsounds.push(Hitsound::new("tail.wav"));
+        chart.hitsounds.push(Hitsound::new("roll.wav"));
+
+        let mut hold = Note::hold(0, 500_000, 0);
+        hold.hitsound_index = Some(0);
+        hold.tail_hitsound_index = Some(1);
+        chart.notes.push(hold);
+        // Bursts ignore the tail sample
+        let mut burst = Note::burst(0, 500_000, 1);
+        burst.tail_hitsound_index = Some(2);
+        chart.notes.push(burst);
+
+        assert_eq!(
+            chart.sample_manifest(),
+            vec!["head.wav".to_string(), "tail.wav".to_string()]
+        );
+        assert_eq!(chart.unused_samples(), vec!["roll.wav".to_string()]);
+    }
+
+    #[test]
+    fn test_sample_manifest_ignores_dangling_index() {
+        let mut chart = RoxChart::new(4);
+        chart.hitsounds.push(Hitsound::new("a.wav"));
+        let mut note = Note::tap(0, 0);
+        note.hitsound_index = Some(7);
+        chart.notes.push(note);
+
+        assert!(chart.sample_manifest().is_empty());
+        assert_eq!(chart.unused_samples(), vec!["a.wav".to_string()]);
+    }
+}
@@ -0,0 +1,209 @@
+//! Seeded column shuffles ("random" and "S-random" mods).
+//!
+//! The permutations come from `rand`'s `StdRng`, so a seed reproduces the
+//! same chart for a given version of this crate.
+
+use rand::SeedableRng;
+use rand::rngs::StdRng;
+use rand::seq::SliceRandom;
+
+use super::RoxChart;
+
+impl RoxChart {
+    /// Apply one random column permutation, derived from `seed`, to the whole
+    /// chart ("random" mod).
+    ///
+    /// Note times are preserved, and since every column maps to a distinct
+    /// column no new overlaps can appear. Coop charts are shuffled within each
+    /// player's half.
+    pub fn shuffle_columns(&mut self, seed: u64) {
+        let mut rng = StdRng::seed_from_u64(seed);
+        let permutation = self.column_permutation(&mut rng);
+
+        for note in &mut self.notes {
+            if let Some(&column) = permutation.get(note.column as usize) {
+                note.column = column;
+            }
+        }
+    }
+
+    /// Re-permute the columns of every measure with permutations derived from
+    /// `seed` ("S-random" mod).
+    ///
+    /// A hold crossing a measure boundary can land on a column that the next
+    /// measure's permutation also uses. Such a note is moved to the nearest
+    /// free column instead, so a valid chart stays valid. Notes must be sorted
+    /// by time.
+    pub fn shuffle_columns_per_measure(&mut self, seed: u64) {
+        let key_count = self.key_count();
+        let ends: Vec<i64> = self.measures().map(|m| m.end_us).collect();
+        if ends.is_empty() || key_count == 0 {
+            return;
+        }
+
+        let mut rng = StdRng::seed_from_u64(seed);
+        let permutations: Vec<Vec<u8>> = ends
+            .iter()
+            .map(|_| self.column_permutation(&mut rng))
+            .collect();
+
+        let split = self.coop_split();
+        // (start, end) of the last note placed on each column
+        let mut placed = vec![(i64::MIN, i64::MIN); key_count as usize];
+        let mut measure = 0;
+
+        for note in &mut self.notes {
+            if note.column >= key_count {
+                continue;
+            }
+            while measure + 1 < ends.len() && note.time_us >= ends[measure] {
+                measure += 1;
+            }
+
+            let is_free = |column: u8| {
+                let (start, end) = placed[column as usize];
+                note.time_us >= end && note.time_us != start
+            };
+
+            let target = permutations[measure][note.column as usize];
+            let (low, high) = match split {
+                None => (0, key_count),
+                Some(split) if target < split => (0, split),
+                Some(split) => (split, key_count),
+            };
+            // Search outward from the target within the same half
+            let column = (0..high - low)
+                .flat_map(|d| [target.checked_sub(d), target.checked_add(d)])
+                .flatten()
+                .find(|&c| (low..high).contains(&c) && is_free(c))
+                .unwrap_or(target);
+
+            note.column = column;
+            placed[column as usize] = (note.time_us, note.end_time_us());
+        }
+    }
+
+    /// Random permutation of `0..key_count`, keeping each coop half in place.
+    fn column_permutation(&self, rng: &mut StdRng) -> Vec<u8> {
+        let key_count = self.key_count();
+        let mut permutation: Vec<u8> = (0..key_count).collect();
+
+        if let Some(split) = self.coop_split() {
+            let (left, right) = permutation.split_at_mut(split as usize);
+            left.shuffle(rng);
+            right.shuffle(rng);
+        } else {
+            permutation.shuffle(rng);
+        }
+        permutation
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model::{Note, TimingPoint};
+
+    fn stream_chart() -> RoxChart {
+        let mut chart = RoxChart::new(7);
+        chart.timing_points.push(TimingPoint::bpm(0, 120.0));
+        for i in 0..64 {
+            let time = i * 125_000;
+            let column = (i % 7) as u8;
+            if i % 5 == 0 {
+                chart.notes.push(Note::hold(time, 100_000, column));
+            } else {
+                chart.notes.push(Note::tap(time, column));
+            }
+        }
+        chart
+    }
+
+    /// One hold per column crossing every measure boundary (2s at 120 BPM).
+    fn crossing_holds_chart() -> RoxChart {
+        let mut chart = RoxChart::new(4);
+        chart.timing_points.push(TimingPoint::bpm(0, 120.0));
+        for measure in 0..8 {
+            let start = measure * 2_000_000;
+            for column in 0..4 {
+                let time = start + i64::from(column) * 250_000;
+                chart.notes.push(Note::hold(
+                    time,
+                    1_800_000 - i64::from(column) * 100_000,
+                    column,
+                ));
+            }
+        }
+        chart
+    }
+
+    #[test]
+    fn test_shuffle_same_seed_same_output() {
+        let mut a = stream_chart();
+        let mut b = stream_chart();
+        a.shuffle_columns(42);
+        b.shuffle_columns(42);
+        assert_eq!(a, b);
+
+        let mut a = stream_chart();
+        let mut b = stream_chart();
+        a.shuffle_columns_per_measure(42);
+        b.shuffle_columns_per_measure(42);
+        assert_eq!(a, b);
+    }
+
+    #[test]
+    fn test_shuffle_is_a_permutation() {
+        let original = stream_chart();
+        let mut chart = original.clone();
+        chart.shuffle_columns(7);
+
+        assert!(chart.is_valid());
+        // Times are untouched and each source column maps to one target column
+        let mut mapping = [None; 7];
+        for (before, after) in original.notes.iter().zip(&chart.notes) {
+            assert_eq!(before.time_us, after.time_us);
+            let slot = &mut mapping[before.column as usize];
+            assert_eq!(*slot.get_or_insert(after.column), after.column);
+        }
+        let mut targets: Vec<u8> = mapping.iter().flatten().copied().collect();
+        targets.sort_unstable();
+        assert_eq!(targets, (0..7).collect::<Vec<u8>>());
+    }
+
+    #[test]
+    fn test_shuffle_coop_keeps_halves() {
+        let mut chart = RoxChart::new(8);
+        chart.metadata.is_coop = true;
+        for column in 0..8 {
+            chart
+                .notes
+                .push(Note::tap(i64::from(column) * 1000, column));
+        }
+        chart.shuffle_columns(3);
+
+        for (i, note) in chart.notes.iter().enumerate() {
+            assert_eq!(note.column < 4, i < 4);
+        }
+    }
+
+    #[test]
+    fn test_per_measure_resolves_collisions() {
+        for seed in 0..32 {
+            let mut chart = crossing_holds_chart();
+            chart.shuffle_columns_per_measure(seed);
+            assert!(chart.is_valid(), "seed {seed} produced an invalid chart");
+        }
+    }
+
+    #[test]
+    fn test_per_measure_keeps_times() {
+        let original = stream_chart();
+        let mut chart = original.clone();
+        chart.shuffle_columns_per_measure(9);
+
+        assert!(chart.is_valid());
+        let times = |c: &RoxChart| c.notes.iter().map(|n| n.time_us).collect::<Vec<_>>();
+        assert_eq!(times(&chart), times(&original));
+    }
+}
@@ -0,0 +1,148 @@
+//! Removal of redundant timing points, and comparison that ignores them.
+
+use super::RoxChart;
+
+/// Largest drift (µs) tolerated when checking that a repeated BPM point
+/// falls on a bar line of the previous one. osu! stores times in whole ms.
+const BAR_TOLERANCE_US: f64 = 1000.0;
+
+impl RoxChart {
+    /// Remove timing points that change nothing, returning how many were
+    /// removed.
+    ///
+    /// A BPM point is redundant if BPM, signature, kiai and scroll speed all
+    /// match what is already in effect and it lands on a bar line of the
+    /// previous BPM point (otherwise it re-anchors the measures). An SV point
+    /// is redundant if its scroll speed and kiai are already in effect. The
+    /// first point of each distinct value is kept. Timing points must be
+    /// sorted by time.
+    // Exact float comparison on purpose: only identical values are redundant
+    #[allow(clippy::float_cmp)]
+    pub fn simplify_timing(&mut self) -> usize {
+        let before = self.timing_points.len();
+        // (time_us, bpm, signature) of the last kept BPM point
+        let mut anchor: Option<(i64, f32, u8)> = None;
+        let mut scroll_speed = 1.0;
+        let mut kiai = false;
+
+        self.timing_points.retain(|tp| {
+            let same_effects = tp.kiai == kiai && tp.scroll_speed == scroll_speed;
+            let redundant = if tp.is_inherited {
+                same_effects
+            } else {
+                same_effects
+                    && anchor.is_some_and(|(time_us, bpm, signature)| {
+                        bpm == tp.bpm
+                            && signature == tp.signature
+                            && on_bar_line(tp.time_us - time_us, bpm, signature)
+                    })
+            };
+
+            if !redundant {
+                if !tp.is_inherited {
+                    anchor = Some((tp.time_us, tp.bpm, tp.signature));
+                }
+                scroll_speed = tp.scroll_speed;
+                kiai = tp.kiai;
+            }
+            !redundant
+        });
+
+        before - self.timing_points.len()
+    }
+
+    /// Whether two charts are the same once redundant timing points are
+    /// dropped and notes and timing points are put in order.
+    ///
+    /// Unlike `==`, a chart with a repeated 120 BPM point equals the one
+    /// without it, and notes that share a time may be listed in any column
+    /// order. Everything else, metadata and hitsounds included, must match.
+    /// Both charts are cloned to be normalized.
+    #[must_use]
+    pub fn semantically_eq(&self, other: &RoxChart) -> bool {
+        if self.notes.len() != other.notes.len() {
+            return false;
+        }
+        normalized(self) == normalized(other)
+    }
+}
+
+/// Copy of `chart` with sorted notes and simplified timing.
+fn normalized(chart: &RoxChart) -> RoxChart {
+    let mut chart = chart.clone();
+    chart.timing_points.sort_by_key(|tp| tp.time_us);
+    chart.simplify_timing();
+    chart.notes.sort_by_key(|n| (n.time_us, n.column));
+    chart
+}
+
+/// Whether `offset_us` after a BPM point is a whole number of measures.
+fn on_bar_line(offset_us: i64, bpm: f32, signature: u8) -> bool {
+    if bpm <= 0.0 {
+        return false;
+    }
+    let measure_us = 60_000_000.0 / f64::from(bpm) * f64::from(signature.max(1));
+    #[allow(clippy::cast_precision_loss)]
+    let offset_us = offset_us as f64;
+    let measures = (offset_us / measure_us).round();
+    (offset_us - measures * measure_us).abs() < BAR_TOLERANCE_US
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::model::{Note, RoxChart, TimingPoint};
+
+    #[test]
+    fn test_simplify_identical_bpm() {
+        let mut chart = RoxChart::new(4);
+        // 120 BPM 4/4: one measure is 2s
+        chart.timing_points.push(TimingPoint::bpm(0, 120.0));
+        chart.timing_points.push(TimingPoint::bpm(2_000_000, 120.0));
+        chart.timing_points.push(TimingPoint::bpm(8_000_000, 120.0));
+
+        assert_eq!(chart.simplify_timing(), 2);
+        assert_eq!(chart.timing_points, vec![TimingPoint::bpm(0, 120.0)]);
+        assert_eq!(chart.simplify_timing(), 0);
+    }
+
+    #[test]
+    fn test_simplify_keeps_changes() {
+        let mut chart = RoxChart::new(4);
+        chart.timing_points.push(TimingPoint::bpm(0, 120.0));
+        chart.timing_points.push(TimingPoint::sv(1_000_000, 1.5));
+        chart.timing_points.push(TimingPoint::sv(1_500_000, 1.5));
+        // Resets SV to 1.0, so it stays
+        chart.timing_points.push(TimingPoint::bpm(2_000_000, 120.0));
+        chart.timing_points.push(TimingPoint::sv(2_500_000, 1.0));
+        // Off the bar grid: re-anchors measures
+        chart.timing_points.push(TimingPoint::bpm(3_100_000, 120.0));
+        let mut kiai = TimingPoint::sv(4_000_000, 1.0);
+        kiai.kiai = true;
+        chart.timing_points.push(kiai);
+
+        assert_eq!(chart.simplify_timing(), 2);
+        let times: Vec<i64> = chart.timing_points.iter().map(|tp| tp.time_us).collect();
+        assert_eq!(times, vec![0, 1_000_000, 2_000_000, 3_100_000, 4_000_000]);
+    }
+
+    #[test]
+    fn test_semantically_eq_ignores_redundant_timing() {
+        let mut a = RoxChart::new(4);
+        a.timing_points.push(TimingPoint::bpm(0, 120.0));
+        a.notes.push(Note::tap(500_000, 1));
+        a.notes.push(Note::tap(500_000, 0));
+        a.notes.push(Note::hold(1_000_000, 250_000, 2));
+
+        let mut b = a.clone();
+        b.timing_points.push(TimingPoint::bpm(2_000_000, 120.0));
+        b.notes.swap(0, 1);
+
+        assert_ne!(a, b);
+        assert!(a.semantically_eq(&b));
+        assert!(b.semantically_eq(&a));
+
+        // A real BPM change still counts
+        b.timing_points[1].bpm = 240.0;
+        assert!(!a.semantically_eq(&b));
+    }
+}
@@ -0,0 +1,97 @@
+//! Conversions between microseconds and the units formats use.
+//!
+//! Timestamps in the model are whole microseconds. Going to a coarser unit
+//! rounds to the nearest value, halves away from zero, so 1500µs is 2ms and
+//! -1500µs is -2ms. Plain integer division would truncate towards zero and
+//! shift every exported time up to 1ms early.
+
+const US_PER_MS: i64 = 1_000;
+const US_PER_S: f64 = 1_000_000.0;
+
+/// Convert whole milliseconds to microseconds, saturating on overflow.
+#[must_use]
+pub const fn ms_to_us(ms: i64) -> i64 {
+    ms.saturating_mul(US_PER_MS)
+}
+
+/// Convert fractional milliseconds to microseconds, rounded to the nearest.
+///
+/// Non-finite input saturates like an `as` cast (NaN becomes 0).
+#[must_use]
+#[allow(clippy::cast_possible_truncation)]
+pub fn ms_f64_to_us(ms: f64) -> i64 {
+    (ms * 1_000.0).round() as i64
+}
+
+/// Convert microseconds to milliseconds, rounded to the nearest.
+#[must_use]
+pub const fn us_to_ms(us: i64) -> i64 {
+    let ms = us / US_PER_MS;
+    let rem = us % US_PER_MS;
+    if rem >= US_PER_MS / 2 {
+        ms + 1
+    } else if rem <= -US_PER_MS / 2 {
+        ms - 1
+    } else {
+        ms
+    }
+}
+
+/// Convert microseconds to fractional milliseconds.
+///
+/// A single division, so whole milliseconds stay exact (`1_001_000` is
+/// `1001.0`, not `1000.9999999999999` as going through seconds would give).
+#[must_use]
+#[allow(clippy::cast_precision_loss)]
+pub fn us_to_ms_f64(us: i64) -> f64 {
+    us as f64 / 1_000.0
+}
+
+/// Convert seconds to microseconds, rounded to the nearest.
+///
+/// Non-finite input saturates like an `as` cast (NaN becomes 0).
+#[must_use]
+#[allow(clippy::cast_possible_truncation)]
+pub fn s_to_us(s: f64) -> i64 {
+    (s * US_PER_S).round() as i64
+}
+
+/// Convert microseconds to seconds.
+#[must_use]
+#[allow(clippy::cast_precision_loss)]
+pub fn us_to_s(us: i64) -> f64 {
+    us as f64 / US_PER_S
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_us_to_ms_rounds() {
+        assert_eq!(us_to_ms(1_500), 2);
+        assert_eq!(us_to_ms(1_499), 1);
+        assert_eq!(us_to_ms(999), 1);
+        assert_eq!(us_to_ms(-1_500), -2);
+        assert_eq!(us_to_ms(-1_499), -1);
+        assert_eq!(us_to_ms(i64::MAX), i64::MAX / 1_000 + 1);
+    }
+
+    #[test]
+    fn test_ms_roundtrip() {
+        for ms in [-5_000, -1, 0, 1, 1_234, 600_000] {
+            assert_eq!(us_to_ms(ms_to_us(ms)), ms);
+        }
+        assert_eq!(ms_to_us(i64::MAX), i64::MAX);
+        assert_eq!(ms_f64_to_us(12.3456), 12_346);
+        assert_eq!(us_to_ms_f64(1_001_000).to_string(), "1001");
+        assert_eq!(us_to_ms_f64(1_500), 1.5);
+    }
+
+    #[test]
+    fn test_seconds() {
+        assert_eq!(s_to_us(1.5), 1_500_000);
+        assert_eq!(s_to_us(0.000_000_6), 1);
+        assert!((us_to_s(2_500_000) - 2.5).abs() < f64::EPSILON);
+    }
+}
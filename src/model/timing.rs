@@ -1,8 +1,33 @@
 //! Timing points for BPM and scroll velocity changes.
 
 use rkyv::{Archive, Deserialize, Serialize};
+
+use super::RoxChart;
 use serde::{Deserialize as SerdeDeserialize, Serialize as SerdeSerialize};
 
+/// Tempo assumed when a chart has no BPM of its own.
+///
+/// Decoders can be given another one with
+/// [`DecodeOptions::default_bpm`](field@crate::codec::DecodeOptions::default_bpm).
+pub const DEFAULT_BPM: f32 = 120.0;
+
+/// BPM changes smaller than this still count as one display BPM.
+pub const DISPLAY_BPM_TOLERANCE: f64 = 0.5;
+
+/// BPM shown on a song-select screen, like `StepMania`'s `#DISPLAYBPM`.
+#[derive(
+    Debug, Clone, Copy, PartialEq, Archive, Serialize, Deserialize, SerdeSerialize, SerdeDeserialize,
+)]
+pub enum DisplayBpm {
+    /// One BPM for the whole chart.
+    Single(f64),
+    /// The tempo moves between a lowest and highest BPM.
+    Range(f64, f64),
+    /// A constantly changing random number (`#DISPLAYBPM:*`), used by
+    /// gimmick charts to hide their tempo.
+    Random,
+}
+
 /// A point that defines timing or scroll velocity changes.
 #[derive(
     Debug, Clone, PartialEq, Archive, Serialize, Deserialize, SerdeSerialize, SerdeDeserialize,
@@ -18,6 +43,9 @@ pub struct TimingPoint {
     pub is_inherited: bool,
     /// Scroll velocity multiplier (1.0 = normal speed).
     pub scroll_speed: f32,
+    /// Kiai time (highlight section) is active from this point on.
+    #[serde(default)]
+    pub kiai: bool,
 }
 
 impl TimingPoint {
@@ -30,6 +58,7 @@ impl TimingPoint {
             signature: 4,
             is_inherited: false,
             scroll_speed: 1.0,
+            kiai: false,
         }
     }
 
@@ -42,7 +71,58 @@ impl TimingPoint {
             signature: 4,
             is_inherited: true,
             scroll_speed,
+            kiai: false,
+        }
+    }
+}
+
+impl RoxChart {
+    /// Display BPM from the timing points: the BPM in effect the longest
+    /// when all BPMs are within [`DISPLAY_BPM_TOLERANCE`] of each other, the
+    /// lowest to highest BPM otherwise. Backs `analysis::display_bpm`.
+    ///
+    /// Lives in the model so the SM encoder writes the same `#DISPLAYBPM`
+    /// with or without the `analysis` feature.
+    pub(crate) fn computed_display_bpm(&self) -> DisplayBpm {
+        let mut points: Vec<&TimingPoint> = self
+            .timing_points
+            .iter()
+            .filter(|tp| !tp.is_inherited && tp.bpm.is_finite() && tp.bpm > 0.0)
+            .collect();
+        points.sort_by_key(|tp| tp.time_us);
+        let Some(first) = points.first() else {
+            return DisplayBpm::Single(0.0);
+        };
+
+        let (min, max) = points
+            .iter()
+            .map(|tp| f64::from(tp.bpm))
+            .fold((f64::INFINITY, f64::NEG_INFINITY), |(min, max), bpm| {
+                (min.min(bpm), max.max(bpm))
+            });
+        if max - min > DISPLAY_BPM_TOLERANCE {
+            return DisplayBpm::Range(min, max);
+        }
+
+        // Time each BPM is in effect up to the end of the last note
+        let duration_us = self.duration_us();
+        let mut totals: Vec<(f32, i64)> = Vec::new();
+        for (i, tp) in points.iter().enumerate() {
+            let end = points.get(i + 1).map_or(duration_us, |next| next.time_us);
+            let span = end.min(duration_us) - tp.time_us.max(0);
+            match totals
+                .iter_mut()
+                .find(|(bpm, _)| bpm.to_bits() == tp.bpm.to_bits())
+            {
+                Some((_, total)) => *total += span.max(0),
+                None => totals.push((tp.bpm, span.max(0))),
+            }
         }
+        // Earliest BPM on ties, so the result doesn't depend on hashing
+        let (bpm, _) = totals.iter().fold((first.bpm, 0), |best, &(bpm, total)| {
+            if total > best.1 { (bpm, total) } else { best }
+        });
+        DisplayBpm::Single(f64::from(bpm))
     }
 }
 
@@ -59,6 +139,7 @@ mod tests {
         assert_eq!(tp.signature, 4);
         assert!(!tp.is_inherited);
         assert_eq!(tp.scroll_speed, 1.0);
+        assert!(!tp.kiai);
     }
 
     #[test]